@@ -0,0 +1,315 @@
+//! A common interface for anything which plays Mancala (e.g., search engines,
+//! random players, humans, and external programs), so that any two players
+//! can be matched against each other.
+//!
+//! Agents for human and external players are provided by the
+//! [terminal user interface][crate::ui::terminal], alongside
+//! [`play_match`][crate::ui::play_match], which plays a game between two agents.
+
+use crate::game::{Clock, Game, Mancala, Move, RecordedMove};
+use crate::mcts::Mcts;
+use crate::minimax::{MancalaZobrist, Minimax, Ponder, PonderMode, SearchResult, TimeBudget};
+
+/// What an [`Agent`] decides to do on its turn.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Make a move, along with any annotations (e.g., its expected utility).
+    Move(RecordedMove),
+    /// Take back moves until the agent's previous turn.
+    Undo,
+    /// Replay taken back moves until the agent's next turn.
+    Redo,
+}
+
+impl From<RecordedMove> for Decision {
+    fn from(value: RecordedMove) -> Self {
+        Decision::Move(value)
+    }
+}
+
+impl From<Move> for Decision {
+    fn from(value: Move) -> Self {
+        Decision::Move(value.into())
+    }
+}
+
+/// A player of Mancala games with states of type `T`.
+pub trait Agent<T: Mancala> {
+    /// Decides what to do when it is the agent's turn in the current state of
+    /// the game. The full history of the game is available via the [`Game`].
+    ///
+    /// Any move returned must be valid for the current state.
+    fn choose_move(&mut self, game: &Game<T>) -> Decision;
+
+    /// Same as [`choose_move`][Self::choose_move], but during a timed match,
+    /// where the agent's clock is running. Agents which manage their own time
+    /// should use the clock to decide how long to think. Calls
+    /// [`choose_move`][Self::choose_move] by default.
+    #[allow(unused_variables)]
+    fn choose_move_timed(&mut self, game: &Game<T>, clock: &Clock) -> Decision {
+        self.choose_move(game)
+    }
+
+    /// Called after the opponent makes a move, with the game updated to include
+    /// it. Does nothing by default.
+    #[allow(unused_variables)]
+    fn notify_opponent_move(&mut self, game: &Game<T>, m: &RecordedMove) {}
+
+    /// Returns a description of the agent (e.g., a name or engine
+    /// configuration), used when recording games.
+    fn name(&self) -> String;
+}
+
+/// An [`Agent`] which makes a random valid move on every turn.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct RandomAgent;
+
+impl<T: Mancala> Agent<T> for RandomAgent {
+    fn choose_move(&mut self, game: &Game<T>) -> Decision {
+        let (_, m) = game
+            .state()
+            .make_move_rand()
+            .expect("Cannot make a move for a finished game");
+        m.into()
+    }
+
+    fn name(&self) -> String {
+        "Random".to_string()
+    }
+}
+
+/// Searches with [`Minimax::search_utility`], annotating the move with its
+/// expected utility and search depth. A random move is made if no move is found.
+///
+/// During timed matches, the time for each move is budgeted from the clock by
+/// the instance's [`time_manager`][Minimax::time_manager], instead of using the
+/// maximum search time.
+///
+/// # Panics
+///
+/// Choosing a move panics if the instance does not optimize for the player to
+/// move (see [`MinimaxBuilder::optimize_for`][crate::minimax::MinimaxBuilder::optimize_for]),
+/// since the search would otherwise choose the best move for the opponent.
+impl<T: MancalaZobrist> Agent<T> for Minimax<T> {
+    fn choose_move(&mut self, game: &Game<T>) -> Decision {
+        assert_plays_as(self, game);
+        search_decision(self.search_utility(game.state()), game)
+    }
+
+    fn choose_move_timed(&mut self, game: &Game<T>, clock: &Clock) -> Decision {
+        assert_plays_as(self, game);
+        let budget = clock_budget(self, game, clock);
+        search_decision(self.search_timed(game.state(), budget), game)
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "Minimax (max_depth: {:?}, max_time: {:?}, iterative_deepening: {}, use_t_table: {})",
+            self.max_depth(),
+            self.max_time(),
+            self.iterative_deepening(),
+            self.use_t_table()
+        )
+    }
+}
+
+/// An [`Agent`] which searches with a [`Minimax`] instance, and ponders (see
+/// [`Minimax::ponder`]) on the opponent's time after each of its moves.
+///
+/// # Panics
+///
+/// Choosing a move panics if the instance does not optimize for the player to
+/// move (in the same way as the [`Agent`] implementation for [`Minimax`]).
+#[derive(Debug)]
+pub struct PonderingAgent<T: MancalaZobrist> {
+    engine: Minimax<T>,
+    mode: PonderMode,
+    ponder: Option<Ponder<T>>,
+}
+
+impl<T: MancalaZobrist> PonderingAgent<T> {
+    /// Create a new agent, which ponders using the supplied mode.
+    pub fn new(engine: Minimax<T>, mode: PonderMode) -> Self {
+        Self {
+            engine,
+            mode,
+            ponder: None,
+        }
+    }
+
+    /// Returns the underlying search engine.
+    #[inline]
+    pub fn engine(&self) -> &Minimax<T> {
+        &self.engine
+    }
+
+    /// Stops pondering, if the agent is currently doing so.
+    pub fn stop_pondering(&mut self) {
+        if let Some(p) = self.ponder.take() {
+            p.stop();
+        }
+    }
+}
+
+impl<T: MancalaZobrist + 'static> PonderingAgent<T> {
+    /// Helper function for starting to ponder after the move found by a
+    /// search, and returning it.
+    fn ponder_after(&mut self, result: Option<SearchResult>, game: &Game<T>) -> Decision {
+        if let Some(next) = result
+            .as_ref()
+            .and_then(|r| game.state().make_move(r.found_move).ok())
+        {
+            self.ponder = self.engine.ponder(&next, self.mode);
+        }
+        search_decision(result, game)
+    }
+}
+
+impl<T: MancalaZobrist> Drop for PonderingAgent<T> {
+    fn drop(&mut self) {
+        self.stop_pondering();
+    }
+}
+
+impl<T: MancalaZobrist + 'static> Agent<T> for PonderingAgent<T> {
+    fn choose_move(&mut self, game: &Game<T>) -> Decision {
+        assert_plays_as(&self.engine, game);
+        let result = match self.ponder.take() {
+            Some(p) => self.engine.search_after_ponder(p, game.state()),
+            None => self.engine.search_utility(game.state()),
+        };
+        self.ponder_after(result, game)
+    }
+
+    fn choose_move_timed(&mut self, game: &Game<T>, clock: &Clock) -> Decision {
+        assert_plays_as(&self.engine, game);
+        let budget = clock_budget(&self.engine, game, clock);
+        let result = match self.ponder.take() {
+            Some(p) => self
+                .engine
+                .search_timed_after_ponder(p, game.state(), budget),
+            None => self.engine.search_timed(game.state(), budget),
+        };
+        self.ponder_after(result, game)
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "{} (pondering: {:?})",
+            Agent::<T>::name(&self.engine),
+            self.mode
+        )
+    }
+}
+
+/// Searches with [`Mcts::search_utility_all`], annotating the move with its
+/// mean playout reward. A random move is made if no move is found.
+impl<T: MancalaZobrist> Agent<T> for Mcts<T> {
+    fn choose_move(&mut self, game: &Game<T>) -> Decision {
+        let result = self.search_utility_all(game.state());
+        match result.as_ref().and_then(|r| Some((r, r.best_index()?))) {
+            Some((r, i)) => Decision::Move(RecordedMove {
+                selection: r.found_moves[i],
+                eval: Some(r.utilities[i]),
+                depth: None,
+                comment: None,
+            }),
+            None => random_fallback(game),
+        }
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "MCTS (exploration: {}, max_iterations: {:?}, max_time: {:?})",
+            self.exploration(),
+            self.max_iterations(),
+            self.max_time()
+        )
+    }
+}
+
+/// Helper function for checking that a [`Minimax`] instance optimizes for the
+/// player to move.
+fn assert_plays_as<T: MancalaZobrist>(engine: &Minimax<T>, game: &Game<T>) {
+    assert_eq!(
+        engine.optimize_for(),
+        game.state().current_turn(),
+        "the Minimax instance must optimize for the player it plays as"
+    );
+}
+
+/// Helper function for computing the time budget for a move from the clock of
+/// the player to move.
+fn clock_budget<T: MancalaZobrist>(
+    engine: &Minimax<T>,
+    game: &Game<T>,
+    clock: &Clock,
+) -> TimeBudget {
+    let remaining = clock.remaining(game.state().current_turn());
+    engine
+        .time_manager()
+        .budget(remaining, clock.control().increment(), game.position())
+}
+
+/// Helper function for making the move found by a search, annotated with its
+/// expected utility and search depth, or a random move if none was found.
+fn search_decision<T: Mancala>(result: Option<SearchResult>, game: &Game<T>) -> Decision {
+    match result {
+        Some(r) => Decision::Move(RecordedMove {
+            selection: r.found_move,
+            eval: Some(r.utility),
+            depth: r.depth_searched,
+            comment: None,
+        }),
+        None => random_fallback(game),
+    }
+}
+
+/// Helper function for making a random move (annotated as such) when a search
+/// does not find a move.
+fn random_fallback<T: Mancala>(game: &Game<T>) -> Decision {
+    let (_, selection) = game
+        .state()
+        .make_move_rand()
+        .expect("Cannot make a move for a finished game");
+    Decision::Move(RecordedMove {
+        selection,
+        eval: None,
+        depth: None,
+        comment: Some("Random".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Player};
+    use crate::minimax::MinimaxBuilder;
+
+    #[test]
+    fn minimax_plays_for_optimized_player() {
+        let mut engine = MinimaxBuilder::default()
+            .optimize_for(Player::Two)
+            .max_depth(Some(2))
+            .build();
+        let game = Game::new(GameState::<6>::default().make_move_pit(1).unwrap());
+        assert!(matches!(engine.choose_move(&game), Decision::Move(_)));
+    }
+
+    #[test]
+    #[should_panic(expected = "must optimize for the player it plays as")]
+    fn minimax_rejects_other_player() {
+        let mut engine = MinimaxBuilder::default().max_depth(Some(2)).build();
+        let game = Game::new(GameState::<6>::default().make_move_pit(1).unwrap());
+        engine.choose_move(&game);
+    }
+
+    #[test]
+    #[should_panic(expected = "must optimize for the player it plays as")]
+    fn pondering_agent_rejects_other_player() {
+        let engine = MinimaxBuilder::default().max_depth(Some(2)).build();
+        let mut agent = PonderingAgent::new(engine, PonderMode::AllReplies);
+        let game = Game::new(GameState::<6>::default().make_move_pit(1).unwrap());
+        agent.choose_move(&game);
+    }
+}
@@ -1,10 +1,24 @@
 //! Components of the Mancala gameplay system.
 
+pub mod clock;
 mod common;
 pub mod dyn_game_state;
+pub mod error;
 pub mod game_state;
 pub mod mancala;
+pub mod notation;
+pub mod record;
+pub mod rules;
+pub mod session;
+pub mod undo;
 
+pub use clock::{Clock, TimeControl};
 pub use dyn_game_state::DynGameState;
+pub use error::{MoveError, NotationError, RecordError, StateError};
 pub use game_state::GameState;
 pub use mancala::{GameOutcome, Mancala, Move, Player};
+pub use notation::Notation;
+pub use record::{GameRecord, RecordedMove};
+pub use rules::{Editor, GrandSlam, Kalah, KalahOptions, Landing, Oware, Relay, RuleSet, Sweep};
+pub use session::{Game, GameStats};
+pub use undo::{Change, Journal, Undo};
@@ -0,0 +1,209 @@
+//! Game clocks for timed matches.
+
+use super::mancala::Player;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// The time allowed for each player during a game.
+///
+/// Each player starts with the base time, and the increment is added to their
+/// clock after each of their moves (i.e., a "Fischer" increment). A time
+/// control without an increment is "sudden death".
+///
+/// Displayed in the format used by the `TimeControl` tag of game records (the
+/// base time in seconds, followed by `+` and the increment in seconds, if any),
+/// e.g., `300+5`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeControl {
+    base: Duration,
+    increment: Duration,
+}
+
+impl TimeControl {
+    /// Create a time control where each player has the supplied time for the
+    /// whole game.
+    #[inline]
+    pub fn sudden_death(base: Duration) -> Self {
+        Self::with_increment(base, Duration::ZERO)
+    }
+
+    /// Create a time control where each player starts with the base time, and
+    /// gains the increment after each of their moves.
+    #[inline]
+    pub fn with_increment(base: Duration, increment: Duration) -> Self {
+        Self { base, increment }
+    }
+
+    /// Returns the time each player starts with.
+    #[inline]
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    /// Returns the time added to a player's clock after each of their moves.
+    #[inline]
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /// Returns whether the time control has no increment.
+    #[inline]
+    pub fn is_sudden_death(&self) -> bool {
+        self.increment.is_zero()
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.base.as_secs_f64())?;
+        if !self.is_sudden_death() {
+            write!(f, "+{}", self.increment.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+/// A clock for both players, following a [`TimeControl`].
+///
+/// At most one player's clock runs at a time. A player whose time runs out
+/// while their clock is running has "flagged", and loses the game (see
+/// [`GameOutcome::TimeForfeit`][super::GameOutcome::TimeForfeit]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    flagged: [bool; 2],
+    running: Option<(Player, Instant)>,
+}
+
+impl Clock {
+    /// Create a new clock, where both players have the base time of the time
+    /// control, and neither clock is running.
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            remaining: [control.base; 2],
+            flagged: [false; 2],
+            running: None,
+        }
+    }
+
+    /// Returns the time control followed by the clock.
+    #[inline]
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Returns the player whose clock is running, if any.
+    #[inline]
+    pub fn running(&self) -> Option<Player> {
+        self.running.map(|(p, _)| p)
+    }
+
+    /// Returns the time remaining for the supplied player, including the time
+    /// elapsed so far if their clock is running.
+    pub fn remaining(&self, player: Player) -> Duration {
+        match self.running {
+            Some((p, start)) if p == player => {
+                self.remaining[player].saturating_sub(start.elapsed())
+            }
+            _ => self.remaining[player],
+        }
+    }
+
+    /// Returns whether the supplied player has run out of time. A running
+    /// clock is only checked once it is stopped.
+    #[inline]
+    pub fn is_flagged(&self, player: Player) -> bool {
+        self.flagged[player]
+    }
+
+    /// Starts the clock of the supplied player, stopping the other player's
+    /// clock (without an increment) if it is running.
+    pub fn start(&mut self, player: Player) {
+        self.pause();
+        self.running = Some((player, Instant::now()));
+    }
+
+    /// Stops the running clock after a move, charging the time elapsed to the
+    /// player, and adding the increment if they have not run out of time.
+    ///
+    /// Returns the time elapsed, or [`None`] if no clock was running.
+    pub fn press(&mut self) -> Option<Duration> {
+        let player = self.running()?;
+        let elapsed = self.pause();
+        if !self.flagged[player] {
+            self.remaining[player] += self.control.increment;
+        }
+        elapsed
+    }
+
+    /// Stops the running clock without a move (e.g., when a move is taken
+    /// back), charging the time elapsed to the player, without an increment.
+    ///
+    /// Returns the time elapsed, or [`None`] if no clock was running.
+    pub fn pause(&mut self) -> Option<Duration> {
+        let (player, start) = self.running.take()?;
+        let elapsed = start.elapsed();
+        if elapsed > self.remaining[player] {
+            self.flagged[player] = true;
+        }
+        self.remaining[player] = self.remaining[player].saturating_sub(elapsed);
+        Some(elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn time_control_display() {
+        let control = TimeControl::with_increment(Duration::from_secs(300), Duration::from_secs(5));
+        assert_eq!(control.to_string(), "300+5");
+        assert!(!control.is_sudden_death());
+        let control = TimeControl::sudden_death(1500 * MS);
+        assert_eq!(control.to_string(), "1.5");
+        assert!(control.is_sudden_death());
+    }
+
+    #[test]
+    fn increment() {
+        let mut clock = Clock::new(TimeControl::with_increment(1000 * MS, 100 * MS));
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.press(), None);
+
+        clock.start(Player::One);
+        assert_eq!(clock.running(), Some(Player::One));
+        let elapsed = clock.press().unwrap();
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.remaining(Player::One), 1100 * MS - elapsed);
+        assert_eq!(clock.remaining(Player::Two), 1000 * MS);
+
+        // Starting the other player's clock pauses the first without an increment.
+        clock.start(Player::Two);
+        clock.start(Player::One);
+        assert!(clock.remaining(Player::Two) <= 1000 * MS);
+        assert!(!clock.is_flagged(Player::Two));
+    }
+
+    #[test]
+    fn flag_fall() {
+        let mut clock = Clock::new(TimeControl::with_increment(10 * MS, 1000 * MS));
+        clock.start(Player::One);
+        thread::sleep(30 * MS);
+        assert_eq!(clock.remaining(Player::One), Duration::ZERO);
+
+        // The player is only flagged once their clock stops, and does not gain
+        // the increment.
+        assert!(!clock.is_flagged(Player::One));
+        clock.press();
+        assert!(clock.is_flagged(Player::One));
+        assert!(!clock.is_flagged(Player::Two));
+        assert_eq!(clock.remaining(Player::One), Duration::ZERO);
+    }
+}
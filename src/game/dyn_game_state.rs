@@ -1,11 +1,15 @@
 //! Definitions and implementations for dynamically sized Mancala game states.
 
 use super::common::fmt_common;
+use super::error::{NotationError, StateError};
 use super::game_state::GameState;
 use super::mancala::{Mancala, Player};
+use super::notation;
+use super::rules::{Kalah, RuleSet};
 use crate::minimax::MancalaZobrist;
 use std::fmt::{Display, Formatter};
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 /// Stores the necessary components of a Mancala game, including the board,
 /// each player's store, the current ply, and the player currently allowed to move.
@@ -13,14 +17,18 @@ use std::hash::Hash;
 /// Uses a dynamically sized board for use in scenarios where the desired board size
 /// is not known at compile time.
 ///
+/// The rule set `R` determines how moves are made, and defaults to [`Kalah`].
+///
 /// Implements the [`Mancala`] and [`MancalaZobrist`] traits, and can be converted to
 /// and from [`GameState`] structs.
 ///
 /// If the `serde` feature is enabled, this struct will be serializable and
 /// deserializable.
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct DynGameState {
+pub struct DynGameState<R: RuleSet = Kalah> {
+    #[cfg_attr(feature = "serde", serde(default))]
+    rules: R,
     board: [Vec<usize>; 2],
     stores: [usize; 2],
     ply: usize,
@@ -29,12 +37,41 @@ pub struct DynGameState {
     zobrist_hash: u64,
 }
 
-impl Display for DynGameState {
+impl<R: RuleSet> Display for DynGameState<R> {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         fmt_common(f, self, "Dynamic GameState")
     }
 }
 
+impl<R: RuleSet> PartialEq for DynGameState<R> {
+    /// Compares every component of the states except their Zobrist hashes,
+    /// which depend on the [`ZobristData`][crate::minimax::ZobristData] used
+    /// to compute them (if any).
+    fn eq(&self, other: &Self) -> bool {
+        self.rules == other.rules
+            && self.board == other.board
+            && self.stores == other.stores
+            && self.ply == other.ply
+            && self.current_turn == other.current_turn
+            && self.p2_moved == other.p2_moved
+    }
+}
+
+impl<R: RuleSet> Eq for DynGameState<R> {}
+
+impl<R: RuleSet> Hash for DynGameState<R> {
+    /// Hashes every component of the state except its Zobrist hash, matching
+    /// [`PartialEq`].
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rules.hash(state);
+        self.board.hash(state);
+        self.stores.hash(state);
+        self.ply.hash(state);
+        self.current_turn.hash(state);
+        self.p2_moved.hash(state);
+    }
+}
+
 impl Default for DynGameState {
     /// The default Mancala board state is one in which each player
     /// has 6 pits, each containing 4 stones. The ply is set to 1,
@@ -42,6 +79,7 @@ impl Default for DynGameState {
     /// empty.
     fn default() -> Self {
         Self {
+            rules: Kalah::default(),
             board: [vec![4; 6], vec![4; 6]],
             stores: [0, 0],
             ply: 1,
@@ -52,8 +90,39 @@ impl Default for DynGameState {
     }
 }
 
-impl Mancala for DynGameState {
+impl<R: RuleSet> FromStr for DynGameState<R> {
+    type Err = NotationError;
+
+    /// Parses a state from the compact notation described in [`notation`],
+    /// using the default rule set if the notation does not include one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let p = notation::parse(s)?;
+        if p.board[0].len() != p.board[1].len() {
+            return Err(StateError::MismatchedRows(p.board[0].len(), p.board[1].len()).into());
+        }
+        let [store_1, store_2] = p.stores;
+        let state = DynGameState::from_vec(
+            &p.board,
+            store_1,
+            store_2,
+            p.current_turn,
+            p.ply,
+            p.p2_moved,
+        )
+        .with_rules(p.rules);
+        state.validate()?;
+        Ok(state)
+    }
+}
+
+impl<R: RuleSet> Mancala for DynGameState<R> {
     type Board = Vec<usize>;
+    type Rules = R;
+
+    #[inline]
+    fn rules(&self) -> &Self::Rules {
+        &self.rules
+    }
 
     #[inline]
     fn board(&self) -> &[Self::Board; 2] {
@@ -106,7 +175,7 @@ impl Mancala for DynGameState {
     }
 }
 
-impl MancalaZobrist for DynGameState {
+impl<R: RuleSet> MancalaZobrist for DynGameState<R> {
     #[inline]
     fn zobrist_hash(&self) -> u64 {
         self.zobrist_hash
@@ -118,9 +187,10 @@ impl MancalaZobrist for DynGameState {
     }
 }
 
-impl<const N: usize> From<GameState<N>> for DynGameState {
-    fn from(value: GameState<N>) -> Self {
+impl<const N: usize, R: RuleSet> From<GameState<N, R>> for DynGameState<R> {
+    fn from(value: GameState<N, R>) -> Self {
         Self {
+            rules: *value.rules(),
             board: [value.board()[0].to_vec(), value.board()[1].to_vec()],
             stores: *value.stores(),
             ply: value.ply(),
@@ -132,6 +202,31 @@ impl<const N: usize> From<GameState<N>> for DynGameState {
 }
 
 impl DynGameState {
+    /// Create a new [`DynGameState`] based on a series of parameters used
+    /// to construct a starting game of Mancala, returning a [`StateError`]
+    /// if the resulting state does not pass [`Mancala::validate`].
+    pub fn try_new(
+        pits: usize,
+        stones_per: usize,
+        store_1: usize,
+        store_2: usize,
+        current_turn: Player,
+        ply: usize,
+        p2_moved: bool,
+    ) -> Result<Self, StateError> {
+        let state = Self::new(
+            pits,
+            stones_per,
+            store_1,
+            store_2,
+            current_turn,
+            ply,
+            p2_moved,
+        );
+        state.validate()?;
+        Ok(state)
+    }
+
     /// Create a new [`DynGameState`] based on a series of parameters used
     /// to construct a starting game of Mancala.
     pub fn new(
@@ -144,6 +239,7 @@ impl DynGameState {
         p2_moved: bool,
     ) -> Self {
         Self {
+            rules: Kalah::default(),
             board: [vec![stones_per; pits], vec![stones_per; pits]],
             stores: [store_1, store_2],
             ply,
@@ -157,7 +253,7 @@ impl DynGameState {
     /// [`Vec`] of [`Vec`] structs. The input vector must have an effective shape
     /// of `(2, N)`, where `N` is the number of pits per player.
     pub fn from_vec(
-        board: &Vec<Vec<usize>>,
+        board: &[Vec<usize>],
         store_1: usize,
         store_2: usize,
         current_turn: Player,
@@ -181,6 +277,7 @@ impl DynGameState {
             board[1].len(),
         );
         Self {
+            rules: Kalah::default(),
             board: [board[0].clone(), board[1].clone()],
             stores: [store_1, store_2],
             ply,
@@ -190,6 +287,34 @@ impl DynGameState {
         }
     }
 
+    /// Create a new [`DynGameState`] based on a preexisting board, stored as a
+    /// slice of [`Vec`] structs, returning a [`StateError`] if the board does
+    /// not have an effective shape of `(2, N)`, or if the resulting state does
+    /// not pass [`Mancala::validate`].
+    pub fn try_from_vec(
+        board: &[Vec<usize>],
+        store_1: usize,
+        store_2: usize,
+        current_turn: Player,
+        ply: usize,
+        p2_moved: bool,
+    ) -> Result<Self, StateError> {
+        if board.len() != 2 {
+            return Err(StateError::InvalidRows(board.len()));
+        }
+        let state = Self {
+            rules: Kalah::default(),
+            board: [board[0].clone(), board[1].clone()],
+            stores: [store_1, store_2],
+            ply,
+            current_turn,
+            p2_moved,
+            zobrist_hash: 0,
+        };
+        state.validate()?;
+        Ok(state)
+    }
+
     /// Create a new [`DynGameState`] based on a preexisting board array.
     pub fn from_arr<const N: usize>(
         board: [[usize; N]; 2],
@@ -200,6 +325,7 @@ impl DynGameState {
         p2_moved: bool,
     ) -> Self {
         Self {
+            rules: Kalah::default(),
             board: [board[0].to_vec(), board[1].to_vec()],
             stores: [store_1, store_2],
             ply,
@@ -208,4 +334,35 @@ impl DynGameState {
             zobrist_hash: 0,
         }
     }
+
+    /// Create a new [`DynGameState`] based on a preexisting board array, returning
+    /// a [`StateError`] if the resulting state does not pass [`Mancala::validate`].
+    pub fn try_from_arr<const N: usize>(
+        board: [[usize; N]; 2],
+        store_1: usize,
+        store_2: usize,
+        current_turn: Player,
+        ply: usize,
+        p2_moved: bool,
+    ) -> Result<Self, StateError> {
+        let state = Self::from_arr(board, store_1, store_2, current_turn, ply, p2_moved);
+        state.validate()?;
+        Ok(state)
+    }
+}
+
+impl<R: RuleSet> DynGameState<R> {
+    /// Consume the current state, and return an otherwise identical state
+    /// which uses the supplied rule set for all future moves.
+    pub fn with_rules<S: RuleSet>(self, rules: S) -> DynGameState<S> {
+        DynGameState {
+            rules,
+            board: self.board,
+            stores: self.stores,
+            ply: self.ply,
+            current_turn: self.current_turn,
+            p2_moved: self.p2_moved,
+            zobrist_hash: self.zobrist_hash,
+        }
+    }
 }
@@ -0,0 +1,219 @@
+//! Error types produced during Mancala gameplay.
+
+use super::mancala::{GameOutcome, Move};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Describes why a requested move could not be made.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveError {
+    /// The selected pit does not exist (pits are numbered from 1 to `pits`).
+    PitOutOfRange { pit: usize, pits: usize },
+    /// The selected pit does not contain any stones.
+    EmptyPit(usize),
+    /// The swap move is not currently allowed.
+    SwapNotAllowed,
+    /// The game is already over, so no further moves can be made.
+    GameOver,
+    /// The current player has no valid moves available.
+    NoMovesAvailable,
+    /// The move is otherwise forbidden by the state's rule set
+    /// (e.g., because it does not feed the opponent in Oware).
+    Forbidden(Move),
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            MoveError::PitOutOfRange { pit, pits } => {
+                write!(f, "pit {} is out of range (expected 1 to {})", pit, pits)
+            }
+            MoveError::EmptyPit(pit) => write!(f, "pit {} is empty", pit),
+            MoveError::SwapNotAllowed => write!(f, "the swap move is not allowed"),
+            MoveError::GameOver => write!(f, "the game is already over"),
+            MoveError::NoMovesAvailable => write!(f, "no moves are available"),
+            MoveError::Forbidden(m) => write!(f, "move {:?} is forbidden by the rules", m),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+/// Describes why a game state is invalid.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StateError {
+    /// The board did not have exactly two rows (one per player).
+    InvalidRows(usize),
+    /// The two rows of the board had different numbers of pits.
+    MismatchedRows(usize, usize),
+    /// The rows of the board did not have the expected number of pits.
+    WrongPitCount { expected: usize, found: [usize; 2] },
+    /// The board does not have any pits.
+    NoPits,
+    /// The board and stores do not contain any stones.
+    NoStones,
+    /// The ply must be at least 1.
+    InvalidPly(usize),
+    /// Player 1 always moves first, so Player 2 cannot be the player to move
+    /// at the first ply.
+    InvalidTurn { ply: usize },
+    /// Player 2 is marked as having moved, but the ply is too low for
+    /// that to be possible.
+    InconsistentP2Moved { ply: usize },
+    /// One player has no stones left, but the remaining stones on the
+    /// board have not been captured.
+    Unswept,
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            StateError::InvalidRows(rows) => {
+                write!(f, "board has {} rows (expected 2)", rows)
+            }
+            StateError::MismatchedRows(first, second) => write!(
+                f,
+                "board rows have different lengths ({} and {})",
+                first, second
+            ),
+            StateError::WrongPitCount { expected, found } => write!(
+                f,
+                "board rows have lengths {} and {} (expected {} for both)",
+                found[0], found[1], expected
+            ),
+            StateError::NoPits => write!(f, "board has no pits"),
+            StateError::NoStones => write!(f, "board and stores have no stones"),
+            StateError::InvalidPly(ply) => write!(f, "ply {} is invalid (must be at least 1)", ply),
+            StateError::InvalidTurn { ply } => {
+                write!(f, "player 2 cannot be the player to move at ply {}", ply)
+            }
+            StateError::InconsistentP2Moved { ply } => {
+                write!(f, "player 2 cannot have moved by ply {}", ply)
+            }
+            StateError::Unswept => write!(
+                f,
+                "one player has no stones left, but the board has not been swept"
+            ),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/// Describes why a position could not be parsed from the compact notation
+/// (see [`notation`][super::notation]).
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum NotationError {
+    /// The notation did not contain five or six space-separated fields.
+    WrongFieldCount(usize),
+    /// The board did not contain exactly two `/`-separated rows.
+    InvalidBoard(usize),
+    /// A pit, store, or ply was not a non-negative number.
+    InvalidNumber(String),
+    /// The stores were not written as two `:`-separated numbers.
+    InvalidStores(String),
+    /// The player to move was not `1` or `2`.
+    InvalidTurn(String),
+    /// Whether Player 2 has moved was not `m` or `-`.
+    InvalidP2Moved(String),
+    /// The rule set was not recognized by the rule set type being parsed.
+    InvalidRules(String),
+    /// The notation was well-formed, but described an invalid state.
+    State(StateError),
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            NotationError::WrongFieldCount(n) => write!(f, "found {} fields (expected 5 or 6)", n),
+            NotationError::InvalidBoard(rows) => {
+                write!(f, "board has {} rows (expected 2)", rows)
+            }
+            NotationError::InvalidNumber(s) => write!(f, "invalid number {:?}", s),
+            NotationError::InvalidStores(s) => write!(f, "invalid stores {:?}", s),
+            NotationError::InvalidTurn(s) => write!(f, "invalid player to move {:?}", s),
+            NotationError::InvalidP2Moved(s) => write!(f, "invalid p2_moved flag {:?}", s),
+            NotationError::InvalidRules(s) => write!(f, "invalid rule set {:?}", s),
+            NotationError::State(e) => write!(f, "invalid state: {}", e),
+        }
+    }
+}
+
+impl Error for NotationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NotationError::State(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<StateError> for NotationError {
+    fn from(value: StateError) -> Self {
+        NotationError::State(value)
+    }
+}
+
+/// Describes why a game record could not be parsed or replayed
+/// (see [`record`][super::record]).
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordError {
+    /// A tag line was not of the form `[Name "Value"]`.
+    InvalidTag(String),
+    /// A required tag was not present.
+    MissingTag(&'static str),
+    /// The `Result` tag was not `1-0`, `0-1`, `1/2-1/2`, or `*`.
+    InvalidResult(String),
+    /// A move was not a pit number (starting from 1) or `S`.
+    InvalidMove(String),
+    /// An annotation was malformed, or did not follow a move.
+    InvalidAnnotation(String),
+    /// The initial position could not be parsed.
+    Notation(NotationError),
+    /// The move with the supplied number (starting from 1) could not be made.
+    IllegalMove { number: usize, error: MoveError },
+    /// The recorded result does not match the outcome of the replayed game.
+    ResultMismatch {
+        recorded: GameOutcome,
+        actual: GameOutcome,
+    },
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            RecordError::InvalidTag(s) => write!(f, "invalid tag {:?}", s),
+            RecordError::MissingTag(name) => write!(f, "missing required tag {:?}", name),
+            RecordError::InvalidResult(s) => write!(f, "invalid result {:?}", s),
+            RecordError::InvalidMove(s) => write!(f, "invalid move {:?}", s),
+            RecordError::InvalidAnnotation(s) => write!(f, "invalid annotation {:?}", s),
+            RecordError::Notation(e) => write!(f, "invalid position: {}", e),
+            RecordError::IllegalMove { number, error } => {
+                write!(f, "move {} is illegal: {}", number, error)
+            }
+            RecordError::ResultMismatch { recorded, actual } => write!(
+                f,
+                "recorded result {:?} does not match actual outcome {:?}",
+                recorded, actual
+            ),
+        }
+    }
+}
+
+impl Error for RecordError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RecordError::Notation(e) => Some(e),
+            RecordError::IllegalMove { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<NotationError> for RecordError {
+    fn from(value: NotationError) -> Self {
+        RecordError::Notation(value)
+    }
+}
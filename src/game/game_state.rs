@@ -2,10 +2,13 @@
 
 use super::common::fmt_common;
 use super::dyn_game_state::DynGameState;
+use super::error::{NotationError, StateError};
 use super::mancala::{Mancala, Player};
+use super::rules::{Kalah, RuleSet};
 use crate::minimax::MancalaZobrist;
 use std::fmt::{Display, Formatter};
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 /// Stores the necessary components of a Mancala game, including the board,
 /// each player's store, the current ply, and the player currently allowed to move.
@@ -15,13 +18,17 @@ use std::hash::Hash;
 /// especially in scenarios where repeated state creation / modification is necessary
 /// (i.e., during the execution of the minimax algorithm).
 ///
+/// The rule set `R` determines how moves are made, and defaults to [`Kalah`].
+///
 /// Implements the [`Mancala`] and [`MancalaZobrist`] traits, and can be converted to
-/// and from [`DynGameState`] structs.
+/// and from [`DynGameState`] structs (the latter via [`TryFrom`], since the
+/// dynamic board may not have `N` pits per player).
 ///
 /// If the `serde` feature is enabled, this struct will be serializable and
 /// deserializable, via automatic conversion to and from [`DynGameState`].
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-pub struct GameState<const N: usize> {
+#[derive(Debug, Clone, Copy)]
+pub struct GameState<const N: usize, R: RuleSet = Kalah> {
+    rules: R,
     board: [[usize; N]; 2],
     stores: [usize; 2],
     ply: usize,
@@ -31,7 +38,7 @@ pub struct GameState<const N: usize> {
 }
 
 #[cfg(feature = "serde")]
-impl<const N: usize> serde::Serialize for GameState<N> {
+impl<const N: usize, R: RuleSet + serde::Serialize> serde::Serialize for GameState<N, R> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
@@ -41,22 +48,53 @@ impl<const N: usize> serde::Serialize for GameState<N> {
 }
 
 #[cfg(feature = "serde")]
-impl<'a, const N: usize> serde::Deserialize<'a> for GameState<N> {
+impl<'a, const N: usize, R: RuleSet + serde::Deserialize<'a>> serde::Deserialize<'a>
+    for GameState<N, R>
+{
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'a>,
     {
-        let dyn_state = DynGameState::deserialize(deserializer)?;
-        Ok(GameState::from(dyn_state))
+        let dyn_state = DynGameState::<R>::deserialize(deserializer)?;
+        GameState::try_from(dyn_state).map_err(serde::de::Error::custom)
     }
 }
 
-impl<const N: usize> Display for GameState<N> {
+impl<const N: usize, R: RuleSet> Display for GameState<N, R> {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         fmt_common(f, self, "Static GameState")
     }
 }
 
+impl<const N: usize, R: RuleSet> PartialEq for GameState<N, R> {
+    /// Compares every component of the states except their Zobrist hashes,
+    /// which depend on the [`ZobristData`][crate::minimax::ZobristData] used
+    /// to compute them (if any).
+    fn eq(&self, other: &Self) -> bool {
+        self.rules == other.rules
+            && self.board == other.board
+            && self.stores == other.stores
+            && self.ply == other.ply
+            && self.current_turn == other.current_turn
+            && self.p2_moved == other.p2_moved
+    }
+}
+
+impl<const N: usize, R: RuleSet> Eq for GameState<N, R> {}
+
+impl<const N: usize, R: RuleSet> Hash for GameState<N, R> {
+    /// Hashes every component of the state except its Zobrist hash, matching
+    /// [`PartialEq`].
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rules.hash(state);
+        self.board.hash(state);
+        self.stores.hash(state);
+        self.ply.hash(state);
+        self.current_turn.hash(state);
+        self.p2_moved.hash(state);
+    }
+}
+
 impl Default for GameState<6> {
     /// The default Mancala board state is one in which each player
     /// has 6 pits, each containing 4 stones. The ply is set to 1,
@@ -64,6 +102,7 @@ impl Default for GameState<6> {
     /// empty.
     fn default() -> Self {
         Self {
+            rules: Kalah::default(),
             board: [[4; 6]; 2],
             stores: [0, 0],
             ply: 1,
@@ -74,8 +113,26 @@ impl Default for GameState<6> {
     }
 }
 
-impl<const N: usize> Mancala for GameState<N> {
+impl<const N: usize, R: RuleSet> FromStr for GameState<N, R> {
+    type Err = NotationError;
+
+    /// Parses a state from the compact notation described in
+    /// [`notation`][super::notation], using the default rule set if the
+    /// notation does not include one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dyn_state = DynGameState::<R>::from_str(s)?;
+        Ok(GameState::try_from(dyn_state)?)
+    }
+}
+
+impl<const N: usize, R: RuleSet> Mancala for GameState<N, R> {
     type Board = [usize; N];
+    type Rules = R;
+
+    #[inline]
+    fn rules(&self) -> &Self::Rules {
+        &self.rules
+    }
 
     #[inline]
     fn pits(&self) -> usize {
@@ -133,7 +190,11 @@ impl<const N: usize> Mancala for GameState<N> {
     }
 }
 
-impl<const N: usize> MancalaZobrist for GameState<N> {
+impl<const N: usize, R: RuleSet> MancalaZobrist for GameState<N, R> {
+    // Copying a fixed-size board is cheaper than recording each move and
+    // taking it back.
+    const SEARCH_IN_PLACE: bool = false;
+
     #[inline]
     fn zobrist_hash(&self) -> u64 {
         self.zobrist_hash
@@ -145,42 +206,52 @@ impl<const N: usize> MancalaZobrist for GameState<N> {
     }
 }
 
-impl<const N: usize> From<DynGameState> for GameState<N> {
-    fn from(value: DynGameState) -> Self {
-        assert_eq!(
-            value.board().len(),
-            2,
-            "Failed to convert DynGameState to GameState due to invalid dynamic board \
-            (got Vec of length {}, expected 2)",
-            value.board().len()
-        );
-        assert!(
-            value.board()[0].len() == N && value.board()[1].len() == N,
-            "Failed to convert DynGameState to GameState due to invalid dynamic board \
-            (board[0] had length {}, board[1] had length {}; expected \
-            {} for both)",
-            value.board()[0].len(),
-            value.board()[1].len(),
-            N
-        );
+impl<const N: usize, R: RuleSet> TryFrom<DynGameState<R>> for GameState<N, R> {
+    type Error = StateError;
+
+    /// Converts a [`DynGameState`] into a [`GameState`], failing if the board
+    /// does not have `N` pits per player, or if the state does not pass
+    /// [`Mancala::validate`].
+    fn try_from(value: DynGameState<R>) -> Result<Self, Self::Error> {
+        let found = [value.board()[0].len(), value.board()[1].len()];
+        if found != [N, N] {
+            return Err(StateError::WrongPitCount { expected: N, found });
+        }
         let mut board = [[0; N]; 2];
-        for i in 0..2 {
-            for j in 0..N {
-                board[i][j] = value.board()[i][j];
-            }
+        for (side, values) in board.iter_mut().zip(value.board()) {
+            side.copy_from_slice(values);
         }
-        Self {
+        let state = Self {
+            rules: *value.rules(),
             board,
             stores: *value.stores(),
             ply: value.ply(),
             current_turn: value.current_turn(),
             p2_moved: value.p2_moved(),
             zobrist_hash: value.zobrist_hash(),
-        }
+        };
+        state.validate()?;
+        Ok(state)
     }
 }
 
 impl<const N: usize> GameState<N> {
+    /// Create a new [`GameState`] based on a series of parameters used
+    /// to construct a starting game of Mancala, returning a [`StateError`]
+    /// if the resulting state does not pass [`Mancala::validate`].
+    pub fn try_new(
+        stones_per: usize,
+        store_1: usize,
+        store_2: usize,
+        current_turn: Player,
+        ply: usize,
+        p2_moved: bool,
+    ) -> Result<Self, StateError> {
+        let state = Self::new(stones_per, store_1, store_2, current_turn, ply, p2_moved);
+        state.validate()?;
+        Ok(state)
+    }
+
     /// Create a new [`GameState`] based on a series of parameters used
     /// to construct a starting game of Mancala.
     pub fn new(
@@ -192,6 +263,7 @@ impl<const N: usize> GameState<N> {
         p2_moved: bool,
     ) -> Self {
         Self {
+            rules: Kalah::default(),
             board: [[stones_per; N]; 2],
             stores: [store_1, store_2],
             ply,
@@ -209,7 +281,7 @@ impl<const N: usize> GameState<N> {
     /// the board length `N` must be specified correctly in the generic call to
     /// [`GameState::from_vec`].
     pub fn from_vec(
-        board: &Vec<Vec<usize>>,
+        board: &[Vec<usize>],
         store_1: usize,
         store_2: usize,
         current_turn: Player,
@@ -233,12 +305,11 @@ impl<const N: usize> GameState<N> {
             N
         );
         let mut arr = [[0; N]; 2];
-        for i in 0..2 {
-            for j in 0..N {
-                arr[i][j] = board[i][j];
-            }
+        for (side, values) in arr.iter_mut().zip(board) {
+            side.copy_from_slice(values);
         }
         Self {
+            rules: Kalah::default(),
             board: arr,
             stores: [store_1, store_2],
             ply,
@@ -248,6 +319,30 @@ impl<const N: usize> GameState<N> {
         }
     }
 
+    /// Create a new [`GameState`] based on a preexisting board, stored as a
+    /// slice of [`Vec`] structs, returning a [`StateError`] if the board does
+    /// not have an effective shape of `(2, N)`, or if the resulting state does
+    /// not pass [`Mancala::validate`].
+    pub fn try_from_vec(
+        board: &[Vec<usize>],
+        store_1: usize,
+        store_2: usize,
+        current_turn: Player,
+        ply: usize,
+        p2_moved: bool,
+    ) -> Result<Self, StateError> {
+        if board.len() != 2 {
+            return Err(StateError::InvalidRows(board.len()));
+        }
+        let found = [board[0].len(), board[1].len()];
+        if found != [N, N] {
+            return Err(StateError::WrongPitCount { expected: N, found });
+        }
+        let state = Self::from_vec(board, store_1, store_2, current_turn, ply, p2_moved);
+        state.validate()?;
+        Ok(state)
+    }
+
     /// Create a new [`GameState`] based on a preexisting board array.
     pub fn from_arr(
         board: [[usize; N]; 2],
@@ -258,6 +353,7 @@ impl<const N: usize> GameState<N> {
         p2_moved: bool,
     ) -> Self {
         Self {
+            rules: Kalah::default(),
             board,
             stores: [store_1, store_2],
             ply,
@@ -266,4 +362,35 @@ impl<const N: usize> GameState<N> {
             zobrist_hash: 0,
         }
     }
+
+    /// Create a new [`GameState`] based on a preexisting board array, returning
+    /// a [`StateError`] if the resulting state does not pass [`Mancala::validate`].
+    pub fn try_from_arr(
+        board: [[usize; N]; 2],
+        store_1: usize,
+        store_2: usize,
+        current_turn: Player,
+        ply: usize,
+        p2_moved: bool,
+    ) -> Result<Self, StateError> {
+        let state = Self::from_arr(board, store_1, store_2, current_turn, ply, p2_moved);
+        state.validate()?;
+        Ok(state)
+    }
+}
+
+impl<const N: usize, R: RuleSet> GameState<N, R> {
+    /// Consume the current state, and return an otherwise identical state
+    /// which uses the supplied rule set for all future moves.
+    pub fn with_rules<S: RuleSet>(self, rules: S) -> GameState<N, S> {
+        GameState {
+            rules,
+            board: self.board,
+            stores: self.stores,
+            ply: self.ply,
+            current_turn: self.current_turn,
+            p2_moved: self.p2_moved,
+            zobrist_hash: self.zobrist_hash,
+        }
+    }
 }
@@ -1,5 +1,9 @@
 //! Traits and enums necessary for Mancala gameplay.
 
+use super::error::{MoveError, StateError};
+use super::notation::Notation;
+use super::rules::{Editor, Landing, RuleSet};
+use super::undo::{Journal, Undo};
 use rand::seq::IndexedRandom;
 use std::fmt::Display;
 use std::hash::Hash;
@@ -122,22 +126,43 @@ impl From<usize> for Move {
 /// Used to describe the current outcome of a game state.
 ///
 /// During gameplay, there can either be a winner, a tie, or the game may still be ongoing.
+/// In timed matches, a player may also lose by running out of time, which is
+/// described by [`TimeForfeit`][Self::TimeForfeit] (with the player who ran out).
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameOutcome {
     Winner(Player),
     Tie,
     Ongoing,
+    TimeForfeit(Player),
+}
+
+impl GameOutcome {
+    /// Returns the player who won the game, if any (including by their
+    /// opponent running out of time).
+    #[inline]
+    pub fn winner(&self) -> Option<Player> {
+        match self {
+            GameOutcome::Winner(p) => Some(*p),
+            GameOutcome::TimeForfeit(p) => Some(p.other()),
+            GameOutcome::Tie | GameOutcome::Ongoing => None,
+        }
+    }
 }
 
 /// Provides a default implementation of Mancala gameplay for all implementors,
 /// and specifies certain accessor and mutable reference methods that must be
 /// implemented on a per-type basis (i.e., no default implementation can be provided).
+///
+/// Variant-specific behavior is delegated to the associated [`RuleSet`].
 pub trait Mancala: Clone + Display + Send + Sync + Hash + PartialEq + Eq {
     /// Used to indicate the underlying array-like type used to store
     /// the board contents for each player.
     type Board: AsRef<[usize]> + AsMut<[usize]>;
 
+    /// Used to indicate the rule set which determines how moves are made.
+    type Rules: RuleSet;
+
     /// Converts the current board into an array of two [`Vec`] instances for easy access.
     fn board_as_vecs(&self) -> [Vec<usize>; 2] {
         [
@@ -147,15 +172,9 @@ pub trait Mancala: Clone + Display + Send + Sync + Hash + PartialEq + Eq {
     }
 
     /// Determines whether the game is over.
+    #[inline]
     fn is_over(&self) -> bool {
-        for player in [Player::One, Player::Two] {
-            for pit in self.board()[player].as_ref() {
-                if *pit != 0 {
-                    return false;
-                }
-            }
-        }
-        true
+        self.rules().is_over(self)
     }
 
     /// Gets the current score for a player.
@@ -175,61 +194,73 @@ pub trait Mancala: Clone + Display + Send + Sync + Hash + PartialEq + Eq {
     }
 
     /// Determines the current outcome of the game.
+    #[inline]
     fn outcome(&self) -> GameOutcome {
-        if self.is_over() {
-            if self.score(Player::One) > self.score(Player::Two) {
-                GameOutcome::Winner(Player::One)
-            } else if self.score(Player::Two) > self.score(Player::One) {
-                GameOutcome::Winner(Player::Two)
-            } else {
-                GameOutcome::Tie
-            }
-        } else {
-            GameOutcome::Ongoing
-        }
+        self.rules().outcome(self)
     }
 
     /// Determines whether the swap move is currently allowed.
+    #[inline]
     fn swap_allowed(&self) -> bool {
-        !self.p2_moved() && self.current_turn() == Player::Two
+        self.rules().swap_allowed(self)
     }
 
     /// Returns a vector of moves that are currently valid for the current player.
+    #[inline]
     fn valid_moves(&self) -> Vec<Move> {
-        let mut moves = Vec::new();
+        self.rules().valid_moves(self)
+    }
 
-        // If the Swap move is available for player 2.
-        if self.swap_allowed() {
-            moves.push(Move::Swap);
-        }
+    /// Checks whether the specified move is valid for the current player.
+    #[inline]
+    fn is_valid_move(&self, m: Move) -> bool {
+        self.rules().is_valid_move(self, m)
+    }
 
-        // List all pits where the number of stones > 0.
-        for (i, pit) in self.board()[self.current_turn()]
-            .as_ref()
-            .iter()
-            .enumerate()
-        {
-            if *pit > 0 {
-                moves.push(Move::Pit(i + 1));
-            }
+    /// Returns a wrapper which formats the state using the compact, single-line
+    /// notation described in [`notation`][super::notation].
+    ///
+    /// The notation can be parsed back into a state using [`str::parse`].
+    #[inline]
+    fn notation(&self) -> Notation<'_, Self> {
+        Notation {
+            state: self,
+            rules: true,
         }
-
-        moves
     }
 
-    /// Checks whether the specified move is valid for the current player.
-    fn is_valid_move(&self, m: Move) -> bool {
-        match m {
-            Move::Pit(pit)
-                if self
-                    .pit_balance(self.current_turn(), pit)
-                    .is_some_and(|b| b > 0) =>
-            {
-                true
-            }
-            Move::Pit(_) => false,
-            Move::Swap => self.swap_allowed(),
+    /// Checks that the state is internally consistent, returning a [`StateError`]
+    /// describing the first problem found, if any.
+    ///
+    /// Checks the shape of the board, that there is at least one stone, that the
+    /// ply is at least 1, that Player 1 makes the first move, that Player 2 can
+    /// have moved by the current ply, and any invariants of the rule set (e.g.,
+    /// that a finished Kalah board has been swept).
+    fn validate(&self) -> Result<(), StateError> {
+        let [first, second] = [
+            self.board()[0].as_ref().len(),
+            self.board()[1].as_ref().len(),
+        ];
+        if first != second {
+            return Err(StateError::MismatchedRows(first, second));
         }
+        if first == 0 {
+            return Err(StateError::NoPits);
+        }
+        if self.total_stones() == 0 {
+            return Err(StateError::NoStones);
+        }
+        if self.ply() == 0 {
+            return Err(StateError::InvalidPly(self.ply()));
+        }
+        if self.ply() == 1 && self.current_turn() == Player::Two {
+            return Err(StateError::InvalidTurn { ply: self.ply() });
+        }
+        // Player 2 cannot move before the second ply.
+        if self.p2_moved() && self.ply() < 3 {
+            return Err(StateError::InconsistentP2Moved { ply: self.ply() });
+        }
+        self.rules().validate(self)
     }
 
     /// Switches the current turn. Used inside [`make_move`][Self::make_move].
@@ -237,162 +268,93 @@ pub trait Mancala: Clone + Display + Send + Sync + Hash + PartialEq + Eq {
         *self.current_turn_mut() = self.current_turn().other();
     }
 
-    /// Rotates the board. Used inside [`make_move`][Self::make_move] when the swap move is requested.
+    /// Rotates the board, swapping the contents of each player's pits and stores.
     fn rotate_board(&mut self) {
         self.board_mut().swap(0, 1);
         self.stores_mut().swap(0, 1);
     }
 
     /// Returns a new board state, updated to reflect the result of making
-    /// the specified move. If the move was invalid, returns [`None`].
+    /// the specified move. If the move was invalid, returns a [`MoveError`]
+    /// describing why.
     ///
-    /// The default implementation of [`make_move`][Self::make_move] roughly
-    /// follows the gameplay rules of the "Kalah" variant of Mancala.
-    fn make_move(&self, selection: Move) -> Result<Self, ()> {
+    /// Sowing, captures, extra turns, and the end of the game are handled
+    /// by the state's [`RuleSet`] (see [`rules`][Self::rules]).
+    fn make_move(&self, selection: Move) -> Result<Self, MoveError> {
         // Ensure the move is valid.
         if !self.is_valid_move(selection) {
-            return Err(());
+            return Err(invalid_move_reason(self, selection));
         }
 
-        // Make a copy of the current state.
+        // Make a copy of the current state, and play the move on it.
         let mut new_state = self.clone();
+        play(&mut new_state, selection, None);
+        Ok(new_state)
+    }
 
-        let mut pit = match selection {
-            // Handle swap inputs.
-            Move::Swap => {
-                new_state.rotate_board();
-                new_state.switch_turn();
-                *new_state.ply_mut() += 1;
-                new_state.set_p2_moved(true);
-                return Ok(new_state);
-            }
-            Move::Pit(pit) => pit - 1,
-        };
-
-        // Ensure swap move is only available on Player 2's first move.
-        if new_state.current_turn() == Player::Two {
-            new_state.set_p2_moved(true);
-        }
-
-        // Get current player, find adjusted pit index, and collect number of stones to distribute.
-        let mut side = new_state.current_turn();
-        let stones = new_state.board()[side].as_ref()[pit];
-        new_state.board_mut()[side].as_mut()[pit] = 0;
-        pit += 1;
-
-        // Initialize turn variables.
-        let mut go_again = false;
-
-        // Distribute the stones of the selected pit.
-        let mut i = 0;
-        while i < stones {
-            let last_stone = i == stones - 1;
-
-            if pit != new_state.pits() {
-                // Add stone to pit.
-                new_state.board_mut()[side].as_mut()[pit] += 1;
-            } else {
-                // Only add stones to the current player's store.
-                let add_to_store = side == new_state.current_turn();
-                if add_to_store {
-                    new_state.stores_mut()[side] += 1;
-                    go_again = last_stone;
-                }
-
-                // Switch board sides.
-                side = side.other();
-                pit = 0;
-
-                // If we did not add to the store, make sure to add one to the next player's pit.
-                // If we DID add to the store, and if that wasn't the last stone, add one to the
-                // next player's pit, and increment i to avoid adding two stones for the same i.
-                if !add_to_store {
-                    new_state.board_mut()[side].as_mut()[pit] += 1;
-                } else if !last_stone {
-                    new_state.board_mut()[side].as_mut()[pit] += 1;
-                    i += 1;
-                }
-            }
-
-            // Determine which stones to capture (if any).
-            if last_stone
-                && side == new_state.current_turn()
-                && new_state.board()[side].as_ref()[pit] == 1
-            {
-                let to_capture = match side {
-                    Player::One => [pit, new_state.pits() - pit - 1],
-                    Player::Two => [new_state.pits() - pit - 1, pit],
-                };
-
-                new_state.stores_mut()[side] += new_state.board()[0].as_ref()[to_capture[0]];
-                new_state.stores_mut()[side] += new_state.board()[1].as_ref()[to_capture[1]];
-                new_state.board_mut()[0].as_mut()[to_capture[0]] = 0;
-                new_state.board_mut()[1].as_mut()[to_capture[1]] = 0;
-            }
-
-            pit += 1;
-            i += 1;
+    /// Makes the specified move in place, and returns an [`Undo`] which can be
+    /// passed to [`undo_move`][Self::undo_move] to take it back. If the move was
+    /// invalid, returns a [`MoveError`] describing why (and leaves the state
+    /// unchanged).
+    ///
+    /// Unlike [`make_move`][Self::make_move], the state is never cloned, which
+    /// makes this method better suited to searching many moves (see
+    /// [`MancalaZobrist::SEARCH_IN_PLACE`][crate::minimax::MancalaZobrist::SEARCH_IN_PLACE]).
+    #[inline]
+    fn apply_move(&mut self, selection: Move) -> Result<Undo, MoveError> {
+        // Ensure the move is valid.
+        if !self.is_valid_move(selection) {
+            return Err(invalid_move_reason(self, selection));
         }
 
-        // Detect completed game.
-        let final_stone_recipient: Option<Player> = {
-            if new_state.board()[Player::One]
-                .as_ref()
-                .iter()
-                .sum::<usize>()
-                == 0
-            {
-                Some(Player::Two)
-            } else if new_state.board()[Player::Two]
-                .as_ref()
-                .iter()
-                .sum::<usize>()
-                == 0
-            {
-                Some(Player::One)
-            } else {
-                None
-            }
+        let mut undo = Undo {
+            selection,
+            journal: Journal::new(),
+            current_turn: self.current_turn(),
+            p2_moved: self.p2_moved(),
+            ply: self.ply(),
+            zobrist_hash: 0,
         };
+        play(self, selection, Some(&mut undo.journal));
+        Ok(undo)
+    }
 
-        // If game is finished, player with stones on their side captures them all.
-        if let Some(winner) = final_stone_recipient {
-            for pit in 0..new_state.pits() {
-                new_state.stores_mut()[winner] += new_state.board()[winner].as_ref()[pit];
-                new_state.board_mut()[winner].as_mut()[pit] = 0;
+    /// Takes back a move made with [`apply_move`][Self::apply_move], restoring
+    /// the state to exactly how it was before the move.
+    #[inline]
+    fn undo_move(&mut self, undo: Undo) {
+        for change in undo.journal.iter() {
+            match change.location {
+                Landing::Pit(side, i) => self.board_mut()[side].as_mut()[i] = change.old,
+                Landing::Store(side) => self.stores_mut()[side] = change.old,
             }
         }
-
-        // Don't switch players if player goes again.
-        if !go_again {
-            new_state.switch_turn();
-        }
-
-        *new_state.ply_mut() += 1;
-
-        Ok(new_state)
+        *self.current_turn_mut() = undo.current_turn;
+        self.set_p2_moved(undo.p2_moved);
+        *self.ply_mut() = undo.ply;
     }
 
     /// Helper method to select a pit move without the encapsulating enum.
     #[inline]
-    fn make_move_pit(&self, pit: usize) -> Result<Self, ()> {
+    fn make_move_pit(&self, pit: usize) -> Result<Self, MoveError> {
         self.make_move(Move::Pit(pit))
     }
 
     /// Helper method to select the swap move without the encapsulating enum.
     #[inline]
-    fn make_move_swap(&self) -> Result<Self, ()> {
+    fn make_move_swap(&self) -> Result<Self, MoveError> {
         self.make_move(Move::Swap)
     }
 
     /// Make a random move, selected from the available moves.
     ///
     /// Returns a pair of (new_state, selected_move).
-    fn make_move_rand(&self) -> Result<(Self, Move), ()> {
+    fn make_move_rand(&self) -> Result<(Self, Move), MoveError> {
         let mut rng = rand::rng();
         match self.valid_moves().choose(&mut rng) {
             Some(m) => self.make_move(*m).map(|s| (s, *m)),
-            None => Err(()),
+            None if self.is_over() => Err(MoveError::GameOver),
+            None => Err(MoveError::NoMovesAvailable),
         }
     }
 
@@ -414,6 +376,9 @@ pub trait Mancala: Clone + Display + Send + Sync + Hash + PartialEq + Eq {
         sum
     }
 
+    /// Provides immutable access to the rule set used by the current game.
+    fn rules(&self) -> &Self::Rules;
+
     /// Provides immutable access to the board.
     fn board(&self) -> &[Self::Board; 2];
 
@@ -452,3 +417,135 @@ pub trait Mancala: Clone + Display + Send + Sync + Hash + PartialEq + Eq {
     /// Provides mutable access to the current turn variable.
     fn current_turn_mut(&mut self) -> &mut Player;
 }
+
+/// Helper function to play a (valid) move in place, recording any changes
+/// to the board into the journal (if supplied).
+fn play<T: Mancala>(state: &mut T, selection: Move, journal: Option<&mut Journal>) {
+    let go_again = match selection {
+        // Handle swap inputs.
+        Move::Swap => {
+            Editor::new(state, journal).rotate_board();
+            state.set_p2_moved(true);
+            false
+        }
+        Move::Pit(pit) => {
+            // Ensure swap move is only available on Player 2's first move.
+            if state.current_turn() == Player::Two {
+                state.set_p2_moved(true);
+            }
+
+            // Play the selected pit according to the rule set.
+            let rules = *state.rules();
+            rules.play_pit(&mut Editor::new(state, journal), pit - 1)
+        }
+    };
+
+    // Don't switch players if player goes again.
+    if !go_again {
+        state.switch_turn();
+    }
+
+    *state.ply_mut() += 1;
+}
+
+/// Helper function to determine why a move is invalid for the supplied state.
+/// Only called once [`Mancala::is_valid_move`] has rejected the move.
+fn invalid_move_reason<T: Mancala>(state: &T, selection: Move) -> MoveError {
+    if state.is_over() {
+        return MoveError::GameOver;
+    }
+    match selection {
+        Move::Pit(pit) => match state.pit_balance(state.current_turn(), pit) {
+            None => MoveError::PitOutOfRange {
+                pit,
+                pits: state.pits(),
+            },
+            Some(0) => MoveError::EmptyPit(pit),
+            Some(_) => MoveError::Forbidden(selection),
+        },
+        Move::Swap => MoveError::SwapNotAllowed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DynGameState, GameState, Oware};
+
+    #[test]
+    fn move_errors() {
+        let state = GameState::<6>::default();
+        for pit in [0, 7] {
+            assert_eq!(
+                state.make_move_pit(pit),
+                Err(MoveError::PitOutOfRange { pit, pits: 6 })
+            );
+        }
+        assert_eq!(state.make_move_swap(), Err(MoveError::SwapNotAllowed));
+
+        let mut state = GameState::from_arr([[0, 1, 2], [1, 1, 1]], 0, 0, Player::One, 1, false);
+        assert_eq!(state.make_move_pit(1), Err(MoveError::EmptyPit(1)));
+        assert_eq!(
+            state.apply_move(Move::Pit(1)).err(),
+            Some(MoveError::EmptyPit(1))
+        );
+
+        // Every move is rejected once the game is over.
+        let over = GameState::from_arr([[0; 3]; 2], 4, 2, Player::Two, 9, true);
+        assert_eq!(over.make_move_pit(1), Err(MoveError::GameOver));
+        assert_eq!(over.make_move_swap(), Err(MoveError::GameOver));
+        assert_eq!(over.make_move_rand(), Err(MoveError::GameOver));
+
+        // An unswept board leaves the current player without moves.
+        let stuck = GameState::from_arr([[0; 3], [1, 1, 1]], 0, 0, Player::One, 5, true);
+        assert_eq!(stuck.make_move_rand(), Err(MoveError::NoMovesAvailable));
+
+        // Player 1 must feed Player 2 in Oware, which the first pit cannot.
+        let oware = GameState::from_arr([[1, 0, 3], [0; 3]], 0, 2, Player::One, 5, true)
+            .with_rules(Oware::default());
+        assert_eq!(
+            oware.make_move_pit(1),
+            Err(MoveError::Forbidden(Move::Pit(1)))
+        );
+        assert!(oware.make_move_pit(3).is_ok());
+    }
+
+    #[test]
+    fn validate() {
+        let valid = GameState::<3>::try_new(2, 0, 0, Player::One, 1, false).unwrap();
+        assert_eq!(valid.validate(), Ok(()));
+        assert_eq!(
+            GameState::<3>::try_new(0, 0, 0, Player::One, 1, false),
+            Err(StateError::NoStones)
+        );
+        assert!(GameState::<3>::try_new(0, 3, 3, Player::One, 1, false).is_ok());
+
+        // The ply starts from 1, and Player 2 can only have moved from the third.
+        assert_eq!(
+            GameState::<3>::try_new(2, 0, 0, Player::One, 0, false),
+            Err(StateError::InvalidPly(0))
+        );
+        assert_eq!(
+            GameState::<3>::try_new(2, 0, 0, Player::One, 2, true),
+            Err(StateError::InconsistentP2Moved { ply: 2 })
+        );
+        assert!(GameState::<3>::try_new(2, 0, 0, Player::One, 3, true).is_ok());
+
+        // Player 1 always makes the first move.
+        assert_eq!(
+            GameState::<3>::try_new(2, 0, 0, Player::Two, 1, false),
+            Err(StateError::InvalidTurn { ply: 1 })
+        );
+        assert!(GameState::<3>::try_new(2, 0, 0, Player::Two, 2, false).is_ok());
+
+        // A finished Kalah board must have been swept.
+        assert_eq!(
+            GameState::<3>::try_from_arr([[0; 3], [1, 0, 2]], 3, 0, Player::One, 5, true),
+            Err(StateError::Unswept)
+        );
+        assert_eq!(
+            DynGameState::try_from_vec(&[vec![1, 2], vec![3]], 0, 0, Player::One, 1, false),
+            Err(StateError::MismatchedRows(2, 1))
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Compact, single-line text notation for Mancala positions.
+//!
+//! A position is written as five or six space-separated fields:
+//!
+//! 1. The board: Player 1's pits (starting from pit 1), separated by commas,
+//!    followed by a `/` and Player 2's pits in the same format.
+//! 2. The stores: Player 1's store and Player 2's store, separated by a `:`.
+//! 3. The player to move: `1` or `2`.
+//! 4. The current ply.
+//! 5. Whether Player 2 has moved: `m` if so, otherwise `-`.
+//! 6. The rule set and its options (see [`RuleSet::notation`]), e.g.,
+//!    `oware:forbidden,300`. Omitted if the state uses the default rule set of its
+//!    type, in which case parsing also uses the default rule set.
+//!
+//! For example, the position given by [`GameState::default`] is written as
+//! `4,4,4,4,4,4/4,4,4,4,4,4 0:0 1 1 -`.
+//!
+//! The notation does not include the Zobrist hash of a state, so parsed states
+//! have a hash of zero. Since states are compared without their hashes,
+//! parsing a formatted state always produces an equal state.
+//!
+//! [`GameState::default`]: super::GameState::default
+
+use super::error::NotationError;
+use super::mancala::{Mancala, Player};
+use super::rules::RuleSet;
+use std::fmt::{Display, Formatter};
+
+/// Wrapper used to format a Mancala state using the compact notation
+/// described in the [module documentation][self].
+///
+/// Created by calling [`Mancala::notation`].
+#[derive(Debug, Clone, Copy)]
+pub struct Notation<'a, T: Mancala> {
+    pub(super) state: &'a T,
+    /// Whether the sixth field (the rule set) is written, if necessary.
+    pub(super) rules: bool,
+}
+
+impl<T: Mancala> Display for Notation<'_, T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let state = self.state;
+        for (i, side) in state.board().iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            let pits: Vec<String> = side.as_ref().iter().map(|p| p.to_string()).collect();
+            write!(f, "{}", pits.join(","))?;
+        }
+        write!(
+            f,
+            " {}:{} {} {} {}",
+            state.stores()[0],
+            state.stores()[1],
+            usize::from(state.current_turn()),
+            state.ply(),
+            if state.p2_moved() { "m" } else { "-" }
+        )?;
+        let rules = state.rules();
+        match rules.notation() {
+            Some(field) if self.rules && *rules != T::Rules::default() => {
+                write!(f, " {}", field)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The components of a position parsed from the compact notation.
+pub(super) struct ParsedPosition<R: RuleSet> {
+    pub(super) rules: R,
+    pub(super) board: Vec<Vec<usize>>,
+    pub(super) stores: [usize; 2],
+    pub(super) current_turn: Player,
+    pub(super) ply: usize,
+    pub(super) p2_moved: bool,
+}
+
+/// Parses the fields of the compact notation, without checking whether the
+/// resulting position is valid.
+pub(super) fn parse<R: RuleSet>(s: &str) -> Result<ParsedPosition<R>, NotationError> {
+    let fields: Vec<&str> = s.split_whitespace().collect();
+    let (board, stores, turn, ply, p2_moved, rules) = match fields[..] {
+        [board, stores, turn, ply, p2_moved] => (board, stores, turn, ply, p2_moved, None),
+        [board, stores, turn, ply, p2_moved, rules] => {
+            (board, stores, turn, ply, p2_moved, Some(rules))
+        }
+        _ => return Err(NotationError::WrongFieldCount(fields.len())),
+    };
+
+    let board = board
+        .split('/')
+        .map(|row| row.split(',').map(parse_number).collect())
+        .collect::<Result<Vec<Vec<usize>>, _>>()?;
+    if board.len() != 2 {
+        return Err(NotationError::InvalidBoard(board.len()));
+    }
+
+    let stores = match stores.split_once(':') {
+        Some((one, two)) => [parse_number(one)?, parse_number(two)?],
+        None => return Err(NotationError::InvalidStores(stores.to_string())),
+    };
+
+    let current_turn = match turn {
+        "1" => Player::One,
+        "2" => Player::Two,
+        _ => return Err(NotationError::InvalidTurn(turn.to_string())),
+    };
+
+    let p2_moved = match p2_moved {
+        "m" => true,
+        "-" => false,
+        _ => return Err(NotationError::InvalidP2Moved(p2_moved.to_string())),
+    };
+
+    let rules = match rules {
+        Some(field) => {
+            R::from_notation(field).ok_or_else(|| NotationError::InvalidRules(field.to_string()))?
+        }
+        None => R::default(),
+    };
+
+    Ok(ParsedPosition {
+        rules,
+        board,
+        stores,
+        current_turn,
+        ply: parse_number(ply)?,
+        p2_moved,
+    })
+}
+
+/// Helper function to parse a single non-negative number.
+fn parse_number(s: &str) -> Result<usize, NotationError> {
+    s.parse()
+        .map_err(|_| NotationError::InvalidNumber(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::{GrandSlam, Kalah, KalahOptions, Oware, Relay, Sweep};
+    use crate::game::{DynGameState, GameState};
+    use crate::minimax::{MancalaZobrist, ZobristData};
+    use std::fmt::Debug;
+    use std::str::FromStr;
+
+    const OPTIONS: KalahOptions = KalahOptions {
+        empty_capture: false,
+        sweep: Sweep::Emptied,
+        pie_rule: false,
+    };
+
+    /// Plays random games from the state, checking that every position
+    /// (hashed using Zobrist data) survives a round-trip through the notation.
+    fn assert_round_trips<T>(start: T)
+    where
+        T: MancalaZobrist + Debug + FromStr<Err = NotationError>,
+    {
+        let z_data = ZobristData::for_state_like(&start, 3);
+        for _ in 0..20 {
+            let mut state = start.clone();
+            while !state.is_over() {
+                let (next, m) = state.make_move_rand().unwrap();
+                state = state.make_move_zobrist(&z_data, m).unwrap();
+                assert_eq!(state, next);
+                let notation = state.notation().to_string();
+                let parsed = T::from_str(&notation).unwrap();
+                assert_eq!(parsed, state, "{}", notation);
+                assert_eq!(*parsed.rules(), *state.rules());
+                assert_eq!(parsed.zobrist_hash(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn default_state() {
+        let state = GameState::<6>::default();
+        let notation = state.notation().to_string();
+        assert_eq!(notation, "4,4,4,4,4,4/4,4,4,4,4,4 0:0 1 1 -");
+        assert_eq!(notation.parse::<GameState<6>>().unwrap(), state);
+        assert_eq!(notation.parse::<DynGameState>().unwrap(), state.into());
+    }
+
+    #[test]
+    fn game_state_round_trip() {
+        assert_round_trips(GameState::<6>::default());
+        assert_round_trips(GameState::<6>::default().with_rules(Kalah::new(OPTIONS)));
+        assert_round_trips(GameState::<6>::default().with_rules(Oware::new(GrandSlam::Forbidden)));
+        assert_round_trips(
+            GameState::<4>::new(3, 0, 0, Player::One, 1, false)
+                .with_rules(Relay::new(3).with_options(OPTIONS)),
+        );
+    }
+
+    #[test]
+    fn dyn_game_state_round_trip() {
+        assert_round_trips(DynGameState::default());
+        assert_round_trips(DynGameState::default().with_rules(Kalah::new(OPTIONS)));
+        assert_round_trips(DynGameState::default().with_rules(Oware::new(GrandSlam::Allowed)));
+        assert_round_trips(
+            DynGameState::new(5, 3, 0, 0, Player::One, 1, false).with_rules(Relay::new(2)),
+        );
+    }
+
+    #[test]
+    fn rule_set_field() {
+        let state = GameState::<6>::default().with_rules(Oware::new(GrandSlam::Forbidden));
+        let notation = state.notation().to_string();
+        assert_eq!(
+            notation,
+            "4,4,4,4,4,4/4,4,4,4,4,4 0:0 1 1 - oware:forbidden,300"
+        );
+
+        // Rule sets of a different type are rejected, rather than replaced.
+        assert!(matches!(
+            notation.parse::<GameState<6>>(),
+            Err(NotationError::InvalidRules(_))
+        ));
+        assert!(matches!(
+            notation.parse::<GameState<6, Relay>>(),
+            Err(NotationError::InvalidRules(_))
+        ));
+        assert!(matches!(
+            "4,4/4,4 0:0 1 1 - relay:0,y,owner,y".parse::<GameState<2, Relay>>(),
+            Err(NotationError::InvalidRules(_))
+        ));
+    }
+}
@@ -0,0 +1,538 @@
+//! Records of complete Mancala games, including a PGN-like text format.
+//!
+//! A record is written as a series of tag lines, followed by a blank line
+//! and the movetext:
+//!
+//! ```text
+//! [Player1 "Human"]
+//! [Player2 "Minimax (max_depth: Some(18), max_time: None)"]
+//! [Result "0-1"]
+//! [Position "4,4,4,4,4,4/4,4,4,4,4,4 0:0 1 1 -"]
+//! [Rules "kalah:y,owner,y"]
+//!
+//! 1. 3 2. 6 {eval=2 depth=18} 3. S {"Swapped"} 4. 1
+//! ```
+//!
+//! - The `Position` tag holds the initial state, written using the compact
+//!   [`notation`][super::notation] (without the rule set), and is required.
+//! - The `Rules` tag holds the rule set and its options (see
+//!   [`RuleSet::notation`]), and is omitted if the rule set cannot be written.
+//!   If it is missing, the default rule set is used.
+//! - The `Result` tag is one of `1-0`, `0-1`, `1/2-1/2`, or `*` (ongoing).
+//! - A loss on time is written as a win for the opponent, followed by a
+//!   `Termination` tag with the value `time forfeit`.
+//! - Any other tags are preserved, in order.
+//! - Each move is preceded by its (optional) number, and is either a pit number
+//!   or `S` for the swap move. Moves may be followed by an annotation in braces,
+//!   containing an evaluation (`eval=`), a search depth (`depth=`), and/or a
+//!   quoted comment.
+//! - Quoted values escape backslashes, quotes, and line breaks (`\n` and `\r`)
+//!   using a backslash.
+
+use super::error::{NotationError, RecordError};
+use super::mancala::{GameOutcome, Mancala, Move, Player};
+use super::notation::Notation;
+use super::rules::RuleSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A single move stored in a [`GameRecord`], along with any annotations.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedMove {
+    pub selection: Move,
+    pub eval: Option<f32>,
+    pub depth: Option<usize>,
+    pub comment: Option<String>,
+}
+
+impl From<Move> for RecordedMove {
+    fn from(value: Move) -> Self {
+        Self {
+            selection: value,
+            eval: None,
+            depth: None,
+            comment: None,
+        }
+    }
+}
+
+impl RecordedMove {
+    /// Returns whether the move has any annotations.
+    pub fn is_annotated(&self) -> bool {
+        self.eval.is_some() || self.depth.is_some() || self.comment.is_some()
+    }
+}
+
+/// Stores a complete (or in-progress) game of Mancala: the initial state,
+/// the ordered list of moves made, a description of each player (e.g., a name
+/// or engine configuration), the result, and any additional tags.
+///
+/// Can be written to and parsed from the text format described in the
+/// [module documentation][self] via [`Display`] and [`FromStr`].
+///
+/// If the `serde` feature is enabled, this struct will be serializable and
+/// deserializable.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord<T: Mancala> {
+    initial_state: T,
+    players: [String; 2],
+    moves: Vec<RecordedMove>,
+    result: GameOutcome,
+    tags: Vec<(String, String)>,
+}
+
+impl<T: Mancala> GameRecord<T> {
+    /// Create a new, empty record for a game starting from the supplied state.
+    ///
+    /// Both players are described as `"?"`, and the result is set to
+    /// [`GameOutcome::Ongoing`].
+    pub fn new(initial_state: T) -> Self {
+        Self {
+            initial_state,
+            players: ["?".to_string(), "?".to_string()],
+            moves: Vec::new(),
+            result: GameOutcome::Ongoing,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Consume and return the current record, using the supplied descriptions
+    /// for Player 1 and Player 2.
+    pub fn with_players(mut self, one: impl Into<String>, two: impl Into<String>) -> Self {
+        self.players = [one.into(), two.into()];
+        self
+    }
+
+    /// Provides a reference to the initial state of the game.
+    pub fn initial_state(&self) -> &T {
+        &self.initial_state
+    }
+
+    /// Returns the description of the supplied player.
+    pub fn player(&self, player: Player) -> &str {
+        &self.players[player]
+    }
+
+    /// Provides a reference to the moves made during the game.
+    pub fn moves(&self) -> &Vec<RecordedMove> {
+        &self.moves
+    }
+
+    /// Returns the recorded result of the game.
+    pub fn result(&self) -> GameOutcome {
+        self.result
+    }
+
+    /// Provides a reference to any additional (name, value) tags.
+    pub fn tags(&self) -> &Vec<(String, String)> {
+        &self.tags
+    }
+
+    /// Sets the description of the supplied player.
+    pub fn set_player(&mut self, player: Player, description: impl Into<String>) {
+        self.players[player] = description.into();
+    }
+
+    /// Sets the recorded result of the game.
+    pub fn set_result(&mut self, result: GameOutcome) {
+        self.result = result;
+    }
+
+    /// Adds a tag, replacing the value of any existing tag with the same name.
+    pub fn set_tag(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let (name, value) = (name.into(), value.into());
+        match self.tags.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((name, value)),
+        }
+    }
+
+    /// Appends a move (with or without annotations) to the record.
+    ///
+    /// The move is not checked for legality; see [`replay`][Self::replay].
+    pub fn push(&mut self, m: impl Into<RecordedMove>) {
+        self.moves.push(m.into());
+    }
+
+    /// Replays every move, starting from the initial state, and returns the
+    /// final state. Fails if any move is illegal.
+    pub fn replay(&self) -> Result<T, RecordError> {
+        let mut state = self.initial_state.clone();
+        for (i, m) in self.moves.iter().enumerate() {
+            state = state
+                .make_move(m.selection)
+                .map_err(|error| RecordError::IllegalMove {
+                    number: i + 1,
+                    error,
+                })?;
+        }
+        Ok(state)
+    }
+
+    /// Replays every move, and checks that the recorded result matches the
+    /// outcome of the final state. Returns the final state if so.
+    ///
+    /// A loss on time ([`GameOutcome::TimeForfeit`]) matches any final state
+    /// where the game is still ongoing.
+    pub fn verify(&self) -> Result<T, RecordError> {
+        let state = self.replay()?;
+        let expected = match self.result {
+            GameOutcome::TimeForfeit(_) => GameOutcome::Ongoing,
+            result => result,
+        };
+        if state.outcome() != expected {
+            return Err(RecordError::ResultMismatch {
+                recorded: self.result,
+                actual: state.outcome(),
+            });
+        }
+        Ok(state)
+    }
+}
+
+impl<T: Mancala> Display for GameRecord<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(f, "[Player1 {}]", quote(&self.players[0]))?;
+        writeln!(f, "[Player2 {}]", quote(&self.players[1]))?;
+        writeln!(f, "[Result {}]", quote(result_to_str(self.result)))?;
+        if let GameOutcome::TimeForfeit(_) = self.result {
+            writeln!(f, "[Termination {}]", quote(TIME_FORFEIT))?;
+        }
+        let position = Notation {
+            state: &self.initial_state,
+            rules: false,
+        };
+        writeln!(f, "[Position {}]", quote(&position.to_string()))?;
+        if let Some(rules) = self.initial_state.rules().notation() {
+            writeln!(f, "[Rules {}]", quote(&rules))?;
+        }
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} {}]", name, quote(value))?;
+        }
+        writeln!(f)?;
+
+        // Write the movetext, wrapping lines at roughly 80 characters.
+        let mut line = String::new();
+        for (i, m) in self.moves.iter().enumerate() {
+            let mut token = match m.selection {
+                Move::Pit(pit) => format!("{}. {}", i + 1, pit),
+                Move::Swap => format!("{}. S", i + 1),
+            };
+            if m.is_annotated() {
+                let mut parts = Vec::new();
+                if let Some(eval) = m.eval {
+                    parts.push(format!("eval={}", eval));
+                }
+                if let Some(depth) = m.depth {
+                    parts.push(format!("depth={}", depth));
+                }
+                if let Some(comment) = &m.comment {
+                    parts.push(quote(comment));
+                }
+                token += &format!(" {{{}}}", parts.join(" "));
+            }
+            if !line.is_empty() && line.len() + token.len() >= 80 {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += &token;
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+impl<T: Mancala + FromStr<Err = NotationError>> FromStr for GameRecord<T> {
+    type Err = RecordError;
+
+    /// Parses a record from the text format described in the
+    /// [module documentation][self]. The moves are not replayed; use
+    /// [`replay`][GameRecord::replay] or [`verify`][GameRecord::verify]
+    /// to check them.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut players = ["?".to_string(), "?".to_string()];
+        let mut result = GameOutcome::Ongoing;
+        let mut time_forfeit = false;
+        let mut position = None;
+        let mut rules = None;
+        let mut tags = Vec::new();
+
+        // Parse the tag lines.
+        let mut lines = s.lines().peekable();
+        while let Some(line) = lines.peek().map(|l| l.trim()) {
+            if !line.starts_with('[') {
+                if line.is_empty() {
+                    lines.next();
+                    continue;
+                }
+                break;
+            }
+            let (name, value) = parse_tag(line)?;
+            match name.as_str() {
+                "Player1" => players[0] = value,
+                "Player2" => players[1] = value,
+                "Result" => result = result_from_str(&value)?,
+                "Position" => position = Some(value),
+                "Rules" => rules = Some(value),
+                "Termination" if value == TIME_FORFEIT => time_forfeit = true,
+                _ => tags.push((name, value)),
+            }
+            lines.next();
+        }
+
+        if time_forfeit {
+            result = match result {
+                GameOutcome::Winner(p) => GameOutcome::TimeForfeit(p.other()),
+                _ => {
+                    tags.push(("Termination".to_string(), TIME_FORFEIT.to_string()));
+                    result
+                }
+            };
+        }
+
+        let position = position.ok_or(RecordError::MissingTag("Position"))?;
+        let initial_state = match rules {
+            Some(rules) => format!("{} {}", position, rules).parse()?,
+            None => position.parse()?,
+        };
+        let movetext: Vec<&str> = lines.collect();
+
+        Ok(Self {
+            initial_state,
+            players,
+            moves: parse_movetext(&movetext.join("\n"))?,
+            result,
+            tags,
+        })
+    }
+}
+
+/// The value of the `Termination` tag for a loss on time.
+const TIME_FORFEIT: &str = "time forfeit";
+
+/// Helper function to convert a game outcome to a result string.
+fn result_to_str(result: GameOutcome) -> &'static str {
+    match result {
+        GameOutcome::Winner(Player::One) | GameOutcome::TimeForfeit(Player::Two) => "1-0",
+        GameOutcome::Winner(Player::Two) | GameOutcome::TimeForfeit(Player::One) => "0-1",
+        GameOutcome::Tie => "1/2-1/2",
+        GameOutcome::Ongoing => "*",
+    }
+}
+
+/// Helper function to convert a result string to a game outcome.
+fn result_from_str(s: &str) -> Result<GameOutcome, RecordError> {
+    match s {
+        "1-0" => Ok(GameOutcome::Winner(Player::One)),
+        "0-1" => Ok(GameOutcome::Winner(Player::Two)),
+        "1/2-1/2" => Ok(GameOutcome::Tie),
+        "*" => Ok(GameOutcome::Ongoing),
+        _ => Err(RecordError::InvalidResult(s.to_string())),
+    }
+}
+
+/// Helper function to surround a string in quotes, escaping as necessary.
+fn quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '\\' => quoted += "\\\\",
+            '"' => quoted += "\\\"",
+            '\n' => quoted += "\\n",
+            '\r' => quoted += "\\r",
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Helper function to read a quoted string from the start of `chars`,
+/// removing any escapes. Returns [`None`] if the string is not terminated.
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut value = String::new();
+    chars.next_if_eq(&'"')?;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                c => c,
+            }),
+            '"' => return Some(value),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Helper function to parse a single `[Name "Value"]` tag line.
+fn parse_tag(line: &str) -> Result<(String, String), RecordError> {
+    let invalid = || RecordError::InvalidTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, rest) = inner.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let mut chars = rest.trim().chars().peekable();
+    let value = read_quoted(&mut chars).ok_or_else(invalid)?;
+    if chars.next().is_some() || name.is_empty() {
+        return Err(invalid());
+    }
+    Ok((name.to_string(), value))
+}
+
+/// Helper function to parse the movetext of a record.
+fn parse_movetext(text: &str) -> Result<Vec<RecordedMove>, RecordError> {
+    let mut moves: Vec<RecordedMove> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '{' {
+            // Parse an annotation for the previous move.
+            chars.next();
+            let mut annotation = String::new();
+            let mut comment = None;
+            loop {
+                match chars.peek() {
+                    Some('}') => {
+                        chars.next();
+                        break;
+                    }
+                    Some('"') => {
+                        comment =
+                            Some(read_quoted(&mut chars).ok_or_else(|| {
+                                RecordError::InvalidAnnotation(annotation.clone())
+                            })?);
+                    }
+                    Some(&c) => {
+                        annotation.push(c);
+                        chars.next();
+                    }
+                    None => return Err(RecordError::InvalidAnnotation(annotation)),
+                }
+            }
+            let m = moves
+                .last_mut()
+                .ok_or_else(|| RecordError::InvalidAnnotation(annotation.clone()))?;
+            m.comment = comment;
+            for part in annotation.split_whitespace() {
+                let invalid = || RecordError::InvalidAnnotation(part.to_string());
+                match part.split_once('=') {
+                    Some(("eval", v)) => m.eval = Some(v.parse().map_err(|_| invalid())?),
+                    Some(("depth", v)) => m.depth = Some(v.parse().map_err(|_| invalid())?),
+                    _ => return Err(invalid()),
+                }
+            }
+        } else {
+            // Parse a move number or move.
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '{' {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            if token.ends_with('.') && token[..token.len() - 1].parse::<usize>().is_ok() {
+                continue;
+            }
+            let selection = match token.as_str() {
+                "S" => Move::Swap,
+                t => match t.parse::<usize>() {
+                    Ok(pit) if pit > 0 => Move::Pit(pit),
+                    _ => return Err(RecordError::InvalidMove(token)),
+                },
+            };
+            moves.push(selection.into());
+        }
+    }
+
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::game::rules::{GrandSlam, Kalah, KalahOptions, Oware, Sweep};
+    use std::fmt::Debug;
+
+    /// Plays a random game from the state, recording the moves (annotating
+    /// some of them), and the result.
+    fn random_game<T: Mancala>(start: T) -> GameRecord<T> {
+        let mut record =
+            GameRecord::new(start.clone()).with_players("Line1\nLine2", "\"Q\" \\ r\r");
+        let mut state = start;
+        while !state.is_over() {
+            let (next, m) = state.make_move_rand().unwrap();
+            record.push(RecordedMove {
+                selection: m,
+                eval: Some(state.ply() as f32 / 4.0),
+                depth: None,
+                comment: state
+                    .ply()
+                    .is_multiple_of(5)
+                    .then(|| format!("ply\n{}", state.ply())),
+            });
+            state = next;
+        }
+        record.set_result(state.outcome());
+        record
+    }
+
+    fn assert_round_trips<T>(start: T)
+    where
+        T: Mancala + Debug + FromStr<Err = NotationError>,
+    {
+        for _ in 0..200 {
+            let record = random_game(start.clone());
+            let text = record.to_string();
+            let parsed: GameRecord<T> = text.parse().unwrap();
+            assert_eq!(parsed, record, "{}", text);
+            parsed.verify().unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_round_trips(GameState::<6>::default());
+        assert_round_trips(
+            GameState::<6>::default().with_rules(Kalah::new(KalahOptions {
+                sweep: Sweep::Emptied,
+                ..Default::default()
+            })),
+        );
+        assert_round_trips(GameState::<6>::default().with_rules(Oware::new(GrandSlam::Allowed)));
+    }
+
+    #[test]
+    fn rules_tag() {
+        let record =
+            GameRecord::new(GameState::<6>::default().with_rules(Oware::new(GrandSlam::Forbidden)));
+        let text = record.to_string();
+        assert!(text.contains("[Position \"4,4,4,4,4,4/4,4,4,4,4,4 0:0 1 1 -\"]\n"));
+        assert!(text.contains("[Rules \"oware:forbidden,300\"]\n"));
+
+        // A record using a different rule set type is rejected.
+        assert!(matches!(
+            text.parse::<GameRecord<GameState<6>>>(),
+            Err(RecordError::Notation(NotationError::InvalidRules(_)))
+        ));
+    }
+
+    #[test]
+    fn escapes_line_breaks() {
+        assert_eq!(quote("a\"b\\c\nd\re"), r#""a\"b\\c\nd\re""#);
+        let quoted = quote("Line1\nLine2\r");
+        let mut chars = quoted.chars().peekable();
+        assert_eq!(read_quoted(&mut chars).unwrap(), "Line1\nLine2\r");
+        assert!(chars.next().is_none());
+    }
+}
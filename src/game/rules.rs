@@ -0,0 +1,247 @@
+//! Rule sets which determine how Mancala moves are carried out.
+//!
+//! The [`Mancala`] trait delegates all variant-specific behavior (sowing,
+//! capturing, extra turns, and the end of the game) to the [`RuleSet`]
+//! stored inside each game state. This allows the same state types, and
+//! therefore the same search algorithms, to be used with several variants.
+//!
+//! Rule sets only modify the stones on the board through an [`Editor`], which
+//! records each change so that moves can be made and taken back in place
+//! (see [`Mancala::apply_move`]).
+
+pub mod kalah;
+pub mod oware;
+pub mod relay;
+
+pub use kalah::{Kalah, KalahOptions, Sweep};
+pub use oware::{GrandSlam, Oware};
+pub use relay::Relay;
+
+use super::error::StateError;
+use super::mancala::{GameOutcome, Mancala, Move, Player};
+use super::undo::Journal;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Deref;
+
+/// Describes the location in which the final stone of a sowing move landed.
+///
+/// Pit indices stored in the [`Pit`][Self::Pit] variant start from 0, as they
+/// are used to index into the board directly.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Landing {
+    Pit(Player, usize),
+    Store(Player),
+}
+
+impl Landing {
+    /// Returns the location following the current one in counter-clockwise
+    /// order, as seen by the player `mover`. The opponent's store is skipped.
+    #[inline]
+    pub fn next(self, mover: Player, pits: usize) -> Landing {
+        match self {
+            Landing::Pit(side, i) if i + 1 < pits => Landing::Pit(side, i + 1),
+            Landing::Pit(side, _) if side == mover => Landing::Store(side),
+            Landing::Pit(side, _) => Landing::Pit(side.other(), 0),
+            Landing::Store(side) => Landing::Pit(side.other(), 0),
+        }
+    }
+}
+
+/// Provides read access to a game state (via [`Deref`]), while ensuring every
+/// change to the stones on the board is recorded in a [`Journal`] (if supplied).
+pub struct Editor<'a, T: Mancala> {
+    state: &'a mut T,
+    journal: Option<&'a mut Journal>,
+}
+
+impl<T: Mancala> Deref for Editor<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.state
+    }
+}
+
+impl<'a, T: Mancala> Editor<'a, T> {
+    /// Create a new editor for the supplied state, recording changes into the
+    /// journal (if supplied).
+    #[inline]
+    pub fn new(state: &'a mut T, journal: Option<&'a mut Journal>) -> Self {
+        Self { state, journal }
+    }
+
+    /// Returns the number of stones at the location.
+    #[inline]
+    pub fn stones(&self, location: Landing) -> usize {
+        match location {
+            Landing::Pit(side, i) => self.state.board()[side].as_ref()[i],
+            Landing::Store(side) => self.state.stores()[side],
+        }
+    }
+
+    /// Sets the number of stones at the location.
+    #[inline]
+    pub fn set(&mut self, location: Landing, stones: usize) {
+        let slot = match location {
+            Landing::Pit(side, i) => &mut self.state.board_mut()[side].as_mut()[i],
+            Landing::Store(side) => &mut self.state.stores_mut()[side],
+        };
+        let old = std::mem::replace(slot, stones);
+        if let Some(journal) = self.journal.as_deref_mut() {
+            journal.record(location, old, self.state.pits());
+        }
+    }
+
+    /// Adds stones to the location.
+    #[inline]
+    pub fn add(&mut self, location: Landing, stones: usize) {
+        self.set(location, self.stones(location) + stones);
+    }
+
+    /// Removes every stone from the location, and returns how many there were.
+    #[inline]
+    pub fn take(&mut self, location: Landing) -> usize {
+        let stones = self.stones(location);
+        if stones > 0 {
+            self.set(location, 0);
+        }
+        stones
+    }
+
+    /// Swaps the contents of each player's pits and stores.
+    pub fn rotate_board(&mut self) {
+        for i in 0..self.pits() {
+            self.swap(Landing::Pit(Player::One, i), Landing::Pit(Player::Two, i));
+        }
+        self.swap(Landing::Store(Player::One), Landing::Store(Player::Two));
+    }
+
+    /// Swaps the contents of two locations.
+    #[inline]
+    fn swap(&mut self, a: Landing, b: Landing) {
+        let (a_stones, b_stones) = (self.stones(a), self.stones(b));
+        if a_stones != b_stones {
+            self.set(a, b_stones);
+            self.set(b, a_stones);
+        }
+    }
+}
+
+/// Specifies the gameplay rules of a Mancala variant.
+///
+/// Implementors only need to describe how a pit is sown, what is captured,
+/// when the mover is granted an extra turn, and what happens once the game is
+/// over. Validation, swapping, turn switching, and ply tracking are handled
+/// by [`Mancala::make_move`].
+///
+/// All methods are called while it is still the moving player's turn
+/// (i.e., [`Mancala::current_turn`] returns the player making the move).
+pub trait RuleSet: Debug + Clone + Copy + Default + Hash + PartialEq + Eq + Send + Sync {
+    /// Determines whether the swap move is currently allowed.
+    fn swap_allowed<T: Mancala>(&self, state: &T) -> bool {
+        !state.p2_moved() && state.current_turn() == Player::Two
+    }
+
+    /// Returns a vector of moves that are currently valid for the current player.
+    fn valid_moves<T: Mancala>(&self, state: &T) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        // If the Swap move is available for player 2.
+        if self.swap_allowed(state) {
+            moves.push(Move::Swap);
+        }
+
+        // List all pits where the number of stones > 0.
+        for (i, pit) in state.board()[state.current_turn()]
+            .as_ref()
+            .iter()
+            .enumerate()
+        {
+            if *pit > 0 {
+                moves.push(Move::Pit(i + 1));
+            }
+        }
+
+        moves
+    }
+
+    /// Checks whether the specified move is valid for the current player.
+    fn is_valid_move<T: Mancala>(&self, state: &T, m: Move) -> bool {
+        match m {
+            Move::Pit(pit) => state
+                .pit_balance(state.current_turn(), pit)
+                .is_some_and(|b| b > 0),
+            Move::Swap => self.swap_allowed(state),
+        }
+    }
+
+    /// Picks up the stones in the current player's pit (starting from 0), and
+    /// distributes them around the board. Returns the location of the last stone.
+    fn sow<T: Mancala>(&self, state: &mut Editor<T>, pit: usize) -> Landing;
+
+    /// Performs any captures resulting from the last stone landing at `landing`.
+    fn capture<T: Mancala>(&self, state: &mut Editor<T>, landing: Landing);
+
+    /// Determines whether the current player moves again after their last
+    /// stone landed at `landing`.
+    fn extra_turn<T: Mancala>(&self, state: &T, landing: Landing) -> bool;
+
+    /// Detects a completed game, and distributes any stones left on the board.
+    fn end_of_game<T: Mancala>(&self, state: &mut Editor<T>);
+
+    /// Determines whether the game is over.
+    fn is_over<T: Mancala>(&self, state: &T) -> bool {
+        state
+            .board()
+            .iter()
+            .all(|side| side.as_ref().iter().all(|pit| *pit == 0))
+    }
+
+    /// Determines the current outcome of the game.
+    fn outcome<T: Mancala>(&self, state: &T) -> GameOutcome {
+        if self.is_over(state) {
+            if state.score(Player::One) > state.score(Player::Two) {
+                GameOutcome::Winner(Player::One)
+            } else if state.score(Player::Two) > state.score(Player::One) {
+                GameOutcome::Winner(Player::Two)
+            } else {
+                GameOutcome::Tie
+            }
+        } else {
+            GameOutcome::Ongoing
+        }
+    }
+
+    /// Checks any invariants specific to the rule set. Called inside
+    /// [`Mancala::validate`] once the general invariants have been checked.
+    fn validate<T: Mancala>(&self, _state: &T) -> Result<(), StateError> {
+        Ok(())
+    }
+
+    /// Writes the rule set (including its options) as the optional sixth field
+    /// of the compact [`notation`][super::notation], e.g., `oware:forbidden,300`.
+    /// The field must not contain whitespace.
+    ///
+    /// Returns [`None`] by default, in which case the field is always omitted.
+    fn notation(&self) -> Option<String> {
+        None
+    }
+
+    /// Parses a rule set written by [`notation`][Self::notation], returning
+    /// [`None`] if the field does not describe this rule set.
+    fn from_notation(_field: &str) -> Option<Self> {
+        None
+    }
+
+    /// Plays the current player's pit (starting from 0) by running each stage
+    /// of the rule set in order. Returns whether the player should move again.
+    fn play_pit<T: Mancala>(&self, state: &mut Editor<T>, pit: usize) -> bool {
+        let landing = self.sow(state, pit);
+        self.capture(state, landing);
+        let go_again = self.extra_turn(&**state, landing);
+        self.end_of_game(state);
+        go_again
+    }
+}
@@ -0,0 +1,312 @@
+//! The "Kalah" rule set, used by default for all game states.
+
+use super::{Editor, Landing, RuleSet};
+use crate::game::error::StateError;
+use crate::game::mancala::{Mancala, Player};
+
+/// Gameplay rules roughly following the "Kalah" variant of Mancala.
+///
+/// - Stones are sown counter-clockwise into the player's own store, but
+///   never into the opponent's store.
+/// - If the last stone lands in the player's store, they move again.
+/// - If the last stone lands in one of the player's own empty pits, that
+///   stone and any stones in the opposite pit are captured.
+/// - Once either player has no stones left in their pits, the remaining
+///   stones are captured and the game ends.
+/// - Player 2 may swap the board instead of making their first move.
+///
+/// Details which differ between tournaments are configured via [`KalahOptions`].
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Kalah {
+    options: KalahOptions,
+}
+
+/// Determines which player captures the stones left on the board once
+/// either player has no stones left in their pits.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sweep {
+    /// Each remaining stone goes to the player on whose side it lies.
+    #[default]
+    Owner,
+    /// The remaining stones go to the player who ran out of stones.
+    Emptied,
+}
+
+/// Configurable details of the [`Kalah`] rule set.
+///
+/// The default options are the following:
+/// - `empty_capture`: [`true`]
+/// - `sweep`: [`Sweep::Owner`]
+/// - `pie_rule`: [`true`]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KalahOptions {
+    /// Whether a capture is made when the last stone lands in an empty pit,
+    /// even though the opposite pit is also empty.
+    pub empty_capture: bool,
+    /// Which player captures the stones left on the board at the end of the game.
+    pub sweep: Sweep,
+    /// Whether Player 2 may swap the board instead of making their first move.
+    pub pie_rule: bool,
+}
+
+impl Default for KalahOptions {
+    fn default() -> Self {
+        Self {
+            empty_capture: true,
+            sweep: Sweep::Owner,
+            pie_rule: true,
+        }
+    }
+}
+
+impl Kalah {
+    /// Create a new Kalah rule set using the supplied options.
+    pub fn new(options: KalahOptions) -> Self {
+        Self { options }
+    }
+
+    /// Returns the options used by the rule set.
+    #[inline]
+    pub fn options(&self) -> KalahOptions {
+        self.options
+    }
+}
+
+impl RuleSet for Kalah {
+    #[inline]
+    fn swap_allowed<T: Mancala>(&self, state: &T) -> bool {
+        self.options.pie_rule && !state.p2_moved() && state.current_turn() == Player::Two
+    }
+
+    #[inline]
+    fn sow<T: Mancala>(&self, state: &mut Editor<T>, pit: usize) -> Landing {
+        let mover = state.current_turn();
+        sow_from(state, mover, Landing::Pit(mover, pit))
+    }
+
+    fn capture<T: Mancala>(&self, state: &mut Editor<T>, landing: Landing) {
+        let mover = state.current_turn();
+
+        // Only capture if the last stone landed in one of the mover's empty pits.
+        let pit = match landing {
+            Landing::Pit(side, pit) if side == mover => pit,
+            _ => return,
+        };
+        if state.board()[mover].as_ref()[pit] != 1 {
+            return;
+        }
+
+        let opposite = Landing::Pit(mover.other(), state.pits() - pit - 1);
+        if !self.options.empty_capture && state.stones(opposite) == 0 {
+            return;
+        }
+
+        let captured = state.take(Landing::Pit(mover, pit)) + state.take(opposite);
+        state.add(Landing::Store(mover), captured);
+    }
+
+    #[inline]
+    fn extra_turn<T: Mancala>(&self, state: &T, landing: Landing) -> bool {
+        landing == Landing::Store(state.current_turn())
+    }
+
+    fn validate<T: Mancala>(&self, state: &T) -> Result<(), StateError> {
+        // Either both sides have stones left, or the game is over and both are empty.
+        let [one, two] = [Player::One, Player::Two]
+            .map(|p| state.board()[p].as_ref().iter().any(|pit| *pit > 0));
+        if one != two {
+            return Err(StateError::Unswept);
+        }
+        Ok(())
+    }
+
+    fn end_of_game<T: Mancala>(&self, state: &mut Editor<T>) {
+        // Detect completed game.
+        let owner = if state.board()[Player::One].as_ref().iter().sum::<usize>() == 0 {
+            Player::Two
+        } else if state.board()[Player::Two].as_ref().iter().sum::<usize>() == 0 {
+            Player::One
+        } else {
+            return;
+        };
+
+        // If game is finished, the remaining stones are captured by the player
+        // specified in the options.
+        let recipient = match self.options.sweep {
+            Sweep::Owner => owner,
+            Sweep::Emptied => owner.other(),
+        };
+        let remaining: usize = (0..state.pits())
+            .map(|i| state.take(Landing::Pit(owner, i)))
+            .sum();
+        state.add(Landing::Store(recipient), remaining);
+    }
+
+    /// Written as `kalah:` followed by the options, e.g., `kalah:y,owner,y`.
+    fn notation(&self) -> Option<String> {
+        Some(format!("kalah:{}", options_notation(self.options)))
+    }
+
+    fn from_notation(field: &str) -> Option<Self> {
+        parse_options(field.strip_prefix("kalah:")?).map(Self::new)
+    }
+}
+
+/// Writes the options as used by the compact notation: `empty_capture`, `sweep`,
+/// and `pie_rule`, separated by commas, where the flags are written as `y` or
+/// `n`, and the sweep as `owner` or `emptied` (e.g., `y,owner,y`).
+pub(super) fn options_notation(options: KalahOptions) -> String {
+    let flag = |b: bool| if b { "y" } else { "n" };
+    let sweep = match options.sweep {
+        Sweep::Owner => "owner",
+        Sweep::Emptied => "emptied",
+    };
+    format!(
+        "{},{},{}",
+        flag(options.empty_capture),
+        sweep,
+        flag(options.pie_rule)
+    )
+}
+
+/// Parses options written by [`options_notation`].
+pub(super) fn parse_options(s: &str) -> Option<KalahOptions> {
+    let flag = |s: &str| match s {
+        "y" => Some(true),
+        "n" => Some(false),
+        _ => None,
+    };
+    let [empty_capture, sweep, pie_rule] = s.split(',').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let sweep = match sweep {
+        "owner" => Sweep::Owner,
+        "emptied" => Sweep::Emptied,
+        _ => return None,
+    };
+    Some(KalahOptions {
+        empty_capture: flag(empty_capture)?,
+        sweep,
+        pie_rule: flag(pie_rule)?,
+    })
+}
+
+/// Picks up the stones at `origin`, and distributes them on behalf of `mover`,
+/// skipping the opponent's store. Returns the location of the last stone.
+pub(super) fn sow_from<T: Mancala>(
+    state: &mut Editor<T>,
+    mover: Player,
+    origin: Landing,
+) -> Landing {
+    let pits = state.pits();
+
+    // Collect the stones to distribute.
+    let mut stones = state.take(origin);
+
+    // Distribute one stone per location, skipping the opponent's store.
+    let mut landing = origin;
+    while stones > 0 {
+        landing = landing.next(mover, pits);
+        state.add(landing, 1);
+        stones -= 1;
+    }
+
+    landing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Move, MoveError, Oware};
+    use crate::minimax::ZobristData;
+    use std::collections::HashSet;
+
+    fn state(board: [[usize; 4]; 2], options: KalahOptions) -> GameState<4> {
+        GameState::from_arr(board, 0, 0, Player::One, 1, false).with_rules(Kalah::new(options))
+    }
+
+    #[test]
+    fn empty_capture() {
+        // The last stone lands in an empty pit opposite another empty pit.
+        let board = [[1, 0, 0, 1], [1, 1, 0, 1]];
+        let next = state(board, KalahOptions::default())
+            .make_move_pit(1)
+            .unwrap();
+        assert_eq!(next.board(), &[[0, 0, 0, 1], [1, 1, 0, 1]]);
+        assert_eq!(next.stores(), &[1, 0]);
+
+        let options = KalahOptions {
+            empty_capture: false,
+            ..Default::default()
+        };
+        let next = state(board, options).make_move_pit(1).unwrap();
+        assert_eq!(next.board(), &[[0, 1, 0, 1], [1, 1, 0, 1]]);
+        assert_eq!(next.stores(), &[0, 0]);
+    }
+
+    #[test]
+    fn sweep() {
+        // Player 1 runs out of stones by moving into their store.
+        let board = [[0, 0, 0, 1], [1, 1, 1, 1]];
+        let next = state(board, KalahOptions::default())
+            .make_move_pit(4)
+            .unwrap();
+        assert!(next.is_over());
+        assert_eq!(next.board(), &[[0; 4]; 2]);
+        assert_eq!(next.stores(), &[1, 4]);
+
+        let options = KalahOptions {
+            sweep: Sweep::Emptied,
+            ..Default::default()
+        };
+        let next = state(board, options).make_move_pit(4).unwrap();
+        assert!(next.is_over());
+        assert_eq!(next.board(), &[[0; 4]; 2]);
+        assert_eq!(next.stores(), &[5, 0]);
+    }
+
+    #[test]
+    fn pie_rule() {
+        let start = GameState::<6>::default();
+        let next = start.make_move_pit(1).unwrap();
+        assert_eq!(next.current_turn(), Player::Two);
+        assert!(next.valid_moves().contains(&Move::Swap));
+        assert!(next.make_move(Move::Swap).is_ok());
+
+        let options = KalahOptions {
+            pie_rule: false,
+            ..Default::default()
+        };
+        let next = start
+            .with_rules(Kalah::new(options))
+            .make_move_pit(1)
+            .unwrap();
+        assert!(!next.valid_moves().contains(&Move::Swap));
+        assert_eq!(next.make_move(Move::Swap), Err(MoveError::SwapNotAllowed));
+    }
+
+    #[test]
+    fn options_change_rules_key() {
+        let mut keys = HashSet::new();
+        for empty_capture in [true, false] {
+            for sweep in [Sweep::Owner, Sweep::Emptied] {
+                for pie_rule in [true, false] {
+                    let options = KalahOptions {
+                        empty_capture,
+                        sweep,
+                        pie_rule,
+                    };
+                    let state = GameState::<6>::default().with_rules(Kalah::new(options));
+                    keys.insert(ZobristData::for_state_like(&state, 0).rules_key());
+                }
+            }
+        }
+        assert_eq!(keys.len(), 8);
+
+        let oware = GameState::<6>::default().with_rules(Oware::default());
+        assert!(!keys.contains(&ZobristData::for_state_like(&oware, 0).rules_key()));
+    }
+}
@@ -0,0 +1,478 @@
+//! The "Oware" (Abapa) rule set.
+
+use super::{Editor, Landing, RuleSet};
+use crate::game::mancala::{Mancala, Move, Player};
+
+/// Determines what happens when a move would capture every stone on the
+/// opponent's side of the board (a "grand slam").
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GrandSlam {
+    /// The move may be played, but nothing is captured.
+    #[default]
+    NoCapture,
+    /// The move may be played, and every stone is captured.
+    Allowed,
+    /// The move may not be played, unless no other moves are available
+    /// (in which case it is played without capturing).
+    Forbidden,
+}
+
+/// Gameplay rules following the "Abapa" variant of Oware.
+///
+/// - Stones are sown counter-clockwise around the pits only; the stores are
+///   never sown into, and the pit being emptied is skipped when sowing 12 or
+///   more stones (i.e., one or more full laps).
+/// - If the last stone lands in one of the opponent's pits, bringing its total
+///   to 2 or 3, those stones are captured, along with any consecutive preceding
+///   pits on the opponent's side that also contain 2 or 3 stones.
+/// - If the opponent has no stones, the player must make a move which gives
+///   them stones ("feeding"). If no such move exists, the player captures all
+///   stones on their side and the game ends.
+/// - The game ends once a player has captured more than half of the stones,
+///   once both players have captured exactly half, or once the current player
+///   has no valid moves.
+/// - There are no extra turns, and the swap move is never allowed.
+///
+/// Since the same positions can repeat indefinitely (where players would
+/// normally agree to end the game), the number of plies is always limited
+/// (see [`max_plies`][Self::max_plies]). Once the limit is reached, each player
+/// captures the stones on their side and the game ends.
+///
+/// The ply is not part of the Zobrist hash, so transposition table entries
+/// may be inaccurate for searches which reach the ply limit.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Oware {
+    grand_slam: GrandSlam,
+    max_plies: usize,
+}
+
+impl Default for Oware {
+    /// The default Oware rule set ends the game after 300 plies.
+    fn default() -> Self {
+        Self {
+            grand_slam: GrandSlam::default(),
+            max_plies: 300,
+        }
+    }
+}
+
+impl Oware {
+    /// Create a new Oware rule set using the supplied grand slam handling.
+    pub fn new(grand_slam: GrandSlam) -> Self {
+        Self {
+            grand_slam,
+            ..Self::default()
+        }
+    }
+
+    /// Consume and return the current rule set, ending the game once the move
+    /// at ply `max_plies` has been made.
+    pub fn with_max_plies(mut self, max_plies: usize) -> Self {
+        self.max_plies = max_plies;
+        self
+    }
+
+    /// Returns the grand slam handling used by the rule set.
+    #[inline]
+    pub fn grand_slam(&self) -> GrandSlam {
+        self.grand_slam
+    }
+
+    /// Returns the ply of the last move which may be made before the game ends.
+    #[inline]
+    pub fn max_plies(&self) -> usize {
+        self.max_plies
+    }
+
+    /// Returns the moves (selected pits, starting from 1) available to `player`,
+    /// taking the feeding and grand slam rules into account.
+    fn moves_for<T: Mancala>(&self, state: &T, player: Player) -> Vec<Move> {
+        let moves: Vec<Move> = playable_pits(state, player)
+            .map(|i| Move::Pit(i + 1))
+            .collect();
+
+        if self.grand_slam != GrandSlam::Forbidden {
+            return moves;
+        }
+
+        // Remove any grand slams, as long as at least one move remains.
+        let legal: Vec<Move> = moves
+            .iter()
+            .filter(|m| !self.is_grand_slam(state, player, usize::from(**m) - 1))
+            .copied()
+            .collect();
+        if legal.is_empty() { moves } else { legal }
+    }
+
+    /// Determines whether sowing the pit (starting from 0) would capture all
+    /// of the opponent's stones.
+    ///
+    /// The opponent's pits after sowing are computed directly from the number
+    /// of stones sown, rather than by sowing a copy of the state: every pit
+    /// except the origin receives one stone per full lap, and the pits up to
+    /// the last stone receive one more.
+    fn is_grand_slam<T: Mancala>(&self, state: &T, player: Player, pit: usize) -> bool {
+        let pits = state.pits();
+        let stones = state.board()[player].as_ref()[pit];
+
+        // Positions are counted from 1 (the pit after the origin) to `cycle`
+        // (the pit before it), so the opponent's pits are at `first..first + pits`.
+        let cycle = 2 * pits - 1;
+        let (laps, rest) = (stones / cycle, stones % cycle);
+        let last = if rest == 0 { cycle } else { rest };
+        let first = pits - pit;
+        if stones == 0 || last < first || last >= first + pits {
+            return false;
+        }
+
+        let opponent = state.board()[player.other()].as_ref();
+        let after = |i: usize| opponent[i] + laps + usize::from(first + i <= rest);
+
+        // Find the first pit captured by walking backward from the last stone.
+        let last = last - first;
+        let Some(lowest) = (0..=last)
+            .rev()
+            .take_while(|i| matches!(after(*i), 2 | 3))
+            .last()
+        else {
+            return false;
+        };
+        (0..lowest).chain(last + 1..pits).all(|i| after(i) == 0)
+    }
+}
+
+impl RuleSet for Oware {
+    #[inline]
+    fn swap_allowed<T: Mancala>(&self, _state: &T) -> bool {
+        false
+    }
+
+    fn valid_moves<T: Mancala>(&self, state: &T) -> Vec<Move> {
+        // The game is also over if no moves are available, in which case the
+        // list of moves is empty anyway.
+        if majority_captured(state) {
+            return Vec::new();
+        }
+        self.moves_for(state, state.current_turn())
+    }
+
+    fn is_valid_move<T: Mancala>(&self, state: &T, m: Move) -> bool {
+        let (Move::Pit(pit), false) = (m, majority_captured(state)) else {
+            return false;
+        };
+        let player = state.current_turn();
+        if !playable_pits(state, player).any(|i| i + 1 == pit) {
+            return false;
+        }
+
+        // A grand slam may only be played if every other move is one too.
+        self.grand_slam != GrandSlam::Forbidden
+            || !self.is_grand_slam(state, player, pit - 1)
+            || playable_pits(state, player).all(|i| self.is_grand_slam(state, player, i))
+    }
+
+    #[inline]
+    fn sow<T: Mancala>(&self, state: &mut Editor<T>, pit: usize) -> Landing {
+        sow_from(state, state.current_turn(), pit)
+    }
+
+    fn capture<T: Mancala>(&self, state: &mut Editor<T>, landing: Landing) {
+        let mover = state.current_turn();
+        let opponent = mover.other();
+        let captured = capturable(&**state, mover, landing);
+        if captured.is_empty() {
+            return;
+        }
+
+        // Handle grand slams according to the configuration.
+        let grand_slam = state.board()[opponent]
+            .as_ref()
+            .iter()
+            .enumerate()
+            .all(|(i, s)| *s == 0 || captured.contains(&i));
+        if grand_slam && self.grand_slam != GrandSlam::Allowed {
+            return;
+        }
+
+        let stones: usize = captured
+            .into_iter()
+            .map(|i| state.take(Landing::Pit(opponent, i)))
+            .sum();
+        state.add(Landing::Store(mover), stones);
+    }
+
+    #[inline]
+    fn extra_turn<T: Mancala>(&self, _state: &T, _landing: Landing) -> bool {
+        false
+    }
+
+    fn end_of_game<T: Mancala>(&self, state: &mut Editor<T>) {
+        if majority_captured(&**state) {
+            return;
+        }
+
+        // If the next player cannot move (because they have no stones, or
+        // cannot feed their opponent), or the ply limit has been reached, each
+        // player captures their own stones.
+        let next = state.current_turn().other();
+        if state.ply() >= self.max_plies || playable_pits(&**state, next).next().is_none() {
+            for player in [Player::One, Player::Two] {
+                let remaining: usize = (0..state.pits())
+                    .map(|i| state.take(Landing::Pit(player, i)))
+                    .sum();
+                state.add(Landing::Store(player), remaining);
+            }
+        }
+    }
+
+    fn is_over<T: Mancala>(&self, state: &T) -> bool {
+        // The grand slam rule never removes every move, so it does not need to
+        // be checked here.
+        majority_captured(state) || playable_pits(state, state.current_turn()).next().is_none()
+    }
+
+    /// Written as `oware:` followed by the grand slam handling (`nocapture`,
+    /// `allowed`, or `forbidden`) and the ply limit, e.g., `oware:nocapture,300`.
+    /// When parsing, the ply limit may be omitted to use the default.
+    fn notation(&self) -> Option<String> {
+        let grand_slam = match self.grand_slam {
+            GrandSlam::NoCapture => "nocapture",
+            GrandSlam::Allowed => "allowed",
+            GrandSlam::Forbidden => "forbidden",
+        };
+        Some(format!("oware:{},{}", grand_slam, self.max_plies))
+    }
+
+    fn from_notation(field: &str) -> Option<Self> {
+        let field = field.strip_prefix("oware:")?;
+        let (grand_slam, max_plies) = match field.split_once(',') {
+            Some((grand_slam, max_plies)) => (grand_slam, max_plies.parse().ok()?),
+            None => (field, Self::default().max_plies),
+        };
+        let grand_slam = match grand_slam {
+            "nocapture" => GrandSlam::NoCapture,
+            "allowed" => GrandSlam::Allowed,
+            "forbidden" => GrandSlam::Forbidden,
+            _ => return None,
+        };
+        Some(Self::new(grand_slam).with_max_plies(max_plies))
+    }
+}
+
+/// Returns the pits (starting from 0) on `player`'s side which contain stones,
+/// and which also give the opponent stones if they have none (i.e., the moves
+/// available before the grand slam rule is applied).
+fn playable_pits<T: Mancala>(state: &T, player: Player) -> impl Iterator<Item = usize> + '_ {
+    let pits = state.pits();
+    let must_feed = state.board()[player.other()]
+        .as_ref()
+        .iter()
+        .all(|s| *s == 0);
+    state.board()[player]
+        .as_ref()
+        .iter()
+        .enumerate()
+        .filter(move |(i, s)| **s > 0 && (!must_feed || **s >= pits - i))
+        .map(|(i, _)| i)
+}
+
+/// Determines whether a player has captured more than half of the stones,
+/// or whether both players have captured exactly half.
+fn majority_captured<T: Mancala>(state: &T) -> bool {
+    let total = state.total_stones();
+    let [one, two] = *state.stores();
+    one * 2 > total || two * 2 > total || (one == two && one * 2 == total)
+}
+
+/// Sows the stones in `player`'s pit (starting from 0), skipping the stores
+/// and the origin pit. Returns the location of the last stone.
+fn sow_from<T: Mancala>(state: &mut Editor<T>, player: Player, pit: usize) -> Landing {
+    let pits = state.pits();
+
+    let mut stones = state.take(Landing::Pit(player, pit));
+
+    let (mut side, mut i) = (player, pit);
+    while stones > 0 {
+        // Advance to the next pit, ignoring both stores.
+        if i + 1 < pits {
+            i += 1;
+        } else {
+            side = side.other();
+            i = 0;
+        }
+
+        // Skip the origin pit on laps of 12 or more stones.
+        if side == player && i == pit {
+            continue;
+        }
+
+        state.add(Landing::Pit(side, i), 1);
+        stones -= 1;
+    }
+
+    Landing::Pit(side, i)
+}
+
+/// Returns the opponent's pits (starting from 0) that would be captured by
+/// `player` after their last stone landed at `landing`.
+fn capturable<T: Mancala>(state: &T, player: Player, landing: Landing) -> Vec<usize> {
+    let mut captured = Vec::new();
+    let Landing::Pit(side, last) = landing else {
+        return captured;
+    };
+    if side == player {
+        return captured;
+    }
+
+    // Walk backward along the opponent's row while pits contain 2 or 3 stones.
+    for i in (0..=last).rev() {
+        match state.board()[side].as_ref()[i] {
+            2 | 3 => captured.push(i),
+            _ => break,
+        }
+    }
+    captured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use rand::rngs::StdRng;
+    use rand::{RngExt, SeedableRng};
+
+    /// Sows a copy of the state to determine whether the move is a grand slam.
+    fn is_grand_slam_by_sowing<T: Mancala>(state: &T, player: Player, pit: usize) -> bool {
+        let mut after = state.clone();
+        let landing = sow_from(&mut Editor::new(&mut after, None), player, pit);
+        let captured = capturable(&after, player, landing);
+        !captured.is_empty()
+            && after.board()[player.other()]
+                .as_ref()
+                .iter()
+                .enumerate()
+                .all(|(i, s)| *s == 0 || captured.contains(&i))
+    }
+
+    fn random_state(rng: &mut StdRng) -> GameState<6, Oware> {
+        let mut board = [[0; 6]; 2];
+        for side in board.iter_mut() {
+            for pit in side.iter_mut() {
+                // Mostly small counts (to produce captures), with some laps.
+                *pit = match rng.random_range(0..10) {
+                    0 => rng.random_range(11..30),
+                    _ => rng.random_range(0..4),
+                };
+            }
+        }
+        let player = if rng.random_bool(0.5) {
+            Player::One
+        } else {
+            Player::Two
+        };
+        GameState::from_arr(board, 0, 0, player, 1, true)
+            .with_rules(Oware::new(GrandSlam::Forbidden))
+    }
+
+    #[test]
+    fn grand_slam_matches_sowing() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut grand_slams = 0;
+        for _ in 0..20_000 {
+            let state = random_state(&mut rng);
+            let player = state.current_turn();
+            for pit in 0..6 {
+                if state.board()[player][pit] == 0 {
+                    continue;
+                }
+                let expected = is_grand_slam_by_sowing(&state, player, pit);
+                assert_eq!(
+                    state.rules().is_grand_slam(&state, player, pit),
+                    expected,
+                    "{:?} pit {}",
+                    state.board(),
+                    pit
+                );
+                grand_slams += usize::from(expected);
+            }
+        }
+        assert!(grand_slams > 0);
+    }
+
+    #[test]
+    fn is_valid_move_matches_valid_moves() {
+        let mut rng = StdRng::seed_from_u64(13);
+        for _ in 0..20_000 {
+            let state = random_state(&mut rng);
+            let moves = state.valid_moves();
+            for m in (0..=7).map(Move::Pit).chain([Move::Swap]) {
+                assert_eq!(state.is_valid_move(m), moves.contains(&m), "{:?}", m);
+            }
+        }
+    }
+
+    #[test]
+    fn is_over_matches_valid_moves() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..20_000 {
+            let state = random_state(&mut rng);
+            assert_eq!(state.is_over(), state.valid_moves().is_empty());
+        }
+    }
+
+    #[test]
+    fn ply_limit_ends_game() {
+        let rules = Oware::default().with_max_plies(10);
+        let mut state = GameState::<6>::default().with_rules(rules);
+        while state.ply() < 10 {
+            assert!(!state.is_over());
+            state = state.make_move_rand().unwrap().0;
+        }
+        assert!(!state.is_over());
+
+        // Each player captures the stones on their side after the last move.
+        let mut expected = *state.stores();
+        for player in [Player::One, Player::Two] {
+            expected[player] += state.board()[player].iter().sum::<usize>();
+        }
+        let last = state.valid_moves()[0];
+        state = state.make_move(last).unwrap();
+        assert!(state.is_over());
+        assert!(state.valid_moves().is_empty());
+        assert_eq!(state.board(), &[[0; 6]; 2]);
+        assert_eq!(state.ply(), 11);
+
+        // Random games always end, even if positions repeat.
+        let mut rng = StdRng::seed_from_u64(3);
+        for grand_slam in [
+            GrandSlam::NoCapture,
+            GrandSlam::Allowed,
+            GrandSlam::Forbidden,
+        ] {
+            for _ in 0..100 {
+                let mut state = GameState::<6>::default().with_rules(Oware::new(grand_slam));
+                while !state.is_over() {
+                    let moves = state.valid_moves();
+                    state = state
+                        .make_move(moves[rng.random_range(0..moves.len())])
+                        .unwrap();
+                }
+                assert!(state.ply() <= 301);
+            }
+        }
+    }
+
+    #[test]
+    fn notation_ply_limit() {
+        let rules = Oware::new(GrandSlam::Allowed).with_max_plies(120);
+        assert_eq!(rules.notation().unwrap(), "oware:allowed,120");
+        assert_eq!(Oware::from_notation("oware:allowed,120"), Some(rules));
+        assert_eq!(
+            Oware::from_notation("oware:allowed"),
+            Some(Oware::new(GrandSlam::Allowed))
+        );
+        assert_eq!(Oware::from_notation("oware:allowed,"), None);
+    }
+}
@@ -0,0 +1,198 @@
+//! The "Relay" rule set, used by multi-lap variants such as Dakon and Congkak.
+
+use super::kalah::{options_notation, parse_options, sow_from};
+use super::{Editor, Kalah, KalahOptions, Landing, RuleSet};
+use crate::game::error::StateError;
+use crate::game::mancala::Mancala;
+
+/// Gameplay rules following the "Kalah" variant of Mancala, but with relay
+/// (multi-lap) sowing, as in variants such as Dakon and Congkak.
+///
+/// If the last stone of a lap lands in an occupied pit (on either side of the
+/// board), the player picks up every stone in that pit and continues sowing.
+/// Sowing stops once the last stone lands in a store or an empty pit, or once
+/// the lap limit has been reached. Captures, extra turns, and the end of the
+/// game are otherwise identical to [`Kalah`], and are configured using the same
+/// [`KalahOptions`].
+///
+/// Since relay sowing can cycle indefinitely for some positions, the number
+/// of laps per move is always limited (see [`max_laps`][Self::max_laps]).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Relay {
+    max_laps: usize,
+    kalah: Kalah,
+}
+
+impl Default for Relay {
+    /// The default relay rule set allows up to 64 laps per move.
+    fn default() -> Self {
+        Self {
+            max_laps: 64,
+            kalah: Kalah::default(),
+        }
+    }
+}
+
+impl Relay {
+    /// Create a new relay rule set which sows at most `max_laps` laps per move.
+    ///
+    /// A limit of `1` is equivalent to single-lap [`Kalah`] sowing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_laps` is `0`, since every move sows at least one lap.
+    pub fn new(max_laps: usize) -> Self {
+        assert!(
+            max_laps > 0,
+            "Relay sowing requires a lap limit of at least 1"
+        );
+        Self {
+            max_laps,
+            kalah: Kalah::default(),
+        }
+    }
+
+    /// Consume and return the current rule set, using the supplied options
+    /// for captures, the end of the game, and the swap move.
+    pub fn with_options(mut self, options: KalahOptions) -> Self {
+        self.kalah = Kalah::new(options);
+        self
+    }
+
+    /// Returns the maximum number of laps sown per move.
+    #[inline]
+    pub fn max_laps(&self) -> usize {
+        self.max_laps
+    }
+
+    /// Returns the options used by the rule set.
+    #[inline]
+    pub fn options(&self) -> KalahOptions {
+        self.kalah.options()
+    }
+}
+
+impl RuleSet for Relay {
+    #[inline]
+    fn swap_allowed<T: Mancala>(&self, state: &T) -> bool {
+        self.kalah.swap_allowed(state)
+    }
+
+    fn sow<T: Mancala>(&self, state: &mut Editor<T>, pit: usize) -> Landing {
+        let mover = state.current_turn();
+        let mut landing = sow_from(state, mover, Landing::Pit(mover, pit));
+
+        // Keep picking up occupied pits until the last stone lands somewhere
+        // empty, or the lap limit is reached.
+        for _ in 1..self.max_laps {
+            match landing {
+                Landing::Pit(..) if state.stones(landing) > 1 => {
+                    landing = sow_from(state, mover, landing);
+                }
+                _ => break,
+            }
+        }
+
+        landing
+    }
+
+    #[inline]
+    fn capture<T: Mancala>(&self, state: &mut Editor<T>, landing: Landing) {
+        self.kalah.capture(state, landing)
+    }
+
+    #[inline]
+    fn extra_turn<T: Mancala>(&self, state: &T, landing: Landing) -> bool {
+        self.kalah.extra_turn(state, landing)
+    }
+
+    #[inline]
+    fn validate<T: Mancala>(&self, state: &T) -> Result<(), StateError> {
+        self.kalah.validate(state)
+    }
+
+    #[inline]
+    fn end_of_game<T: Mancala>(&self, state: &mut Editor<T>) {
+        self.kalah.end_of_game(state)
+    }
+
+    /// Written as `relay:` followed by the lap limit and the options, e.g.,
+    /// `relay:64,y,owner,y`.
+    fn notation(&self) -> Option<String> {
+        Some(format!(
+            "relay:{},{}",
+            self.max_laps,
+            options_notation(self.options())
+        ))
+    }
+
+    fn from_notation(field: &str) -> Option<Self> {
+        let (max_laps, options) = field.strip_prefix("relay:")?.split_once(',')?;
+        let max_laps = max_laps.parse().ok().filter(|laps| *laps > 0)?;
+        Some(Self::new(max_laps).with_options(parse_options(options)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Player};
+
+    fn state(board: [[usize; 4]; 2], max_laps: usize) -> GameState<4, Relay> {
+        GameState::from_arr(board, 0, 0, Player::One, 1, false).with_rules(Relay::new(max_laps))
+    }
+
+    #[test]
+    fn multi_lap() {
+        // The last stone lands in an occupied pit, which is sown in turn, and the
+        // second lap ends in an empty pit (capturing the opposite pit).
+        let next = state([[1, 1, 0, 0], [2, 2, 2, 2]], 64)
+            .make_move_pit(1)
+            .unwrap();
+        assert_eq!(next.board(), &[[0, 0, 1, 0], [0, 2, 2, 2]]);
+        assert_eq!(next.stores(), &[3, 0]);
+        assert_eq!(next.current_turn(), Player::Two);
+
+        // Single-lap sowing stops in the occupied pit instead.
+        let next = state([[1, 1, 0, 0], [2, 2, 2, 2]], 1)
+            .make_move_pit(1)
+            .unwrap();
+        assert_eq!(next.board(), &[[0, 2, 0, 0], [2, 2, 2, 2]]);
+        assert_eq!(next.stores(), &[0, 0]);
+    }
+
+    #[test]
+    fn lap_limit() {
+        let start = state([[1, 1, 0, 1], [0, 3, 3, 3]], 3);
+
+        // The third lap passes the store and ends on the opponent's side.
+        let next = start.make_move_pit(1).unwrap();
+        assert_eq!(next.board(), &[[0, 0, 1, 0], [1, 3, 3, 3]]);
+        assert_eq!(next.stores(), &[1, 0]);
+
+        // With a limit of two laps, sowing stops in the occupied pit.
+        let next = start.with_rules(Relay::new(2)).make_move_pit(1).unwrap();
+        assert_eq!(next.board(), &[[0, 0, 1, 2], [0, 3, 3, 3]]);
+        assert_eq!(next.stores(), &[0, 0]);
+        assert_eq!(next.current_turn(), Player::Two);
+    }
+
+    #[test]
+    fn store_landing() {
+        // The second lap ends in the mover's store, so they move again.
+        let next = state([[0, 1, 1, 0], [2, 2, 2, 2]], 64)
+            .make_move_pit(2)
+            .unwrap();
+        assert_eq!(next.board(), &[[0, 0, 0, 1], [2, 2, 2, 2]]);
+        assert_eq!(next.stores(), &[1, 0]);
+        assert_eq!(next.current_turn(), Player::One);
+        assert_eq!(next.ply(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_laps() {
+        Relay::new(0);
+    }
+}
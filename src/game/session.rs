@@ -0,0 +1,363 @@
+//! Game sessions which track the full history of a game of Mancala.
+
+use super::error::{MoveError, RecordError};
+use super::mancala::{Mancala, Move, Player};
+use super::record::{GameRecord, RecordedMove};
+
+/// Summary statistics for the moves played so far in a [`Game`].
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameStats {
+    /// The number of moves made by each player.
+    pub moves: [usize; 2],
+    /// The number of moves made by each player which resulted in an extra turn.
+    pub extra_turns: [usize; 2],
+    /// The number of stones each player added to their store.
+    pub stones_gained: [usize; 2],
+    /// Whether the swap move was played.
+    pub swapped: bool,
+    /// The largest number of times any position occurred.
+    pub max_repetitions: usize,
+}
+
+/// A game session, which stores every state and move played since the initial
+/// state. Moves can be taken back with [`undo`][Self::undo], and replayed with
+/// [`redo`][Self::redo] or [`jump_to`][Self::jump_to], until a different move is
+/// played.
+///
+/// Moves are stored as [`RecordedMove`]s, so any annotations supplied when
+/// playing them are kept when the session is converted into a [`GameRecord`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game<T: Mancala> {
+    states: Vec<T>,
+    moves: Vec<RecordedMove>,
+    position: usize,
+}
+
+impl<T: Mancala> Game<T> {
+    /// Create a new session starting from the supplied state.
+    pub fn new(initial_state: T) -> Self {
+        Self {
+            states: vec![initial_state],
+            moves: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Create a new session by replaying every move in a record. The session
+    /// is positioned after the last move. Fails if any move is illegal.
+    pub fn from_record(record: &GameRecord<T>) -> Result<Self, RecordError> {
+        let mut game = Self::new(record.initial_state().clone());
+        for (i, m) in record.moves().iter().enumerate() {
+            game.play(m.clone())
+                .map_err(|error| RecordError::IllegalMove {
+                    number: i + 1,
+                    error,
+                })?;
+        }
+        Ok(game)
+    }
+
+    /// Create a record of the moves played up to the current position, using the
+    /// outcome of the current state as the result.
+    pub fn to_record(&self) -> GameRecord<T> {
+        let mut record = GameRecord::new(self.initial_state().clone());
+        for m in self.moves() {
+            record.push(m.clone());
+        }
+        record.set_result(self.state().outcome());
+        record
+    }
+
+    /// Provides a reference to the current state.
+    #[inline]
+    pub fn state(&self) -> &T {
+        &self.states[self.position]
+    }
+
+    /// Provides a reference to the initial state.
+    #[inline]
+    pub fn initial_state(&self) -> &T {
+        &self.states[0]
+    }
+
+    /// Returns the number of moves played to reach the current state.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the number of moves stored, including any which have been
+    /// undone but not yet replaced.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Returns whether the session does not store any moves.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Provides a slice of the moves played to reach the current state.
+    #[inline]
+    pub fn moves(&self) -> &[RecordedMove] {
+        &self.moves[..self.position]
+    }
+
+    /// Provides a slice of every state up to and including the current state.
+    #[inline]
+    pub fn states(&self) -> &[T] {
+        &self.states[..=self.position]
+    }
+
+    /// Returns whether there is a move to undo.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    /// Returns whether there is an undone move to replay.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        self.position < self.moves.len()
+    }
+
+    /// Plays a move (with or without annotations) from the current state, and
+    /// returns the new state.
+    ///
+    /// If the move matches the next undone move, the remaining undone moves are
+    /// kept. Otherwise, they are discarded.
+    pub fn play(&mut self, m: impl Into<RecordedMove>) -> Result<&T, MoveError> {
+        let m = m.into();
+        if self.can_redo() && self.moves[self.position].selection == m.selection {
+            self.moves[self.position] = m;
+            self.position += 1;
+            return Ok(self.state());
+        }
+
+        let new_state = self.state().make_move(m.selection)?;
+        self.states.truncate(self.position + 1);
+        self.moves.truncate(self.position);
+        self.states.push(new_state);
+        self.moves.push(m);
+        self.position += 1;
+        Ok(self.state())
+    }
+
+    /// Plays a random valid move from the current state, and returns it.
+    pub fn play_rand(&mut self) -> Result<Move, MoveError> {
+        let (_, m) = self.state().make_move_rand()?;
+        self.play(m)?;
+        Ok(m)
+    }
+
+    /// Takes back the last move played, and returns it. Returns [`None`] if no
+    /// moves have been played.
+    pub fn undo(&mut self) -> Option<Move> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.position -= 1;
+        Some(self.moves[self.position].selection)
+    }
+
+    /// Replays the last move undone, and returns it. Returns [`None`] if there
+    /// are no undone moves.
+    pub fn redo(&mut self) -> Option<Move> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.position += 1;
+        Some(self.moves[self.position - 1].selection)
+    }
+
+    /// Moves to the state reached after the supplied number of moves, undoing
+    /// or replaying moves as necessary. Returns [`None`] (without changing the
+    /// current state) if fewer moves are stored.
+    pub fn jump_to(&mut self, position: usize) -> Option<&T> {
+        if position > self.moves.len() {
+            return None;
+        }
+        self.position = position;
+        Some(self.state())
+    }
+
+    /// Moves to the state with the supplied ply, undoing or replaying moves as
+    /// necessary. Returns [`None`] (without changing the current state) if no
+    /// stored state has that ply.
+    pub fn jump_to_ply(&mut self, ply: usize) -> Option<&T> {
+        let position = ply.checked_sub(self.initial_state().ply())?;
+        self.jump_to(position)
+    }
+
+    /// Returns the most recent position (i.e., number of moves played) before
+    /// the current one at which it was the supplied player's turn.
+    pub fn last_turn_of(&self, player: Player) -> Option<usize> {
+        (0..self.position)
+            .rev()
+            .find(|i| self.states[*i].current_turn() == player)
+    }
+
+    /// Returns the number of times the current position has occurred so far,
+    /// including the current occurrence. Positions are considered equal if
+    /// they have the same board, stores, current player, and swap availability
+    /// (i.e., the ply is ignored).
+    pub fn repetitions(&self) -> usize {
+        let current = self.state();
+        self.states()
+            .iter()
+            .filter(|s| same_position(*s, current))
+            .count()
+    }
+
+    /// Computes summary statistics for the moves played up to the current state.
+    pub fn stats(&self) -> GameStats {
+        let mut stats = GameStats::default();
+        let states = self.states();
+
+        for ((before, after), m) in states.iter().zip(&states[1..]).zip(self.moves()) {
+            let mover = before.current_turn();
+            stats.moves[mover] += 1;
+            if m.selection == Move::Swap {
+                stats.swapped = true;
+            }
+            if after.current_turn() == mover && !after.is_over() {
+                stats.extra_turns[mover] += 1;
+            }
+            for player in [Player::One, Player::Two] {
+                stats.stones_gained[player] +=
+                    after.score(player).saturating_sub(before.score(player));
+            }
+        }
+
+        stats.max_repetitions = states
+            .iter()
+            .map(|s| states.iter().filter(|o| same_position(*o, s)).count())
+            .max()
+            .unwrap_or(0);
+
+        stats
+    }
+}
+
+/// Helper function to determine whether two states represent the same position,
+/// ignoring the ply.
+fn same_position<T: Mancala>(a: &T, b: &T) -> bool {
+    [Player::One, Player::Two]
+        .iter()
+        .all(|p| a.board()[*p].as_ref() == b.board()[*p].as_ref())
+        && a.stores() == b.stores()
+        && a.current_turn() == b.current_turn()
+        && a.p2_moved() == b.p2_moved()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    /// Plays an extra turn for Player 1, a normal move, and Player 2's swap.
+    fn sample_game() -> Game<GameState<6>> {
+        let mut game = Game::new(GameState::default());
+        for m in [Move::Pit(3), Move::Pit(1), Move::Swap] {
+            game.play(m).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut game = sample_game();
+        let states = game.states().to_vec();
+        assert_eq!((game.position(), game.len()), (3, 3));
+        assert!(game.can_undo() && !game.can_redo());
+        assert_eq!(game.redo(), None);
+
+        assert_eq!(game.undo(), Some(Move::Swap));
+        assert_eq!(game.undo(), Some(Move::Pit(1)));
+        assert_eq!(game.state(), &states[1]);
+        assert_eq!(game.moves().len(), 1);
+        assert_eq!(game.len(), 3);
+        assert_eq!(game.redo(), Some(Move::Pit(1)));
+        assert_eq!(game.state(), &states[2]);
+
+        assert_eq!(game.jump_to(0), Some(&states[0]));
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.jump_to(4), None);
+        assert_eq!(game.position(), 0);
+        assert_eq!(game.jump_to_ply(4), Some(&states[3]));
+        assert_eq!(game.jump_to_ply(0), None);
+        assert_eq!(game.states(), &states[..]);
+
+        // Replaying the next undone move keeps the moves after it.
+        game.jump_to(1);
+        assert_eq!(game.play(Move::Pit(1)), Ok(&states[2]));
+        assert_eq!(game.len(), 3);
+        assert!(game.can_redo());
+    }
+
+    #[test]
+    fn new_move_discards_undone_moves() {
+        let mut game = sample_game();
+        game.undo();
+        game.undo();
+        let state = *game.play(Move::Pit(2)).unwrap();
+        assert_eq!(game.len(), 2);
+        assert!(!game.can_redo());
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.states().len(), 3);
+        assert_eq!(game.state(), &state);
+        assert_eq!(game.moves()[1].selection, Move::Pit(2));
+
+        // Illegal moves leave the session unchanged.
+        game.undo();
+        assert_eq!(game.play(Move::Pit(3)), Err(MoveError::EmptyPit(3)));
+        assert_eq!((game.position(), game.len()), (1, 2));
+    }
+
+    #[test]
+    fn history_queries() {
+        let mut game = sample_game();
+        assert_eq!(game.last_turn_of(Player::One), Some(1));
+        assert_eq!(game.last_turn_of(Player::Two), Some(2));
+        assert_eq!(game.repetitions(), 1);
+        game.jump_to(1);
+        assert_eq!(game.last_turn_of(Player::One), Some(0));
+        assert_eq!(game.last_turn_of(Player::Two), None);
+
+        // The session survives a round trip through a record.
+        let game = sample_game();
+        let replayed = Game::from_record(&game.to_record()).unwrap();
+        assert_eq!(replayed, game);
+    }
+
+    #[test]
+    fn stats() {
+        let mut game = sample_game();
+        assert_eq!(
+            game.stats(),
+            GameStats {
+                moves: [2, 1],
+                extra_turns: [1, 0],
+                stones_gained: [1, 1],
+                swapped: true,
+                max_repetitions: 1,
+            }
+        );
+
+        game.jump_to(1);
+        let stats = game.stats();
+        assert_eq!(stats.moves, [1, 0]);
+        assert!(!stats.swapped);
+        game.jump_to(0);
+        assert_eq!(
+            game.stats(),
+            GameStats {
+                max_repetitions: 1,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Components used to make moves in place, and take them back afterward.
+
+use super::mancala::{Move, Player};
+use super::rules::Landing;
+
+/// The number of stones at a location on the board before a move was made.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Change {
+    pub location: Landing,
+    pub old: usize,
+}
+
+/// The number of changes a [`Journal`] stores without allocating (enough for
+/// every location on a board with up to 7 pits per player).
+const INLINE_CHANGES: usize = 16;
+
+/// Compact form of a [`Change`] stored inside a [`Journal`], to keep the
+/// journal (and therefore each [`Undo`]) small enough to copy cheaply.
+///
+/// The location is packed into 16 bits: the highest bit stores the side of the
+/// board, the next bit stores whether the location is a store, and the remaining
+/// bits store the pit index. Boards are therefore limited to 16384 pits per
+/// player, and `u32::MAX` stones per location (checked in debug builds).
+#[derive(Debug, Clone, Copy, Default)]
+struct PackedChange {
+    location: u16,
+    old: u32,
+}
+
+impl PackedChange {
+    #[inline]
+    fn pack(location: Landing) -> u16 {
+        if let Landing::Pit(_, i) = location {
+            debug_assert!(
+                i < 1 << 14,
+                "journals support at most 16384 pits per player"
+            );
+        }
+        match location {
+            Landing::Pit(Player::One, i) => i as u16,
+            Landing::Pit(Player::Two, i) => (1 << 15) | i as u16,
+            Landing::Store(Player::One) => 1 << 14,
+            Landing::Store(Player::Two) => (1 << 15) | (1 << 14),
+        }
+    }
+
+    #[inline]
+    fn unpack(self) -> Change {
+        let side = if self.location & (1 << 15) == 0 {
+            Player::One
+        } else {
+            Player::Two
+        };
+        let location = if self.location & (1 << 14) != 0 {
+            Landing::Store(side)
+        } else {
+            Landing::Pit(side, usize::from(self.location & 0x3FFF))
+        };
+        Change {
+            location,
+            old: self.old as usize,
+        }
+    }
+}
+
+/// Records the original number of stones at each location changed while a
+/// move is played. Only the first change to each location is recorded, since
+/// the final number of stones can be read from the state itself.
+///
+/// Changes are stored inline for boards with up to 7 pits per player, so moves
+/// on typical boards can be recorded without any heap allocation.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    len: usize,
+    recorded: u64,
+    inline: [PackedChange; INLINE_CHANGES],
+    spill: Vec<PackedChange>,
+}
+
+impl Journal {
+    /// Create a new, empty journal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the number of stones at a location on a board with `pits` pits
+    /// per player, unless the location has already been recorded.
+    #[inline]
+    pub fn record(&mut self, location: Landing, old: usize, pits: usize) {
+        let packed = PackedChange::pack(location);
+
+        // Track which locations have been recorded using a bit mask (where
+        // possible), or by searching through the existing changes otherwise.
+        let index = match location {
+            Landing::Pit(side, i) => (usize::from(side) - 1) * (pits + 1) + i,
+            Landing::Store(side) => (usize::from(side) - 1) * (pits + 1) + pits,
+        };
+        if index < 64 {
+            if self.recorded & (1 << index) != 0 {
+                return;
+            }
+            self.recorded |= 1 << index;
+        } else if self.packed().any(|c| c.location == packed) {
+            return;
+        }
+
+        debug_assert!(
+            u32::try_from(old).is_ok(),
+            "journals support at most {} stones per location",
+            u32::MAX
+        );
+        let change = PackedChange {
+            location: packed,
+            old: old as u32,
+        };
+        if self.len < INLINE_CHANGES {
+            self.inline[self.len] = change;
+        } else {
+            self.spill.push(change);
+        }
+        self.len += 1;
+    }
+
+    /// Returns the number of locations recorded.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no locations have been recorded.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the recorded changes.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = Change> {
+        self.packed().map(|c| c.unpack())
+    }
+
+    #[inline]
+    fn packed(&self) -> impl Iterator<Item = &PackedChange> {
+        self.inline[..self.len.min(INLINE_CHANGES)]
+            .iter()
+            .chain(self.spill.iter())
+    }
+}
+
+/// Stores everything needed to take back a move made with
+/// [`Mancala::apply_move`][super::Mancala::apply_move].
+///
+/// Must be passed to [`Mancala::undo_move`][super::Mancala::undo_move] on the
+/// same state, before any other moves are made (or after they are undone).
+#[derive(Debug, Clone)]
+pub struct Undo {
+    pub(super) selection: Move,
+    pub(super) journal: Journal,
+    pub(super) current_turn: Player,
+    pub(super) p2_moved: bool,
+    pub(super) ply: usize,
+    pub(crate) zobrist_hash: u64,
+}
+
+impl Undo {
+    /// Returns the move that was made.
+    #[inline]
+    pub fn selection(&self) -> Move {
+        self.selection
+    }
+
+    /// Provides a reference to the changes made to the board by the move.
+    #[inline]
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Returns the player whose turn it was before the move.
+    #[inline]
+    pub fn current_turn(&self) -> Player {
+        self.current_turn
+    }
+
+    /// Returns whether Player 2 had moved before the move.
+    #[inline]
+    pub fn p2_moved(&self) -> bool {
+        self.p2_moved
+    }
+
+    /// Returns the ply before the move.
+    #[inline]
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::rules::{GrandSlam, Kalah, KalahOptions, Oware, Relay, RuleSet, Sweep};
+    use crate::game::{DynGameState, GameState, Player};
+    use crate::minimax::{MancalaZobrist, ZobristData};
+
+    /// Plays random games from the state, checking that every valid move can be
+    /// applied and taken back, restoring both the state and its Zobrist hash.
+    fn assert_undo_restores<T: MancalaZobrist + std::fmt::Debug>(start: T) {
+        let data = ZobristData::for_state_like(&start, 5);
+        for _ in 0..50 {
+            let mut state = start.clone();
+            state.set_zobrist_hash(data.hash_of(&state));
+            while !state.is_over() {
+                let before = state.clone();
+                for m in before.valid_moves() {
+                    let made = before.make_move_zobrist(&data, m).unwrap();
+                    let undo = state.apply_move_zobrist(&data, m).unwrap();
+                    assert_eq!(state, made);
+                    assert_eq!(state.zobrist_hash(), made.zobrist_hash());
+                    assert_eq!(state.zobrist_hash(), data.hash_of(&state));
+                    assert!(undo.journal().len() <= 2 * (state.pits() + 1));
+
+                    state.undo_move_zobrist(undo);
+                    assert_eq!(state, before);
+                    assert_eq!(state.zobrist_hash(), before.zobrist_hash());
+                }
+                state = state.make_move_rand_zobrist(&data).unwrap().0;
+            }
+        }
+    }
+
+    fn assert_all_states<R: RuleSet>(rules: R) {
+        assert_undo_restores(GameState::<6>::default().with_rules(rules));
+        assert_undo_restores(GameState::<4>::new(6, 0, 0, Player::One, 1, false).with_rules(rules));
+        assert_undo_restores(
+            DynGameState::new(5, 3, 0, 0, Player::One, 1, false).with_rules(rules),
+        );
+    }
+
+    #[test]
+    fn kalah() {
+        assert_all_states(Kalah::default());
+        assert_all_states(Kalah::new(KalahOptions {
+            empty_capture: false,
+            sweep: Sweep::Emptied,
+            pie_rule: true,
+        }));
+    }
+
+    #[test]
+    fn oware() {
+        for grand_slam in [
+            GrandSlam::NoCapture,
+            GrandSlam::Allowed,
+            GrandSlam::Forbidden,
+        ] {
+            assert_all_states(Oware::new(grand_slam));
+        }
+    }
+
+    #[test]
+    fn relay() {
+        assert_all_states(Relay::default());
+        assert_all_states(Relay::new(3));
+    }
+}
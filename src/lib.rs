@@ -3,7 +3,9 @@
 //!
 //! `[DOCS IN PROGRESS]`
 
+pub mod agent;
 pub mod game;
+pub mod mcts;
 pub mod minimax;
 #[cfg(feature = "ml")]
 pub mod ml;
@@ -11,8 +13,6 @@ pub mod ui;
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     #[test]
     fn example() {}
 }
@@ -1,25 +1,23 @@
 //use burn::data::dataset::Dataset;
-use mancalamax::game::{DynGameState, GameState};
-use mancalamax::game::{Mancala, Move, Player};
+use mancalamax::game::{GameState, Player};
 use mancalamax::minimax::MinimaxBuilder;
 //use mancalamax::ml::MancalaDataset;
-use mancalamax::ui::{
-    ExternalInterface, minimax_v_external, minimax_v_minimax, player_v_external, player_v_minimax,
-    player_v_minimax_default, player_v_player_default,
-};
+use mancalamax::ui::play_match;
+#[allow(unused_imports)] // Used by the alternative matches commented out below.
+use mancalamax::ui::{ExternalAgent, ExternalInterface, HumanAgent};
 
 fn main() {
-    //player_v_player_default();
-    //player_v_minimax_default(Player::One);
+    //play_match(&mut HumanAgent::new("Player 1"), &mut HumanAgent::new("Player 2"), &GameState::default());
+    //play_match(&mut MinimaxBuilder::new().build(), &mut HumanAgent::default(), &GameState::default());
     let minimax = MinimaxBuilder::new()
         .max_depth(Some(18))
         .iterative_deepening(true)
         .use_t_table(true)
         .max_time(None);
-    minimax_v_minimax(
+    play_match(
+        &mut minimax.build(),
+        &mut minimax.clone().optimize_for(Player::Two).build(),
         &GameState::default(),
-        &minimax,
-        &minimax.optimize_for(Player::Two),
     );
     //let result = minimax.build().search_utility(&GameState::default());
     //println!("{:?}", result);
@@ -32,42 +30,39 @@ fn main() {
     // Test CSV functionality.
     //result.save_csv("mancala.csv").expect("Could not save csv");
 
-    //player_v_external(
+    //play_match(
+    //    &mut HumanAgent::default(),
+    //    &mut ExternalAgent::new(ExternalInterface::Minimal, "C:\\Users\\ethan\\Desktop\\test_dir"),
     //    &GameState::default(),
-    //    Player::Two,
-    //    ExternalInterface::Minimal,
-    //    "C:\\Users\\ethan\\Desktop\\test_dir",
     //);
 
-    //minimax_v_external(
+    //play_match(
+    //    &mut MinimaxBuilder::default().max_depth(Some(8)).build(),
+    //    &mut ExternalAgent::new(ExternalInterface::Minimal, "C:\\Users\\ethan\\Desktop\\test_dir"),
     //    &GameState::default(),
-    //    &MinimaxBuilder::default().max_depth(Some(8)),
-    //    Player::Two,
-    //    ExternalInterface::Minimal,
-    //    "C:\\Users\\ethan\\Desktop\\test_dir",
     //);
 
     /*
     let mut gnn_wins = Vec::new();
     let mut minimax_wins = Vec::new();
     for _ in 0..500 {
-        let s = minimax_v_external(
+        let s = play_match(
+            &mut ExternalAgent::new(ExternalInterface::Minimal, "C:\\Users\\ethan\\Desktop\\test_dir"),
+            &mut MinimaxBuilder::new().optimize_for(Player::Two).max_depth(Some(0)).build(),
             &GameState::default(),
-            &MinimaxBuilder::new().max_depth(Some(0)),
-            Player::One,
-            ExternalInterface::Minimal,
-            "C:\\Users\\ethan\\Desktop\\test_dir",
         );
         gnn_wins.push(s.score(Player::One));
 
-        let s = minimax_v_minimax(
-            &GameState::default(),
-            &MinimaxBuilder::new()
+        let s = play_match(
+            &mut MinimaxBuilder::new()
                 .optimize_for(Player::One)
-                .max_depth(Some(12)),
-            &MinimaxBuilder::new()
+                .max_depth(Some(12))
+                .build(),
+            &mut MinimaxBuilder::new()
                 .optimize_for(Player::Two)
-                .max_depth(Some(0)),
+                .max_depth(Some(0))
+                .build(),
+            &GameState::default(),
         );
         minimax_wins.push(s.score(Player::One));
     }
@@ -0,0 +1,47 @@
+//! Components relating to the use of Monte Carlo Tree Search (MCTS) with
+//! Mancala board states.
+
+pub mod algorithm;
+pub mod builder;
+
+pub use algorithm::{Mcts, MctsResult};
+pub use builder::MctsBuilder;
+
+use crate::minimax::Evaluator;
+use std::sync::Arc;
+
+/// Determines how [`Mcts`] scores a state once it has been added to the tree.
+#[derive(Debug)]
+pub enum PlayoutPolicy<T> {
+    /// Play random moves (using [`Mancala::make_move_rand`]) until the game is
+    /// over, then score the state as a win, loss, or tie. Playouts which reach
+    /// the [`max_playout_plies`][MctsBuilder::max_playout_plies] limit are
+    /// scored using the [`playout_heuristic`][MctsBuilder::playout_heuristic].
+    ///
+    /// [`Mancala::make_move_rand`]: crate::game::Mancala::make_move_rand
+    Random,
+    /// Score the state immediately using a heuristic (e.g., a
+    /// [`StateEvalFn`][crate::minimax::StateEvalFn], or any other [`Evaluator`])
+    /// for the player to move, without playing any further moves. Heuristic
+    /// values are converted to a win probability using the logistic function,
+    /// so a value of `0.0` is scored the same as a tie. States where the game
+    /// is over are scored as a win, loss, or tie.
+    Heuristic(Arc<dyn Evaluator<T>>),
+}
+
+impl<T> PlayoutPolicy<T> {
+    /// Create a [`Heuristic`][Self::Heuristic] policy using the supplied evaluator.
+    pub fn heuristic(h: impl Evaluator<T> + 'static) -> Self {
+        PlayoutPolicy::Heuristic(Arc::new(h))
+    }
+}
+
+// Implemented manually, since deriving would require `T: Clone`.
+impl<T> Clone for PlayoutPolicy<T> {
+    fn clone(&self) -> Self {
+        match self {
+            PlayoutPolicy::Random => PlayoutPolicy::Random,
+            PlayoutPolicy::Heuristic(h) => PlayoutPolicy::Heuristic(h.clone()),
+        }
+    }
+}
@@ -0,0 +1,513 @@
+//! Implementation of Monte Carlo Tree Search, using the UCT formula to select
+//! which moves to explore, for Mancala.
+
+use super::{MctsBuilder, PlayoutPolicy};
+use crate::game::{Mancala, Move, Player};
+use crate::minimax::{Evaluator, MancalaZobrist, StopHandle, ZobristData};
+use rand::seq::SliceRandom;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Stores the result of a Monte Carlo Tree Search involving every move
+/// available from the searched state.
+///
+/// Each [`Move`] in the [`found_moves`][Self::found_moves] field has a
+/// corresponding value in the [`utilities`][Self::utilities] field (the mean
+/// score of its playouts, from `0.0` for a loss to `1.0` for a win, for the
+/// player making the move), a corresponding number of playouts in the
+/// [`visits`][Self::visits] field, and a corresponding line of play (starting
+/// with that move, and following the most visited moves) in the
+/// [`principal_variations`][Self::principal_variations] field, at the same index.
+///
+/// The [`iterations`][Self::iterations] field counts the playouts made by this
+/// search only, while the visit counts include playouts made by earlier searches
+/// if the tree was reused.
+#[derive(Debug, Clone)]
+pub struct MctsResult {
+    pub found_moves: Vec<Move>,
+    pub utilities: Vec<f32>,
+    pub visits: Vec<u32>,
+    pub principal_variations: Vec<Vec<Move>>,
+    pub iterations: usize,
+    pub elapsed: Duration,
+}
+
+impl MctsResult {
+    /// Returns the index of the most visited move (i.e., the move chosen by
+    /// [`Mcts::search`]), or [`None`] if there are no moves.
+    pub fn best_index(&self) -> Option<usize> {
+        (0..self.visits.len()).max_by(|a, b| {
+            self.visits[*a]
+                .cmp(&self.visits[*b])
+                .then(self.utilities[*a].total_cmp(&self.utilities[*b]))
+        })
+    }
+}
+
+/// Stores the necessary information for executing Monte Carlo Tree Search on a
+/// Mancala board state in order to determine the most promising move.
+#[derive(Debug, Clone)]
+pub struct Mcts<T: MancalaZobrist> {
+    pub(super) exploration: f64,
+    pub(super) playout: PlayoutPolicy<T>,
+    pub(super) max_playout_plies: Option<usize>,
+    pub(super) playout_heuristic: Arc<dyn Evaluator<T>>,
+    pub(super) max_iterations: Option<usize>,
+    pub(super) max_time: Option<Duration>,
+    pub(super) reuse_tree: bool,
+    pub(super) zobrist_seed: u64,
+    pub(super) stop_handle: StopHandle,
+    pub(super) z_data: RefCell<ZobristData>,
+    pub(super) tree: RefCell<Tree>,
+}
+
+impl<T: MancalaZobrist> From<MctsBuilder<T>> for Mcts<T> {
+    /// Alias for [`MctsBuilder::build`].
+    fn from(value: MctsBuilder<T>) -> Self {
+        value.build()
+    }
+}
+
+impl<T: MancalaZobrist> From<&MctsBuilder<T>> for Mcts<T> {
+    /// Alias for [`MctsBuilder::build`].
+    fn from(value: &MctsBuilder<T>) -> Self {
+        value.build()
+    }
+}
+
+impl<T: MancalaZobrist> Mcts<T> {
+    /// Returns the exploration constant used when selecting moves.
+    #[inline]
+    pub fn exploration(&self) -> f64 {
+        self.exploration
+    }
+
+    /// Returns the maximum number of random moves played by each playout.
+    #[inline]
+    pub fn max_playout_plies(&self) -> Option<usize> {
+        self.max_playout_plies
+    }
+
+    /// Returns the maximum number of iterations per search.
+    #[inline]
+    pub fn max_iterations(&self) -> Option<usize> {
+        self.max_iterations
+    }
+
+    /// Returns the maximum time allowed to find a move.
+    #[inline]
+    pub fn max_time(&self) -> Option<Duration> {
+        self.max_time
+    }
+
+    /// Returns whether the tree is reused between searches.
+    #[inline]
+    pub fn reuse_tree(&self) -> bool {
+        self.reuse_tree
+    }
+
+    /// Returns a handle which stops the searches run by this instance (see
+    /// [`StopHandle`]). The handle is reset whenever a search starts.
+    #[inline]
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop_handle.clone()
+    }
+
+    /// Returns the number of states in the tree kept from the last search.
+    #[inline]
+    pub fn tree_size(&self) -> usize {
+        self.tree.borrow().nodes.len()
+    }
+
+    /// Discards the tree kept from the last search.
+    #[inline]
+    pub fn clear_tree(&self) {
+        self.tree.borrow_mut().nodes.clear();
+    }
+
+    /// Search for the most promising move using Monte Carlo Tree Search,
+    /// based on the set configuration parameters.
+    ///
+    /// The most visited move is chosen (ties are broken by utility). To also
+    /// return the statistics of every move, call
+    /// [`search_utility_all`][Self::search_utility_all] instead.
+    ///
+    /// If no move was found successfully, returns [`None`].
+    pub fn search(&self, state: &T) -> Option<Move> {
+        let result = self.search_utility_all(state)?;
+        result.best_index().map(|i| result.found_moves[i])
+    }
+
+    /// Search for all possible moves and their utilities and visit counts using
+    /// Monte Carlo Tree Search, based on the set configuration parameters.
+    ///
+    /// If tree reuse is enabled and the state was reached during the previous
+    /// search, the search continues from the part of the tree below the state.
+    /// Only moves which were explored at least once are returned.
+    ///
+    /// If the search is stopped using the [`stop_handle`][Self::stop_handle], the
+    /// statistics collected so far are returned, as if the time limit had expired.
+    ///
+    /// If no move was found successfully (e.g., the game is over), returns [`None`].
+    pub fn search_utility_all(&self, state: &T) -> Option<MctsResult> {
+        self.stop_handle.reset();
+        let start = Instant::now();
+        let root = self.prepare_root(state);
+        let data = self.z_data.borrow();
+        let mut tree = self.tree.borrow_mut();
+
+        let mut iterations = 0;
+        while self.max_iterations.is_none_or(|n| iterations < n)
+            && self.max_time.is_none_or(|t| start.elapsed() < t)
+            && !self.stop_handle.is_stopped()
+        {
+            self.iterate(&mut tree, &root, &data);
+            iterations += 1;
+        }
+
+        let children = &tree.nodes[0].children;
+        if children.is_empty() {
+            return None;
+        }
+        Some(MctsResult {
+            found_moves: children
+                .iter()
+                .map(|c| tree.nodes[*c].selection.unwrap())
+                .collect(),
+            utilities: children
+                .iter()
+                .map(|c| tree.nodes[*c].utility() as f32)
+                .collect(),
+            visits: children.iter().map(|c| tree.nodes[*c].visits).collect(),
+            principal_variations: children.iter().map(|c| tree.line_from(*c)).collect(),
+            iterations,
+            elapsed: start.elapsed(),
+        })
+    }
+
+    /// Ensures the current Zobrist data is valid for the supplied state, and
+    /// moves the root of the tree to the supplied state (keeping the statistics
+    /// below it if tree reuse is enabled and it is already in the tree).
+    ///
+    /// Returns a copy of the state with its Zobrist hash computed from scratch.
+    fn prepare_root(&self, state: &T) -> T {
+        let mut tree = self.tree.borrow_mut();
+        if !self.z_data.borrow().is_valid_for(state) {
+            self.z_data
+                .replace(ZobristData::for_state_like(state, self.zobrist_seed));
+            tree.nodes.clear();
+        }
+
+        let mut root = state.clone();
+        root.set_zobrist_hash(self.z_data.borrow().hash_of(state));
+        let found = tree
+            .nodes
+            .iter()
+            .position(|n| n.hash == root.zobrist_hash());
+        match found {
+            Some(i) if self.reuse_tree => tree.keep_subtree(i),
+            _ => {
+                tree.nodes.clear();
+                tree.nodes
+                    .push(Node::new(&root, None, root.current_turn().other()));
+            }
+        }
+        root
+    }
+
+    /// Runs a single iteration of the search: selects a path through the tree
+    /// using the UCT formula, adds a new state to the end of it, scores that
+    /// state using the playout policy, then updates the statistics of each
+    /// state on the path.
+    fn iterate(&self, tree: &mut Tree, root: &T, data: &ZobristData) {
+        let mut state = root.clone();
+        let mut node = 0;
+        let mut path = vec![node];
+
+        // Selection: follow the most promising moves until reaching a state
+        // with unexplored moves (or where the game is over).
+        while tree.nodes[node].untried.is_empty() && !tree.nodes[node].children.is_empty() {
+            node = self.select_child(tree, node);
+            let m = tree.nodes[node].selection.unwrap();
+            state.apply_move_zobrist(data, m).unwrap();
+            path.push(node);
+        }
+
+        // Expansion: add a state reached by one of the unexplored moves.
+        if let Some(m) = tree.nodes[node].untried.pop() {
+            let mover = state.current_turn();
+            state.apply_move_zobrist(data, m).unwrap();
+            tree.nodes.push(Node::new(&state, Some(m), mover));
+            let child = tree.nodes.len() - 1;
+            tree.nodes[node].children.push(child);
+            path.push(child);
+        }
+
+        // Playout, then backpropagation.
+        let rewards = self.play_out(state);
+        for i in path {
+            let n = &mut tree.nodes[i];
+            n.visits += 1;
+            n.reward += rewards[n.mover];
+        }
+    }
+
+    /// Returns the child of a node with the highest UCT value.
+    fn select_child(&self, tree: &Tree, node: usize) -> usize {
+        let log_visits = f64::from(tree.nodes[node].visits).ln();
+        let uct = |child: usize| {
+            let n = &tree.nodes[child];
+            n.utility() + self.exploration * (log_visits / f64::from(n.visits)).sqrt()
+        };
+        tree.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .max_by(|a, b| uct(*a).total_cmp(&uct(*b)))
+            .unwrap()
+    }
+
+    /// Scores a state using the playout policy, returning the reward for each
+    /// player.
+    fn play_out(&self, mut state: T) -> [f64; 2] {
+        match &self.playout {
+            PlayoutPolicy::Random => {
+                for plies in 0.. {
+                    if state.is_over() {
+                        break;
+                    }
+                    // Score long playouts (e.g., in positions which repeat)
+                    // using the heuristic instead.
+                    if self.max_playout_plies.is_some_and(|max| plies >= max) {
+                        return heuristic_rewards(&*self.playout_heuristic, &state);
+                    }
+                    state = match state.make_move_rand() {
+                        Ok((next, _)) => next,
+                        Err(_) => break,
+                    };
+                }
+                outcome_rewards(&state)
+            }
+            PlayoutPolicy::Heuristic(h) if !state.is_over() => heuristic_rewards(&**h, &state),
+            PlayoutPolicy::Heuristic(_) => outcome_rewards(&state),
+        }
+    }
+}
+
+/// The tree of states explored by [`Mcts`], stored as a flat list of nodes
+/// (with the root at index `0`).
+#[derive(Debug, Clone, Default)]
+pub(super) struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    /// Discards every node except those below (and including) the supplied
+    /// node, which becomes the root.
+    fn keep_subtree(&mut self, root: usize) {
+        let mut old: Vec<_> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut stack: Vec<(usize, Option<usize>)> = vec![(root, None)];
+        while let Some((i, parent)) = stack.pop() {
+            let mut node = old[i].take().unwrap();
+            let children = std::mem::take(&mut node.children);
+            self.nodes.push(node);
+            let index = self.nodes.len() - 1;
+            if let Some(p) = parent {
+                self.nodes[p].children.push(index);
+            }
+            stack.extend(children.into_iter().rev().map(|c| (c, Some(index))));
+        }
+    }
+
+    /// Returns the line of play starting with the move leading to a node, then
+    /// following the most visited moves.
+    fn line_from(&self, mut node: usize) -> Vec<Move> {
+        let mut line = vec![self.nodes[node].selection.unwrap()];
+        while let Some(next) = self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .max_by_key(|c| self.nodes[*c].visits)
+        {
+            line.push(self.nodes[next].selection.unwrap());
+            node = next;
+        }
+        line
+    }
+}
+
+/// A state in the tree, along with the statistics of its playouts.
+#[derive(Debug, Clone)]
+struct Node {
+    /// The Zobrist hash of the state.
+    hash: u64,
+    /// The move which led to the state ([`None`] for the root).
+    selection: Option<Move>,
+    /// The player who made the move which led to the state.
+    mover: Player,
+    children: Vec<usize>,
+    /// The moves which have not been explored yet, in random order.
+    untried: Vec<Move>,
+    visits: u32,
+    /// The total reward of the playouts through this state, for the mover.
+    reward: f64,
+}
+
+impl Node {
+    fn new<T: MancalaZobrist>(state: &T, selection: Option<Move>, mover: Player) -> Self {
+        let mut untried = state.valid_moves();
+        untried.shuffle(&mut rand::rng());
+        Self {
+            hash: state.zobrist_hash(),
+            selection,
+            mover,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    /// Returns the mean reward of the playouts through this state.
+    #[inline]
+    fn utility(&self) -> f64 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.reward / f64::from(self.visits)
+        }
+    }
+}
+
+/// Returns the reward for each player by converting the heuristic value for
+/// the player to move into a win probability (using the logistic function).
+fn heuristic_rewards<T: Mancala>(h: &dyn Evaluator<T>, state: &T) -> [f64; 2] {
+    let mover = state.current_turn();
+    let p = 1.0 / (1.0 + (-f64::from(h.evaluate(state, mover))).exp());
+    let mut rewards = [0.0; 2];
+    rewards[mover] = p;
+    rewards[mover.other()] = 1.0 - p;
+    rewards
+}
+
+/// Returns the reward for each player once the game is over (`1.0` for a
+/// win, `0.0` for a loss, and `0.5` for a tie).
+fn outcome_rewards(state: &impl Mancala) -> [f64; 2] {
+    match state.outcome().winner() {
+        Some(Player::One) => [1.0, 0.0],
+        Some(Player::Two) => [0.0, 1.0],
+        None => [0.5, 0.5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, GrandSlam, Oware};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns the hash, move, visit count, reward, and number of children of
+    /// every node below (and including) the supplied node, in depth-first order.
+    fn subtree(tree: &Tree, node: usize) -> Vec<(u64, Option<Move>, u32, f64, usize)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![node];
+        while let Some(i) = stack.pop() {
+            let n = &tree.nodes[i];
+            nodes.push((n.hash, n.selection, n.visits, n.reward, n.children.len()));
+            stack.extend(n.children.iter().rev());
+        }
+        nodes
+    }
+
+    #[test]
+    fn keep_subtree() {
+        let mcts = MctsBuilder::default()
+            .max_iterations(Some(2000))
+            .reuse_tree(true)
+            .build();
+        let state = GameState::<6>::default();
+        let result = mcts.search_utility_all(&state).unwrap();
+        let best = result.best_index().unwrap();
+        let expected = {
+            let tree = mcts.tree.borrow();
+            subtree(&tree, tree.nodes[0].children[best])
+        };
+        assert_eq!(expected[0].2, result.visits[best]);
+
+        // The statistics below the state reached are kept.
+        let next = state.make_move(result.found_moves[best]).unwrap();
+        mcts.prepare_root(&next);
+        assert_eq!(subtree(&mcts.tree.borrow(), 0), expected);
+        assert_eq!(mcts.tree_size(), expected.len());
+
+        // Searching continues from the kept statistics.
+        let result = mcts.search_utility_all(&next).unwrap();
+        assert_eq!(mcts.tree.borrow().nodes[0].visits, expected[0].2 + 2000);
+        assert_eq!(result.iterations, 2000);
+
+        // Without tree reuse, the statistics are discarded.
+        let fresh = MctsBuilder::from(&mcts).reuse_tree(false).build();
+        fresh.search_utility_all(&state).unwrap();
+        fresh.prepare_root(&next);
+        assert_eq!(fresh.tree_size(), 1);
+    }
+
+    #[test]
+    fn heuristic_closure() {
+        let evaluated = Arc::new(AtomicUsize::new(0));
+        let counter = evaluated.clone();
+        let mcts = MctsBuilder::default()
+            .max_iterations(Some(500))
+            .playout(PlayoutPolicy::heuristic(
+                move |s: &GameState<6>, p: Player| {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    s.score(p) as f32 - s.score(p.other()) as f32
+                },
+            ))
+            .build();
+        let result = mcts.search_utility_all(&GameState::default()).unwrap();
+        assert_eq!(result.iterations, 500);
+        assert!(evaluated.load(Ordering::Relaxed) > 0);
+
+        // Built instances share the heuristic.
+        let copy = MctsBuilder::from(&mcts).build();
+        let before = evaluated.load(Ordering::Relaxed);
+        copy.search(&GameState::default()).unwrap();
+        assert!(evaluated.load(Ordering::Relaxed) > before);
+    }
+
+    #[test]
+    fn playout_limit() {
+        let evaluated = Arc::new(AtomicUsize::new(0));
+        let counter = evaluated.clone();
+        let mcts = MctsBuilder::default()
+            .max_iterations(Some(300))
+            .max_playout_plies(Some(20))
+            .playout_heuristic(move |s: &GameState<6, Oware>, p: Player| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                s.score(p) as f32 - s.score(p.other()) as f32
+            })
+            .build();
+        let state = GameState::<6>::default().with_rules(Oware::default());
+        let result = mcts.search_utility_all(&state).unwrap();
+        assert_eq!(result.iterations, 300);
+        assert!(state.is_valid_move(mcts.search(&state).unwrap()));
+        assert!(evaluated.load(Ordering::Relaxed) > 0);
+
+        // Without a playout limit, Oware playouts end at the rule set's ply limit.
+        let mcts = MctsBuilder::default()
+            .max_iterations(Some(100))
+            .max_playout_plies(None)
+            .build();
+        let state = GameState::<6>::default().with_rules(Oware::new(GrandSlam::Forbidden));
+        let result = mcts.search_utility_all(&state).unwrap();
+        assert_eq!(result.iterations, 100);
+        let mut found = result.found_moves;
+        found.sort_by_key(|m| usize::from(*m));
+        assert_eq!(found, state.valid_moves());
+    }
+}
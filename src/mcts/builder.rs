@@ -0,0 +1,198 @@
+//! Builder utilities for constructing [`Mcts`] instances.
+
+use super::{Mcts, PlayoutPolicy};
+use crate::game::Player;
+use crate::minimax::{Evaluator, MancalaZobrist, StopHandle};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Helper for constructing [`Mcts`] instances based on certain specifications.
+#[derive(Debug, Clone)]
+pub struct MctsBuilder<T: MancalaZobrist> {
+    exploration: f64,
+    playout: PlayoutPolicy<T>,
+    max_playout_plies: Option<usize>,
+    playout_heuristic: Arc<dyn Evaluator<T>>,
+    max_iterations: Option<usize>,
+    max_time: Option<Duration>,
+    reuse_tree: bool,
+    zobrist_seed: u64,
+    stop_handle: Option<StopHandle>,
+}
+
+impl<T: MancalaZobrist> Default for MctsBuilder<T> {
+    /// The default [`Mcts`] configuration is the following:
+    /// - `exploration`: `√2`
+    /// - `playout`: [`PlayoutPolicy::Random`]
+    /// - `max_playout_plies`: `200`
+    /// - `playout_heuristic`: A function that returns the point differential
+    ///   between the players (positive if the current player is winning).
+    /// - `max_iterations`: `10,000`
+    /// - `max_time`: [`None`]
+    /// - `reuse_tree`: [`true`]
+    /// - `zobrist_seed`: `0x49CB86856BB06133`
+    /// - `stop_handle`: [`None`]
+    fn default() -> Self {
+        let playout_heuristic = |s: &T, p: Player| match p {
+            Player::One => (s.score(Player::One) as isize - s.score(Player::Two) as isize) as f32,
+            Player::Two => (s.score(Player::Two) as isize - s.score(Player::One) as isize) as f32,
+        };
+        Self {
+            exploration: std::f64::consts::SQRT_2,
+            playout: PlayoutPolicy::Random,
+            max_playout_plies: Some(200),
+            playout_heuristic: Arc::new(playout_heuristic),
+            max_iterations: Some(10_000),
+            max_time: None,
+            reuse_tree: true,
+            zobrist_seed: 0x49CB86856BB06133,
+            stop_handle: None,
+        }
+    }
+}
+
+impl<T: MancalaZobrist> From<Mcts<T>> for MctsBuilder<T> {
+    fn from(value: Mcts<T>) -> Self {
+        from_common(&value)
+    }
+}
+
+impl<T: MancalaZobrist> From<&Mcts<T>> for MctsBuilder<T> {
+    fn from(value: &Mcts<T>) -> Self {
+        from_common(value)
+    }
+}
+
+impl<T: MancalaZobrist> MctsBuilder<T> {
+    /// Construct a new [`MctsBuilder`] instance using the default configuration.
+    ///
+    /// See [`MctsBuilder::default`] for details.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the exploration constant used when selecting which move to explore
+    /// (i.e., the `c` in the UCT formula).
+    ///
+    /// Larger values explore less promising moves more often.
+    pub fn exploration(mut self, c: f64) -> Self {
+        self.exploration = c;
+        self
+    }
+
+    /// Set how states added to the tree are scored.
+    pub fn playout(mut self, p: PlayoutPolicy<T>) -> Self {
+        self.playout = p;
+        self
+    }
+
+    /// Set the maximum number of random moves played by each playout (when
+    /// using [`PlayoutPolicy::Random`]). Playouts which reach the limit before
+    /// the game is over are scored using the
+    /// [`playout_heuristic`][Self::playout_heuristic] instead.
+    ///
+    /// [`None`] means no limit, which is only safe for rule sets where every
+    /// game ends.
+    pub fn max_playout_plies(mut self, n: Option<usize>) -> Self {
+        self.max_playout_plies = n;
+        self
+    }
+
+    /// Set the heuristic (e.g., a [`StateEvalFn`][crate::minimax::StateEvalFn],
+    /// or any other [`Evaluator`]) used to score random playouts which reach the
+    /// [`max_playout_plies`][Self::max_playout_plies] limit, in the same way as
+    /// [`PlayoutPolicy::Heuristic`].
+    pub fn playout_heuristic(mut self, h: impl Evaluator<T> + 'static) -> Self {
+        self.playout_heuristic = Arc::new(h);
+        self
+    }
+
+    /// Set the maximum number of iterations (i.e., playouts) per search.
+    ///
+    /// [`None`] means no iteration limit.
+    ///
+    /// <div class="warning">
+    ///
+    /// If [`None`] is selected, and [`max_time`][Self::max_time] is also set to
+    /// [`None`], the search will never terminate.
+    ///
+    /// </div>
+    pub fn max_iterations(mut self, n: Option<usize>) -> Self {
+        self.max_iterations = n;
+        self
+    }
+
+    /// Set the maximum time allowed to find a move.
+    ///
+    /// [`None`] means no time limit.
+    ///
+    /// <div class="warning">
+    ///
+    /// If [`None`] is selected, and [`max_iterations`][Self::max_iterations] is
+    /// also set to [`None`], the search will never terminate.
+    ///
+    /// </div>
+    pub fn max_time(mut self, t: Option<Duration>) -> Self {
+        self.max_time = t;
+        self
+    }
+
+    /// Set whether the tree built by one search is reused by the next.
+    ///
+    /// When enabled, a search of a state found in the previous tree (e.g., the
+    /// state after the engine's move and the opponent's reply) continues from
+    /// the statistics already collected for it.
+    pub fn reuse_tree(mut self, enabled: bool) -> Self {
+        self.reuse_tree = enabled;
+        self
+    }
+
+    /// Set the seed used to generate the Zobrist values which identify states
+    /// when reusing the tree.
+    pub fn zobrist_seed(mut self, seed: u64) -> Self {
+        self.zobrist_seed = seed;
+        self
+    }
+
+    /// Set the handle used to stop searches early (see [`Mcts::stop_handle`]).
+    ///
+    /// [`None`] means each instance built gets a new handle. Otherwise, every
+    /// instance built shares the supplied handle.
+    pub fn stop_handle(mut self, handle: Option<StopHandle>) -> Self {
+        self.stop_handle = handle;
+        self
+    }
+
+    /// Construct a [`Mcts`] instance based on the set configuration.
+    pub fn build(&self) -> Mcts<T> {
+        Mcts {
+            exploration: self.exploration,
+            playout: self.playout.clone(),
+            max_playout_plies: self.max_playout_plies,
+            playout_heuristic: self.playout_heuristic.clone(),
+            max_iterations: self.max_iterations,
+            max_time: self.max_time,
+            reuse_tree: self.reuse_tree,
+            zobrist_seed: self.zobrist_seed,
+            stop_handle: self.stop_handle.clone().unwrap_or_default(),
+            z_data: Default::default(),
+            tree: Default::default(),
+        }
+    }
+}
+
+/// Helper function for implementing the [`From`] trait.
+fn from_common<T: MancalaZobrist>(value: &Mcts<T>) -> MctsBuilder<T> {
+    MctsBuilder {
+        exploration: value.exploration,
+        playout: value.playout.clone(),
+        max_playout_plies: value.max_playout_plies,
+        playout_heuristic: value.playout_heuristic.clone(),
+        max_iterations: value.max_iterations,
+        max_time: value.max_time,
+        reuse_tree: value.reuse_tree,
+        zobrist_seed: value.zobrist_seed,
+        stop_handle: Some(value.stop_handle.clone()),
+    }
+}
@@ -2,14 +2,35 @@
 //! board states.
 
 pub mod algorithm;
+pub mod book;
 pub mod builder;
+pub mod endgame;
+pub mod eval;
+pub mod persist;
+pub mod ponder;
+pub mod solver;
+pub mod stats;
+pub mod stop;
+mod t_table;
+pub mod time;
 pub mod zobrist;
 
 pub use algorithm::{Minimax, MultiSearchResult, SearchResult};
+pub use book::{BookMove, OpeningBook};
 pub use builder::MinimaxBuilder;
+pub use endgame::EndgameDb;
+pub use eval::{Evaluator, MoveOrderer};
+pub use persist::PersistError;
+pub use ponder::{Ponder, PonderMode};
+pub use solver::{SolveResult, Solver};
+pub use stats::SearchStats;
+pub use stop::StopHandle;
+pub use time::{TimeBudget, TimeManager};
 pub use zobrist::{MancalaZobrist, ZobristAction, ZobristData};
 
 use crate::game::{Move, Player};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
 
 /// Type alias for any function that evaluates a reference to a type
 /// (usually some kind of Mancala game state) and a current player,
@@ -22,3 +43,19 @@ pub type StateEvalFn<T> = fn(&T, player: Player) -> f32;
 /// of moves in a specific order. Every move in the vector should
 /// be a valid move, given the supplied game state reference.
 pub type MoveOrderFn<T> = fn(&T) -> Vec<Move>;
+
+/// Type alias for any function (or closure) that is called with the statistics
+/// for each iteration of a minimax search, once the iteration has finished (or
+/// the time limit has expired). Shared between copies of a [`Minimax`] instance,
+/// so closures may capture state (e.g., a channel or a progress display).
+pub type IterationFn = Arc<dyn Fn(&SearchStats) + Send + Sync>;
+
+/// Wrapper used to store an [`IterationFn`] in types which implement [`Debug`].
+#[derive(Clone)]
+pub(crate) struct IterationCallback(pub(crate) IterationFn);
+
+impl Debug for IterationCallback {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("IterationFn")
+    }
+}
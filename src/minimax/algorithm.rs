@@ -1,9 +1,20 @@
 //! Implementation of the minimax algorithm with alpha-beta pruning for Mancala.
 
-use super::{MancalaZobrist, MinimaxBuilder, MoveOrderFn, StateEvalFn, ZobristData};
+use super::book::OpeningBook;
+use super::endgame::EndgameDb;
+use super::stop::StopHandle;
+use super::t_table::{TTEntry, TTable, ValueBound};
+use super::time::{TimeBudget, TimeManager};
+use super::{
+    Evaluator, IterationCallback, MancalaZobrist, MinimaxBuilder, MoveOrderer, SearchStats,
+    ZobristData,
+};
 use crate::game::{Move, Player};
-use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
 use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
 use std::time::{Duration, Instant};
 
 /// Stores the value of a minimax search result.
@@ -11,12 +22,27 @@ use std::time::{Duration, Instant};
 /// If the [`fully_searched`][Self::fully_searched] field is [`true`], then the heuristic
 /// was never used in finding the current result (i.e., the search evaluated all
 /// possible terminal states).
-#[derive(Debug, Clone, Copy)]
+///
+/// The [`principal_variation`][Self::principal_variation] field stores the line of
+/// play expected to follow from the current state (starting with the found move),
+/// including any extra-turn continuations.
+///
+/// The [`stats`][Self::stats] field describes the work done during the whole
+/// search, and the [`iteration_stats`][Self::iteration_stats] field describes
+/// the work done during each iteration (if iterative deepening was used).
+///
+/// If the [`from_book`][Self::from_book] field is [`true`], then the move was
+/// taken from an [`OpeningBook`] without searching, so the depth searched is `0`.
+#[derive(Debug, Clone)]
 pub struct SearchResult {
     pub found_move: Move,
     pub utility: f32,
     pub depth_searched: Option<usize>,
     pub fully_searched: bool,
+    pub principal_variation: Vec<Move>,
+    pub stats: SearchStats,
+    pub iteration_stats: Vec<SearchStats>,
+    pub from_book: bool,
 }
 
 /// Stores the value of a minimax search result involving multiple moves.
@@ -26,14 +52,21 @@ pub struct SearchResult {
 /// possible terminal states).
 ///
 /// Each [`Move`] in the [`found_moves`][Self::found_moves] field has a
-/// corresponding utility value in the [`utilities`][Self::utilities] field
-/// at the same index.
+/// corresponding utility value in the [`utilities`][Self::utilities] field,
+/// and a corresponding line of play (starting with that move) in the
+/// [`principal_variations`][Self::principal_variations] field, at the same index.
+///
+/// The [`stats`][Self::stats] and [`iteration_stats`][Self::iteration_stats]
+/// fields are the same as those in [`SearchResult`].
 #[derive(Debug, Clone)]
 pub struct MultiSearchResult {
     pub found_moves: Vec<Move>,
     pub utilities: Vec<f32>,
     pub depth_searched: Option<usize>,
     pub fully_searched: bool,
+    pub principal_variations: Vec<Vec<Move>>,
+    pub stats: SearchStats,
+    pub iteration_stats: Vec<SearchStats>,
 }
 
 /// Stores the necessary information for executing the minimax algorithm on a
@@ -46,12 +79,23 @@ pub struct Minimax<T: MancalaZobrist> {
     pub(super) max_time: Option<Duration>,
     pub(super) iterative_deepening: bool,
     pub(super) use_t_table: bool,
-    pub(super) move_orderer: MoveOrderFn<T>,
-    pub(super) evaluator: StateEvalFn<T>,
-    pub(super) heuristic: StateEvalFn<T>,
+    pub(super) threads: usize,
+    pub(super) move_orderer: Arc<dyn MoveOrderer<T>>,
+    pub(super) evaluator: Arc<dyn Evaluator<T>>,
+    pub(super) heuristic: Arc<dyn Evaluator<T>>,
+    pub(super) on_iteration: Option<IterationCallback>,
+    pub(super) zobrist_seed: u64,
+    pub(super) opening_book: Option<Arc<OpeningBook>>,
+    pub(super) endgame_db: Option<Arc<EndgameDb>>,
     pub(super) start_time: Cell<Option<Instant>>,
-    pub(super) t_table: RefCell<FxHashMap<u64, TTEntry>>,
+    pub(super) stop: Option<Arc<AtomicBool>>,
+    pub(super) stop_handle: StopHandle,
+    pub(super) time_manager: TimeManager,
+    pub(super) time_budget: Cell<Option<TimeBudget>>,
+    pub(super) t_table: TTable,
     pub(super) z_data: RefCell<ZobristData>,
+    pub(super) pv_table: RefCell<Vec<Vec<Move>>>,
+    pub(super) stats: RefCell<SearchStats>,
 }
 
 impl<T: MancalaZobrist> From<MinimaxBuilder<T>> for Minimax<T> {
@@ -99,97 +143,202 @@ impl<T: MancalaZobrist> Minimax<T> {
         self.use_t_table
     }
 
+    /// Returns the number of threads used during search.
+    #[inline]
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Returns the seed used to generate Zobrist data.
+    #[inline]
+    pub fn zobrist_seed(&self) -> u64 {
+        self.zobrist_seed
+    }
+
     /// Returns the start time (if currently running) of the algorithm.
     #[inline]
     pub fn start_time(&self) -> Option<Instant> {
         self.start_time.get()
     }
 
+    /// Returns a handle which stops the searches run by this instance (see
+    /// [`StopHandle`]). The handle is reset whenever a search starts, so a stop
+    /// requested before a search starts has no effect on it.
+    #[inline]
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop_handle.clone()
+    }
+
+    /// Returns the policy used to budget the time for each move when playing
+    /// under a game clock.
+    #[inline]
+    pub fn time_manager(&self) -> TimeManager {
+        self.time_manager
+    }
+
+    /// Removes every entry from the transposition table, so that the next search
+    /// does not reuse any results from earlier searches.
+    pub fn clear_t_table(&self) {
+        self.t_table.clear();
+    }
+
     /// Returns a reference to the current Zobrist data.
     #[inline]
     pub fn z_data(&self) -> &RefCell<ZobristData> {
         &self.z_data
     }
 
-    /// Calls the move ordering function on a given state.
+    /// Calls the move orderer on a given state.
     #[inline]
     pub fn order_moves(&self, state: &T) -> Vec<Move> {
-        (self.move_orderer)(state)
+        self.move_orderer.order_moves(state)
     }
 
-    /// Calls the evaluation function on a given state.
+    /// Calls the evaluator on a given state.
     #[inline]
     pub fn evaluate(&self, state: &T) -> f32 {
-        (self.evaluator)(state, self.optimize_for)
+        self.evaluator.evaluate(state, self.optimize_for)
     }
 
-    /// Calls the heuristic function on a given state.
+    /// Calls the heuristic on a given state.
     #[inline]
     pub fn get_heuristic(&self, state: &T) -> f32 {
-        (self.heuristic)(state, self.optimize_for)
+        self.heuristic.evaluate(state, self.optimize_for)
     }
 
     /// Search for the optimal move using the minimax algorithm and
     /// alpha-beta pruning, based on the set configuration parameters.
     ///
+    /// The result includes the principal variation (i.e., the line of play
+    /// expected from both players) starting with the found move.
+    ///
+    /// If an opening book is set (see [`set_opening_book`][Self::set_opening_book])
+    /// and contains the state, a valid book move is returned without searching.
+    /// If none of the moves stored for the state are valid, it is searched instead.
+    ///
+    /// If the search is stopped using the [`stop_handle`][Self::stop_handle], the
+    /// result of the last completed iteration is returned, as if the time limit
+    /// had expired.
+    ///
     /// If no move was found successfully, returns [`None`].
     pub fn search_utility(&self, state: &T) -> Option<SearchResult> {
+        self.stop_handle.reset();
+        self.search_without_reset(state)
+    }
+
+    /// Same as [`search_utility`][Self::search_utility], but without clearing
+    /// any stop request first, so that a search started in another thread (e.g.,
+    /// by [`ponder`][Self::ponder]) can be stopped before it begins.
+    pub(super) fn search_without_reset(&self, state: &T) -> Option<SearchResult> {
+        if let Some(m) = self.opening_book.as_ref().and_then(|b| b.choose(state)) {
+            let utility = if state.current_turn() == self.optimize_for {
+                m.utility
+            } else {
+                -m.utility
+            };
+            return Some(SearchResult {
+                found_move: m.selection,
+                utility,
+                depth_searched: Some(0),
+                fully_searched: false,
+                principal_variation: vec![m.selection],
+                stats: SearchStats::default(),
+                iteration_stats: Vec::new(),
+                from_book: true,
+            });
+        }
+
         self.start_time.set(Some(Instant::now()));
         let mut found_move: Option<Move> = None;
         let mut utility = f32::NEG_INFINITY;
         let mut depth_searched: Option<usize> = self.max_depth;
         let mut fully_searched = false;
+        let mut principal_variation = Vec::new();
+        let mut iteration_stats = Vec::new();
 
         // Ensure the current Zobrist values are valid.
-        if !self.z_data.borrow().is_valid_for(state) {
-            self.z_data
-                .replace(ZobristData::for_state_like(state, 0x49CB86856BB06133));
-        }
-
-        if self.iterative_deepening {
-            for limit in 1usize.. {
-                if fully_searched
-                    || self.max_depth.is_some_and(|d| limit > d)
-                    || self.time_exceeded()
-                {
-                    break;
-                }
-                (found_move, utility, fully_searched) =
-                    match self.max_value(state, f32::NEG_INFINITY, f32::INFINITY, 0, Some(limit)) {
+        let mut root = self.prepare_root(state);
+        self.with_helpers(&mut root, |state| {
+            if self.iterative_deepening {
+                let mut stable_iterations = 0;
+                for limit in 1usize.. {
+                    if fully_searched
+                        || self.max_depth.is_some_and(|d| limit > d)
+                        || self.time_exceeded()
+                        || self.budget_spent(stable_iterations)
+                    {
+                        break;
+                    }
+                    let start = self.start_iteration(Some(limit));
+                    let result =
+                        self.max_value(state, f32::NEG_INFINITY, f32::INFINITY, 0, Some(limit));
+                    self.finish_iteration(start, result.is_node(), &mut iteration_stats);
+                    (found_move, utility, fully_searched) = match result {
                         InternalResult::Node {
                             found_move: m,
                             utility: v,
                             fully_searched: f,
                         } if m.is_some() => {
+                            stable_iterations = if m == found_move {
+                                stable_iterations + 1
+                            } else {
+                                0
+                            };
                             depth_searched = Some(limit);
+                            principal_variation = self.principal_variation(state, 0, Some(limit));
                             (m, v, f)
                         }
                         _ => break,
                     };
-            }
-        } else {
-            (found_move, utility, fully_searched) =
-                match self.max_value(state, f32::NEG_INFINITY, f32::INFINITY, 0, self.max_depth) {
+                }
+            } else {
+                let start = self.start_iteration(self.max_depth);
+                let result =
+                    self.max_value(state, f32::NEG_INFINITY, f32::INFINITY, 0, self.max_depth);
+                self.finish_iteration(start, result.is_node(), &mut iteration_stats);
+                (found_move, utility, fully_searched) = match result {
                     InternalResult::Node {
                         found_move: m,
                         utility: v,
                         fully_searched: f,
-                    } => (m, v, f),
+                    } => {
+                        principal_variation = self.principal_variation(state, 0, self.max_depth);
+                        (m, v, f)
+                    }
                     _ => (found_move, utility, fully_searched),
                 }
-        }
+            }
+        });
 
         self.start_time.set(None);
 
-        match found_move {
-            None => None,
-            Some(m) => Some(SearchResult {
-                found_move: m,
-                utility,
-                depth_searched,
-                fully_searched,
-            }),
-        }
+        found_move.map(|m| SearchResult {
+            found_move: m,
+            utility,
+            depth_searched,
+            fully_searched,
+            principal_variation,
+            stats: iteration_stats.iter().sum(),
+            iteration_stats,
+            from_book: false,
+        })
+    }
+
+    /// Search for the optimal move (see [`search_utility`][Self::search_utility])
+    /// within the supplied time budget, instead of the maximum search time
+    /// (e.g., a budget computed from a game clock by the
+    /// [`time_manager`][Self::time_manager]).
+    ///
+    /// With iterative deepening, no further iterations are started once the
+    /// target time (scaled by how long the best move has stayed the same) has
+    /// passed, and the search is always stopped at the maximum time.
+    ///
+    /// If no move was found successfully, returns [`None`].
+    pub fn search_timed(&self, state: &T, budget: TimeBudget) -> Option<SearchResult> {
+        self.time_budget.set(Some(budget));
+        let result = self.search_utility(state);
+        self.time_budget.set(None);
+        result
     }
 
     /// Search for all possible moves and their utilities using the minimax algorithm
@@ -199,36 +348,59 @@ impl<T: MancalaZobrist> Minimax<T> {
     /// disabled for the first call to the utility maximizer. This decreases performance
     /// by a significant amount.
     ///
+    /// With the `parallel` feature enabled, the moves are evaluated in parallel on
+    /// the rayon thread pool, unless a transposition table is used with a depth
+    /// limit (since each utility may then depend on the entries stored while
+    /// evaluating the previous moves). Each move is searched against a snapshot
+    /// of the transposition table taken before any move was evaluated, so results
+    /// do not depend on how the moves are scheduled, and the utilities match
+    /// those found without the `parallel` feature.
+    ///
+    /// The result includes the principal variation (i.e., the line of play
+    /// expected from both players) starting with each move.
+    ///
+    /// If the search is stopped using the [`stop_handle`][Self::stop_handle], the
+    /// result of the last completed iteration is returned.
+    ///
     /// If no moves could be successfully evaluated, returns [`None`].
     pub fn search_utility_all(&self, state: &T) -> Option<MultiSearchResult> {
+        self.stop_handle.reset();
         self.start_time.set(Some(Instant::now()));
         let mut result: Option<MultiSearchResult> = None;
+        let mut iteration_stats = Vec::new();
 
         // Ensure the current Zobrist values are valid.
-        if !self.z_data.borrow().is_valid_for(state) {
-            self.z_data
-                .replace(ZobristData::for_state_like(state, 0x49CB86856BB06133));
-        }
-
-        if self.iterative_deepening {
-            for limit in 1usize.. {
-                if result.as_ref().is_some_and(|r| r.fully_searched)
-                    || self.max_depth.is_some_and(|d| limit > d)
-                    || self.time_exceeded()
-                {
-                    break;
+        let mut root = self.prepare_root(state);
+        self.with_helpers(&mut root, |state| {
+            if self.iterative_deepening {
+                for limit in 1usize.. {
+                    if result.as_ref().is_some_and(|r| r.fully_searched)
+                        || self.max_depth.is_some_and(|d| limit > d)
+                        || self.time_exceeded()
+                    {
+                        break;
+                    }
+                    let start = self.start_iteration(Some(limit));
+                    let r = self.max_value_all(state, 0, Some(limit));
+                    self.finish_iteration(start, r.is_some(), &mut iteration_stats);
+                    result = match r {
+                        Some(r) => Some(r),
+                        None => break,
+                    };
                 }
-                result = match self.max_value_all(state, 0, Some(limit)) {
-                    Some(r) => Some(r),
-                    None => break,
-                };
-            }
-        } else {
-            result = self.max_value_all(state, 0, self.max_depth);
-        };
+            } else {
+                let start = self.start_iteration(self.max_depth);
+                result = self.max_value_all(state, 0, self.max_depth);
+                self.finish_iteration(start, result.is_some(), &mut iteration_stats);
+            };
+        });
 
         self.start_time.set(None);
-        result
+        result.map(|r| MultiSearchResult {
+            stats: iteration_stats.iter().sum(),
+            iteration_stats,
+            ..r
+        })
     }
 
     /// Search for the optimal move using the minimax algorithm and
@@ -242,21 +414,191 @@ impl<T: MancalaZobrist> Minimax<T> {
         self.search_utility(state).map(|r| r.found_move)
     }
 
-    /// Determines whether the algorithm has been running longer than requested.
+    /// Ensures the current Zobrist data is valid for the supplied state (i.e., the
+    /// state has the same number of pits, total stones, and rule set). If not,
+    /// new data is generated, and the transposition table is cleared, since its
+    /// entries were hashed using the old data.
+    pub(super) fn prepare_z_data(&self, state: &T) {
+        if !self.z_data.borrow().is_valid_for(state) {
+            self.z_data
+                .replace(ZobristData::for_state_like(state, self.zobrist_seed));
+            self.t_table.clear();
+        }
+    }
+
+    /// Prepares the Zobrist data for the supplied state (see [`prepare_z_data`]),
+    /// and starts a new search generation in the transposition table.
+    ///
+    /// Returns a copy of the state with its Zobrist hash computed from scratch, so
+    /// that states which were not reached via [`MancalaZobrist::make_move_zobrist`]
+    /// (e.g., parsed states, or those created with
+    /// [`Mancala::make_move`][crate::game::Mancala::make_move]) still
+    /// share transposition table entries correctly between searches.
+    ///
+    /// [`prepare_z_data`]: Self::prepare_z_data
+    fn prepare_root(&self, state: &T) -> T {
+        self.prepare_z_data(state);
+        self.t_table.new_search();
+        let mut root = state.clone();
+        root.set_zobrist_hash(self.z_data.borrow().hash_of(state));
+        root
+    }
+
+    /// Resets the statistics at the start of a search iteration with the supplied
+    /// depth limit, and returns the time at which it started.
+    fn start_iteration(&self, limit: Option<usize>) -> Instant {
+        self.stats.replace(SearchStats::new(limit));
+        Instant::now()
+    }
+
+    /// Records the statistics for a finished (or timed out) search iteration,
+    /// and passes them to the iteration callback, if one is set.
+    fn finish_iteration(&self, start: Instant, completed: bool, iterations: &mut Vec<SearchStats>) {
+        let mut stats = self.stats.take();
+        stats.elapsed = start.elapsed();
+        stats.completed = completed;
+        if let Some(IterationCallback(callback)) = &self.on_iteration {
+            callback(&stats);
+        }
+        iterations.push(stats);
+    }
+
+    /// Runs `search` on the supplied root state, while a helper thread for each
+    /// thread after the first searches a copy of the root state, sharing the
+    /// transposition table (i.e., "Lazy SMP"). Helpers only fill the table, so
+    /// `search` still produces the result. Helpers are stopped once it returns.
+    ///
+    /// If only one thread is used, or the transposition table is disabled (so the
+    /// helpers would not share any work), `search` is run without helpers.
+    fn with_helpers<R>(&self, root: &mut T, search: impl FnOnce(&mut T) -> R) -> R {
+        if self.threads <= 1 || !self.use_t_table {
+            return search(root);
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let helpers: Vec<Minimax<T>> = (1..self.threads)
+            .map(|_| self.helper(stop.clone()))
+            .collect();
+
+        thread::scope(|scope| {
+            for (i, helper) in helpers.into_iter().enumerate() {
+                let root = root.clone();
+                // Every search maximizes the utility at the root, even if it is
+                // not the turn of the player it optimizes for.
+                scope.spawn(move || helper.help(root, i + 1, true));
+            }
+            let result = search(root);
+            stop.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+
+    /// Creates a copy of the current configuration for use in a helper thread,
+    /// sharing the transposition table and stopping once `stop` is set.
+    pub(super) fn helper(&self, stop: Arc<AtomicBool>) -> Minimax<T> {
+        Minimax {
+            on_iteration: None,
+            start_time: self.start_time.clone(),
+            time_budget: self.time_budget.clone(),
+            stop: Some(stop),
+            t_table: self.t_table.share(),
+            z_data: self.z_data.clone(),
+            ..self.clone_config()
+        }
+    }
+
+    /// Creates a copy of the current configuration for evaluating a single root
+    /// move, reading from (but not writing to) the transposition table.
+    #[cfg(feature = "parallel")]
+    fn root_worker(&self) -> Minimax<T> {
+        Minimax {
+            start_time: self.start_time.clone(),
+            time_budget: self.time_budget.clone(),
+            stop: self.stop.clone(),
+            t_table: self.t_table.overlay(),
+            z_data: self.z_data.clone(),
+            ..self.clone_config()
+        }
+    }
+
+    /// Creates a copy of the configuration only (without the transposition
+    /// table or search state).
+    fn clone_config(&self) -> Minimax<T> {
+        MinimaxBuilder::from(self)
+            .threads(1)
+            .t_table_megabytes(0)
+            .build()
+    }
+
+    /// Searches the root state in a helper thread using iterative deepening,
+    /// until the search is complete or the helper is stopped. Odd-numbered
+    /// helpers start one level deeper, so that the threads search different
+    /// depths at the same time.
+    ///
+    /// The utility is maximized at the root if `maximize` is set, and minimized
+    /// otherwise (e.g., when pondering the opponent's moves). Since the table is
+    /// shared, this must match how the root is searched by every other thread.
+    pub(super) fn help(&self, mut root: T, index: usize, maximize: bool) {
+        let state = &mut root;
+        for limit in (1 + index % 2).. {
+            if self.max_depth.is_some_and(|d| limit > d) || self.time_exceeded() {
+                break;
+            }
+            let result = if maximize {
+                self.max_value(state, f32::NEG_INFINITY, f32::INFINITY, 0, Some(limit))
+            } else {
+                self.min_value(state, f32::NEG_INFINITY, f32::INFINITY, 0, Some(limit))
+            };
+            match result {
+                InternalResult::Node {
+                    fully_searched: false,
+                    ..
+                } => {}
+                _ => break,
+            }
+        }
+    }
+
+    /// Determines whether the algorithm has been running longer than requested,
+    /// or whether the search has been stopped (either using the stop handle, or,
+    /// for helper threads, by the main thread).
     ///
     /// Used internally inside [`max_value`] and [`min_value`].
     fn time_exceeded(&self) -> bool {
-        match (self.start_time.get(), self.max_time) {
+        if self.stop_handle.is_stopped() {
+            return true;
+        }
+        if self
+            .stop
+            .as_ref()
+            .is_some_and(|s| s.load(Ordering::Relaxed))
+        {
+            return true;
+        }
+        let max_time = match self.time_budget.get() {
+            Some(budget) => Some(budget.maximum),
+            None => self.max_time,
+        };
+        match (self.start_time.get(), max_time) {
             (Some(start), Some(max)) => Instant::now() - start >= max,
             _ => false,
         }
     }
 
+    /// Determines whether the target time of the current time budget (if any)
+    /// has passed, so that no further iterations should be started.
+    fn budget_spent(&self, stable_iterations: usize) -> bool {
+        match (self.start_time.get(), self.time_budget.get()) {
+            (Some(start), Some(budget)) => budget.should_stop(start.elapsed(), stable_iterations),
+            _ => false,
+        }
+    }
+
     /// Maximize the utility / heuristic for a given state, and return
     /// the utilities for each checked move.
     fn max_value_all(
         &self,
-        state: &T,
+        state: &mut T,
         depth: usize,
         limit: Option<usize>,
     ) -> Option<MultiSearchResult> {
@@ -270,44 +612,146 @@ impl<T: MancalaZobrist> Minimax<T> {
             return None;
         }
 
-        let depth = depth + 1;
-        let mut move_util_term: Vec<(Move, f32, bool)> = Vec::new();
-
-        for m in self.order_moves_with_tt(state) {
-            let new_state = state.make_move_zobrist(&self.z_data.borrow(), m).unwrap();
+        self.stats.borrow_mut().nodes += 1;
+        let moves = self.order_moves_with_tt(state);
 
-            let (utility, terminal) = {
-                let result = if new_state.current_turn() == state.current_turn() {
-                    self.max_value(&new_state, f32::NEG_INFINITY, f32::INFINITY, depth, limit)
-                } else {
-                    self.min_value(&new_state, f32::NEG_INFINITY, f32::INFINITY, depth, limit)
-                };
-                match result {
-                    InternalResult::Node {
-                        utility: v,
-                        fully_searched: f,
-                        ..
-                    } => (v, f),
-                    InternalResult::Timeout => return None,
-                }
-            };
-
-            move_util_term.push((m, utility, terminal));
-        }
+        // A depth-limited utility may depend on the transposition table entries
+        // stored while evaluating the previous moves, so those moves are only
+        // evaluated in parallel when the results cannot differ.
+        #[cfg(feature = "parallel")]
+        let evaluated = if self.use_t_table && limit.is_some() {
+            self.evaluate_root_moves(state, &moves, depth + 1, limit)?
+        } else {
+            self.evaluate_root_moves_parallel(state, &moves, depth + 1, limit)?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let evaluated = self.evaluate_root_moves(state, &moves, depth + 1, limit)?;
 
         Some(MultiSearchResult {
-            found_moves: move_util_term.iter().map(|(m, _, _)| m.clone()).collect(),
-            utilities: move_util_term.iter().map(|(_, v, _)| *v).collect(),
+            found_moves: moves,
+            utilities: evaluated.iter().map(|(v, _, _)| *v).collect(),
             depth_searched: limit,
-            fully_searched: move_util_term.iter().all(|(_, _, t)| *t),
+            fully_searched: evaluated.iter().all(|(_, t, _)| *t),
+            principal_variations: evaluated.into_iter().map(|(_, _, pv)| pv).collect(),
+            stats: SearchStats::default(),
+            iteration_stats: Vec::new(),
         })
     }
 
+    /// Evaluates a single root move using the full search window (i.e., without
+    /// alpha-beta pruning between root moves), and returns its utility, whether
+    /// it was fully searched, and its principal variation. Returns [`None`] if
+    /// the time limit was exceeded.
+    fn evaluate_root_move(
+        &self,
+        state: &mut T,
+        m: Move,
+        depth: usize,
+        limit: Option<usize>,
+    ) -> Option<(f32, bool, Vec<Move>)> {
+        let result = self.search_child(state, m, |state, same_turn| {
+            if same_turn {
+                self.max_value(state, f32::NEG_INFINITY, f32::INFINITY, depth, limit)
+            } else {
+                self.min_value(state, f32::NEG_INFINITY, f32::INFINITY, depth, limit)
+            }
+        });
+
+        match result {
+            InternalResult::Node {
+                utility: v,
+                fully_searched: f,
+                ..
+            } => {
+                self.update_pv(depth - 1, m);
+                Some((v, f, self.principal_variation(state, depth - 1, limit)))
+            }
+            InternalResult::Timeout => None,
+        }
+    }
+
+    /// Evaluates each root move in turn (see
+    /// [`evaluate_root_move`][Self::evaluate_root_move]). Returns [`None`] if the
+    /// time limit was exceeded.
+    fn evaluate_root_moves(
+        &self,
+        state: &mut T,
+        moves: &[Move],
+        depth: usize,
+        limit: Option<usize>,
+    ) -> Option<Vec<(f32, bool, Vec<Move>)>> {
+        moves
+            .iter()
+            .map(|m| self.evaluate_root_move(state, *m, depth, limit))
+            .collect()
+    }
+
+    /// Evaluates each root move in parallel on the rayon thread pool, using a
+    /// separate worker for each move.
+    ///
+    /// Every worker reads from the transposition table, which is not modified
+    /// until every move has been evaluated, and writes to its own private table.
+    /// The private tables are merged back in move order afterward, so the
+    /// results never depend on how the workers were scheduled.
+    #[cfg(feature = "parallel")]
+    fn evaluate_root_moves_parallel(
+        &self,
+        state: &T,
+        moves: &[Move],
+        depth: usize,
+        limit: Option<usize>,
+    ) -> Option<Vec<(f32, bool, Vec<Move>)>> {
+        use rayon::prelude::*;
+
+        let workers: Vec<Minimax<T>> = moves.iter().map(|_| self.root_worker()).collect();
+
+        let (workers, evaluated): (Vec<_>, Vec<_>) = workers
+            .into_par_iter()
+            .zip(moves.par_iter())
+            .map(|(worker, m)| {
+                let mut state = state.clone();
+                let result = worker.evaluate_root_move(&mut state, *m, depth, limit);
+                (worker, result)
+            })
+            .unzip();
+
+        let mut overlays = Vec::with_capacity(workers.len());
+        for worker in workers {
+            self.stats.borrow_mut().add_counts(&worker.stats.borrow());
+            overlays.push(worker.t_table);
+        }
+        self.t_table.merge(overlays);
+
+        evaluated.into_iter().collect()
+    }
+
+    /// Makes a move from the supplied state, then runs `search` on the state it
+    /// leads to, passing whether the same player moves again.
+    ///
+    /// The move is made in place and taken back once `search` returns, unless
+    /// states of this type are cheaper to copy (see
+    /// [`MancalaZobrist::SEARCH_IN_PLACE`]).
+    #[inline]
+    fn search_child<R>(&self, state: &mut T, m: Move, search: impl FnOnce(&mut T, bool) -> R) -> R {
+        let mover = state.current_turn();
+        if T::SEARCH_IN_PLACE {
+            let undo = state.apply_move_zobrist(&self.z_data.borrow(), m).unwrap();
+            let same_turn = state.current_turn() == mover;
+            let result = search(state, same_turn);
+            state.undo_move_zobrist(undo);
+            result
+        } else {
+            let mut child = state.make_move_zobrist(&self.z_data.borrow(), m).unwrap();
+            let same_turn = child.current_turn() == mover;
+            search(&mut child, same_turn)
+        }
+    }
+
     /// Maximize the utility / heuristic for a given state, and return the
     /// move and associated utility that do so.
     fn max_value(
         &self,
-        state: &T,
+        state: &mut T,
         mut alpha: f32,
         mut beta: f32,
         depth: usize,
@@ -318,6 +762,10 @@ impl<T: MancalaZobrist> Minimax<T> {
             "Minimax search must be started with `search_utility()` before calling `max_value`"
         );
 
+        // Start a new principal variation for this depth.
+        self.stats.borrow_mut().nodes += 1;
+        self.clear_pv(depth);
+
         // Run the common starting procedure.
         let (early_result, alpha_orig, beta_orig, remaining) =
             self.max_min_preamble(state, &mut alpha, &mut beta, depth, limit);
@@ -329,15 +777,15 @@ impl<T: MancalaZobrist> Minimax<T> {
         let mut found_move: Option<Move> = None;
         let mut fully_searched = true;
 
-        for m in self.order_moves_with_tt(state) {
-            let new_state = state.make_move_zobrist(&self.z_data.borrow(), m).unwrap();
-
+        for (i, m) in self.order_moves_with_tt(state).into_iter().enumerate() {
             let (v2, local_terminal) = {
-                let result = if new_state.current_turn() == state.current_turn() {
-                    self.max_value(&new_state, alpha, beta, depth + 1, limit)
-                } else {
-                    self.min_value(&new_state, alpha, beta, depth + 1, limit)
-                };
+                let result = self.search_child(state, m, |state, same_turn| {
+                    if same_turn {
+                        self.max_value(state, alpha, beta, depth + 1, limit)
+                    } else {
+                        self.min_value(state, alpha, beta, depth + 1, limit)
+                    }
+                });
                 match result {
                     InternalResult::Node {
                         utility: v,
@@ -352,12 +800,14 @@ impl<T: MancalaZobrist> Minimax<T> {
                 v = v2;
                 found_move = Some(m);
                 alpha = alpha.max(v);
+                self.update_pv(depth, m);
             }
 
             fully_searched &= local_terminal;
 
             // Alpha > beta: prune.
             if v >= beta {
+                self.stats.borrow_mut().record_cutoff(i);
                 break;
             }
         }
@@ -384,7 +834,7 @@ impl<T: MancalaZobrist> Minimax<T> {
     /// move and associated utility that do so.
     fn min_value(
         &self,
-        state: &T,
+        state: &mut T,
         mut alpha: f32,
         mut beta: f32,
         depth: usize,
@@ -395,6 +845,10 @@ impl<T: MancalaZobrist> Minimax<T> {
             "Minimax search must be started with `search_utility()` before calling `min_value`"
         );
 
+        // Start a new principal variation for this depth.
+        self.stats.borrow_mut().nodes += 1;
+        self.clear_pv(depth);
+
         // Run the common starting procedure.
         let (early_result, alpha_orig, beta_orig, remaining) =
             self.max_min_preamble(state, &mut alpha, &mut beta, depth, limit);
@@ -406,15 +860,15 @@ impl<T: MancalaZobrist> Minimax<T> {
         let mut found_move: Option<Move> = None;
         let mut fully_searched = true;
 
-        for m in self.order_moves_with_tt(state) {
-            let new_state = state.make_move_zobrist(&self.z_data.borrow(), m).unwrap();
-
+        for (i, m) in self.order_moves_with_tt(state).into_iter().enumerate() {
             let (v2, local_terminal) = {
-                let result = if new_state.current_turn() == state.current_turn() {
-                    self.min_value(&new_state, alpha, beta, depth + 1, limit)
-                } else {
-                    self.max_value(&new_state, alpha, beta, depth + 1, limit)
-                };
+                let result = self.search_child(state, m, |state, same_turn| {
+                    if same_turn {
+                        self.min_value(state, alpha, beta, depth + 1, limit)
+                    } else {
+                        self.max_value(state, alpha, beta, depth + 1, limit)
+                    }
+                });
                 match result {
                     InternalResult::Node {
                         utility: v,
@@ -429,12 +883,14 @@ impl<T: MancalaZobrist> Minimax<T> {
                 v = v2;
                 found_move = Some(m);
                 beta = beta.min(v);
+                self.update_pv(depth, m);
             }
 
             fully_searched &= local_terminal;
 
             // Alpha > beta: prune.
             if v <= alpha {
+                self.stats.borrow_mut().record_cutoff(i);
                 break;
             }
         }
@@ -460,6 +916,7 @@ impl<T: MancalaZobrist> Minimax<T> {
     /// Helper function that performs the following actions at the beginning
     /// of either [`max_value`] or [`min_value`]:
     /// - Check if the state is a terminal state.
+    /// - Check if the state is in the endgame database (below the root only).
     /// - Check if a valid result is in the transposition table.
     /// - Modify the search bounds based on the transposition table, if necessary.
     /// - Check if the depth limit has been reached.
@@ -479,6 +936,7 @@ impl<T: MancalaZobrist> Minimax<T> {
 
         // If we are in a terminal state, evaluate utility.
         if state.is_over() {
+            self.stats.borrow_mut().exact_evaluations += 1;
             let r = InternalResult::Node {
                 found_move: None,
                 utility: self.evaluate(state),
@@ -487,6 +945,25 @@ impl<T: MancalaZobrist> Minimax<T> {
             return (Some(r), alpha_orig, beta_orig, remaining);
         }
 
+        // If the state is in the endgame database, use its exact value. The root
+        // is always searched, so that a move is found.
+        if depth > 0
+            && let Some(value) = self.endgame_db.as_ref().and_then(|db| db.probe(state))
+        {
+            self.stats.borrow_mut().exact_evaluations += 1;
+            let utility = if state.current_turn() == self.optimize_for {
+                value
+            } else {
+                -value
+            };
+            let r = InternalResult::Node {
+                found_move: None,
+                utility: utility as f32,
+                fully_searched: true,
+            };
+            return (Some(r), alpha_orig, beta_orig, remaining);
+        }
+
         // Check transposition table, and narrow bounds if necessary.
         if let Some(r) = self.tt_probe(state, remaining, alpha, beta) {
             return (Some(r), alpha_orig, beta_orig, remaining);
@@ -494,6 +971,7 @@ impl<T: MancalaZobrist> Minimax<T> {
 
         // If we have reached the artificial depth limit, use the heuristic.
         if limit.is_some_and(|d| depth >= d) {
+            self.stats.borrow_mut().heuristic_evaluations += 1;
             let r = InternalResult::Node {
                 found_move: None,
                 utility: self.get_heuristic(state),
@@ -528,13 +1006,19 @@ impl<T: MancalaZobrist> Minimax<T> {
             return None;
         }
 
-        self.t_table
-            .borrow()
-            .get(&state.zobrist_hash())
-            .and_then(|e| e.probe(remaining, alpha, beta))
+        let mut stats = self.stats.borrow_mut();
+        stats.tt_probes += 1;
+        let entry = self.t_table.get(state.zobrist_hash())?;
+        stats.tt_hits += 1;
+        let result = entry.probe(remaining, alpha, beta);
+        if result.is_some() {
+            stats.tt_cutoffs += 1;
+        }
+        result
     }
 
     /// Helper function to store an evaluated state in the transposition table.
+    #[allow(clippy::too_many_arguments)]
     fn tt_store(
         &self,
         state: &T,
@@ -558,23 +1042,70 @@ impl<T: MancalaZobrist> Minimax<T> {
             alpha_orig,
             beta_orig,
         );
-        let mut table = self.t_table.borrow_mut();
-        if table.get(&key).is_none_or(|old| {
-            (entry.remaining >= old.remaining) || (entry.fully_searched && !old.fully_searched)
-        }) {
-            table.insert(key, entry);
-        }
+        self.t_table.store(key, entry);
     }
 
     /// Helper function to get the move stored for the current state's
     /// transposition table entry, if one exists.
-    fn get_tt_move(&self, state: &T) -> Option<Move> {
+    pub(super) fn get_tt_move(&self, state: &T) -> Option<Move> {
         self.t_table
-            .borrow()
-            .get(&state.zobrist_hash())
+            .get(state.zobrist_hash())
             .and_then(|e| e.found_move)
     }
 
+    /// Helper function to clear the principal variation stored for a depth,
+    /// allocating space for it if necessary.
+    fn clear_pv(&self, depth: usize) {
+        let mut table = self.pv_table.borrow_mut();
+        if table.len() <= depth {
+            table.resize_with(depth + 1, Vec::new);
+        }
+        table[depth].clear();
+    }
+
+    /// Helper function to replace the principal variation stored for a depth
+    /// with the supplied move, followed by the principal variation stored for
+    /// the next depth.
+    fn update_pv(&self, depth: usize, m: Move) {
+        let mut table = self.pv_table.borrow_mut();
+        let (current, next) = table.split_at_mut(depth + 1);
+        let current = &mut current[depth];
+        current.clear();
+        current.push(m);
+        current.extend_from_slice(&next[0]);
+    }
+
+    /// Helper function to get the principal variation collected for a depth,
+    /// starting from the supplied state.
+    ///
+    /// Lines cut short by a transposition table entry are extended by following
+    /// the moves stored in the table, until the depth limit is reached, the game
+    /// ends, or a position repeats.
+    fn principal_variation(&self, state: &T, depth: usize, limit: Option<usize>) -> Vec<Move> {
+        let mut pv = self.pv_table.borrow()[depth].clone();
+        if !self.use_t_table {
+            return pv;
+        }
+
+        // Play the collected line, then follow the stored moves.
+        let data = self.z_data.borrow();
+        let mut state = state.clone();
+        for m in &pv {
+            state.apply_move_zobrist(&data, *m).unwrap();
+        }
+        let mut seen = FxHashSet::default();
+        while limit.is_none_or(|l| depth + pv.len() < l) && seen.insert(state.zobrist_hash()) {
+            match self.get_tt_move(&state) {
+                Some(m) if state.is_valid_move(m) => {
+                    state.apply_move_zobrist(&data, m).unwrap();
+                    pv.push(m);
+                }
+                _ => break,
+            }
+        }
+        pv
+    }
+
     /// Helper function to order the moves during minimax search, ensuring
     /// that the transposition entry is tried first, if it exists.
     fn order_moves_with_tt(&self, state: &T) -> Vec<Move> {
@@ -584,11 +1115,11 @@ impl<T: MancalaZobrist> Minimax<T> {
             return moves;
         }
 
-        if let Some(tt_move) = self.get_tt_move(state) {
-            if let Some(pos) = moves.iter().position(|m| *m == tt_move) {
-                let m = moves.remove(pos);
-                moves.insert(0, m);
-            }
+        if let Some(tt_move) = self.get_tt_move(state)
+            && let Some(pos) = moves.iter().position(|m| *m == tt_move)
+        {
+            let m = moves.remove(pos);
+            moves.insert(0, m);
         }
         moves
     }
@@ -604,52 +1135,17 @@ enum InternalResult {
     Timeout,
 }
 
-/// Helper enum to store transposition table entry bounds.
-#[derive(Debug, Clone, Copy)]
-enum ValueBound {
-    Exact,
-    Lower,
-    Upper,
-}
-
-/// Helper struct for storing data in the transposition table.
-#[derive(Debug, Clone, Copy)]
-pub(crate) struct TTEntry {
-    utility: f32,
-    bound: ValueBound,
-    found_move: Option<Move>,
-    fully_searched: bool,
-    remaining: usize,
+impl InternalResult {
+    /// Returns whether the search finished without timing out.
+    #[inline]
+    fn is_node(&self) -> bool {
+        matches!(self, InternalResult::Node { .. })
+    }
 }
 
 impl TTEntry {
-    fn new(
-        value: f32,
-        remaining: usize,
-        found_move: Option<Move>,
-        fully_searched: bool,
-        alpha_orig: f32,
-        beta_orig: f32,
-    ) -> Self {
-        let bound = if value <= alpha_orig {
-            ValueBound::Upper
-        } else if value >= beta_orig {
-            ValueBound::Lower
-        } else {
-            ValueBound::Exact
-        };
-
-        Self {
-            utility: value,
-            remaining,
-            bound,
-            found_move,
-            fully_searched,
-        }
-    }
-
     /// Helper method to convert TTEntry to an InternalResult.
-    fn to_internal(&self) -> InternalResult {
+    fn to_internal(self) -> InternalResult {
         InternalResult::Node {
             found_move: self.found_move,
             utility: self.utility,
@@ -684,3 +1180,224 @@ impl TTEntry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DynGameState, GameState, Kalah, Mancala};
+    use std::sync::Mutex;
+
+    #[test]
+    fn on_iteration_closure() {
+        let depths = Arc::new(Mutex::new(Vec::new()));
+        let recorded = depths.clone();
+        let minimax = MinimaxBuilder::default()
+            .max_depth(Some(4))
+            .on_iteration(Some(Arc::new(move |s: &SearchStats| {
+                recorded.lock().unwrap().push(s.depth);
+            })))
+            .build();
+        let result = minimax.search_utility(&GameState::<6>::default()).unwrap();
+        let depths = depths.lock().unwrap();
+        assert_eq!(*depths, [Some(1), Some(2), Some(3), Some(4)]);
+        assert_eq!(result.iteration_stats.len(), depths.len());
+    }
+
+    /// Plays the principal variation from the state, asserting that every move
+    /// is legal, and returns the state it leads to.
+    fn play_line<T: Mancala>(state: &T, line: &[Move]) -> T {
+        line.iter().fold(state.clone(), |s, m| {
+            s.make_move(*m)
+                .unwrap_or_else(|e| panic!("{:?} in {:?}: {}", m, line, e))
+        })
+    }
+
+    #[test]
+    fn principal_variation() {
+        let mut state = GameState::<6>::default();
+        for m in [3, 6, 2, 5] {
+            for use_t_table in [false, true] {
+                let minimax = MinimaxBuilder::default()
+                    .max_depth(Some(8))
+                    .use_t_table(use_t_table)
+                    .build();
+                let result = minimax.search_utility(&state).unwrap();
+                let pv = &result.principal_variation;
+                assert_eq!(pv.first(), Some(&result.found_move));
+                assert!(pv.len() <= 8);
+                let end = play_line(&state, pv);
+
+                // Without a table, the line leads to the state whose evaluation
+                // produced the utility.
+                if !use_t_table {
+                    assert!(pv.len() == 8 || end.is_over());
+                    assert_eq!(result.utility, minimax.evaluate(&end));
+                }
+
+                let all = minimax.search_utility_all(&state).unwrap();
+                for (m, pv) in all.found_moves.iter().zip(&all.principal_variations) {
+                    assert_eq!(pv.first(), Some(m));
+                    play_line(&state, pv);
+                }
+            }
+            state = state.make_move_pit(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn helpers_match_single_thread() {
+        // Helpers may store entries searched deeper than the main thread needs,
+        // so the utilities are only guaranteed to match once the depth limit
+        // covers the rest of the game (i.e., every value is exact).
+        // The last state has Player 2 to move, which every thread must still
+        // search by maximizing the utility at the root.
+        let mut state = GameState::<3>::try_new(3, 0, 0, Player::One, 1, false).unwrap();
+        for m in [1, 3, 2] {
+            let [single, smp] = [1, 4].map(|threads| {
+                MinimaxBuilder::default()
+                    .max_depth(Some(40))
+                    .threads(threads)
+                    .build()
+                    .search_utility(&state)
+                    .unwrap()
+            });
+            assert!(single.fully_searched && smp.fully_searched);
+            assert_eq!(smp.utility, single.utility);
+            state = state.make_move_pit(m).unwrap();
+        }
+
+        // Otherwise, helpers still produce a complete search of the same depth.
+        let state = GameState::<6>::default();
+        let smp = MinimaxBuilder::default()
+            .max_depth(Some(8))
+            .threads(4)
+            .build()
+            .search_utility(&state)
+            .unwrap();
+        assert_eq!(smp.depth_searched, Some(8));
+        assert_eq!(smp.principal_variation.first(), Some(&smp.found_move));
+        play_line(&state, &smp.principal_variation);
+    }
+
+    #[test]
+    fn in_place_matches_copying() {
+        // `GameState` is searched by copying, and `DynGameState` in place.
+        const { assert!(!GameState::<6>::SEARCH_IN_PLACE) };
+        const { assert!(DynGameState::<Kalah>::SEARCH_IN_PLACE) };
+
+        let mut state = GameState::<6>::default();
+        for m in [3, 6, 2, 5] {
+            let copied = MinimaxBuilder::default()
+                .max_depth(Some(8))
+                .build()
+                .search_utility(&state)
+                .unwrap();
+            let in_place = MinimaxBuilder::default()
+                .max_depth(Some(8))
+                .build()
+                .search_utility(&DynGameState::from(state))
+                .unwrap();
+            assert_eq!(copied.found_move, in_place.found_move);
+            assert_eq!(copied.utility, in_place.utility);
+            assert_eq!(copied.principal_variation, in_place.principal_variation);
+            assert_eq!(copied.stats.nodes, in_place.stats.nodes);
+            state = state.make_move_pit(m).unwrap();
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    mod parallel {
+        use super::*;
+
+        /// Evaluates every root move one at a time (as `search_utility_all` does
+        /// without the `parallel` feature), returning each move, its utility, and
+        /// its principal variation.
+        fn search_sequentially<T: MancalaZobrist>(
+            minimax: &Minimax<T>,
+            state: &T,
+            limit: Option<usize>,
+        ) -> Vec<(Move, f32, Vec<Move>)> {
+            let mut root = minimax.prepare_root(state);
+            minimax.start_time.set(Some(Instant::now()));
+            minimax
+                .order_moves_with_tt(&root)
+                .into_iter()
+                .map(|m| {
+                    let (v, _, pv) = minimax.evaluate_root_move(&mut root, m, 1, limit).unwrap();
+                    (m, v, pv)
+                })
+                .collect()
+        }
+
+        fn unzip_result(result: MultiSearchResult) -> Vec<(Move, f32, Vec<Move>)> {
+            result
+                .found_moves
+                .into_iter()
+                .zip(result.utilities)
+                .zip(result.principal_variations)
+                .map(|((m, v), pv)| (m, v, pv))
+                .collect()
+        }
+
+        #[test]
+        fn parallel_matches_sequential() {
+            let builder = MinimaxBuilder::default()
+                .max_depth(Some(7))
+                .iterative_deepening(false)
+                .use_t_table(false);
+            let mut state = GameState::<6>::default();
+            for m in [3, 6, 2] {
+                let parallel = builder.build().search_utility_all(&state).unwrap();
+                let sequential = search_sequentially(&builder.build(), &state, Some(7));
+                assert_eq!(unzip_result(parallel), sequential);
+                state = state.make_move_pit(m).unwrap();
+            }
+        }
+
+        #[test]
+        fn depth_limited_with_t_table() {
+            let builder = MinimaxBuilder::default()
+                .max_depth(Some(6))
+                .iterative_deepening(false)
+                .use_t_table(true);
+            let mut state = GameState::<6>::default();
+            for m in [3, 6, 2] {
+                let parallel = builder.build().search_utility_all(&state).unwrap();
+                let sequential = search_sequentially(&builder.build(), &state, Some(6));
+                assert_eq!(unzip_result(parallel), sequential);
+                state = state.make_move_pit(m).unwrap();
+            }
+        }
+
+        #[test]
+        fn parallel_with_t_table() {
+            // Exact utilities do not depend on the transposition table.
+            let state = GameState::<3>::new(2, 0, 0, Player::One, 1, false);
+            let minimax = MinimaxBuilder::default().max_depth(None).build();
+            let parallel = minimax.search_utility_all(&state).unwrap();
+            assert!(parallel.fully_searched);
+            let reference = MinimaxBuilder::default()
+                .max_depth(None)
+                .use_t_table(false)
+                .build();
+            let sequential = search_sequentially(&reference, &state, None);
+            assert_eq!(
+                parallel.found_moves,
+                sequential.iter().map(|r| r.0).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                parallel.utilities,
+                sequential.iter().map(|r| r.1).collect::<Vec<_>>()
+            );
+
+            // Results do not depend on how the moves were scheduled.
+            let builder = MinimaxBuilder::default().max_depth(Some(9));
+            let state = GameState::<6>::default();
+            let first = builder.build().search_utility_all(&state).unwrap();
+            for _ in 0..3 {
+                let again = builder.build().search_utility_all(&state).unwrap();
+                assert_eq!(unzip_result(again), unzip_result(first.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Opening books, which store precomputed moves for the first few plies of a
+//! game, so that [`Minimax`] does not have to repeat the same deep searches at
+//! the start of every game.
+//!
+//! Books are stored in a versioned binary file (all values little-endian),
+//! which starts with the same magic bytes, version, and Zobrist parameters as
+//! a [transposition table file][super::persist] (using the magic bytes `MMOB`),
+//! followed by:
+//!
+//! | Field     | Size     | Description                                          |
+//! |-----------|----------|------------------------------------------------------|
+//! | Positions | 8 bytes  | The number of positions which follow                 |
+//! | Key       | 8 bytes  | The Zobrist hash of the position                     |
+//! | Moves     | 1 byte   | The number of moves which follow                     |
+//! | Move      | 2 bytes  | The pit number, or `0` for the swap move             |
+//! | Weight    | 2 bytes  | The relative probability of choosing the move        |
+//! | Utility   | 4 bytes  | The utility of the move for the player to move       |
+//!
+//! The fields from `Key` onward are repeated for each position, and the fields
+//! from `Move` onward are repeated for each of its moves. Therefore, at most 255
+//! moves can be stored per position, and pit numbers cannot exceed 65,535.
+
+use super::persist::{PersistError, ZobristHeader, read_u32, read_u64};
+use super::{MancalaZobrist, Minimax, MinimaxBuilder, ZobristData};
+use crate::game::{Mancala, Move, Player};
+use rand::seq::IndexedRandom;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The bytes identifying an opening book file.
+const MAGIC: [u8; 4] = *b"MMOB";
+
+/// The current version of the opening book file format.
+const VERSION: u32 = 1;
+
+/// A move stored in an [`OpeningBook`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookMove {
+    /// The move to make.
+    pub selection: Move,
+    /// The relative probability of choosing the move, compared to the other
+    /// moves for the same position.
+    pub weight: u16,
+    /// The utility of the move, from the perspective of the player making it.
+    pub utility: f32,
+}
+
+/// A collection of positions and the moves which should be played from them,
+/// keyed by the Zobrist hash of each position.
+///
+/// Books only contain positions with the same number of pits, total stones,
+/// and rule set as the state they were generated from.
+#[derive(Clone)]
+pub struct OpeningBook {
+    z_data: ZobristData,
+    positions: FxHashMap<u64, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    /// Generates a book for every position reached within `plies` moves of the
+    /// root state, by searching each position with [`Minimax`] (configured by
+    /// the supplied builder, but optimizing for the player to move).
+    ///
+    /// Every move whose utility is within `margin` of the best move's utility is
+    /// added to the book (and the positions it leads to are searched in turn),
+    /// so a margin of `0.0` only includes moves which are equally good. Each move
+    /// is weighted by one more than the amount (rounded to the nearest whole
+    /// number) by which it falls within the margin, so better moves are played
+    /// more often, without making engine-vs-engine games identical every time.
+    pub fn generate<T: MancalaZobrist>(
+        builder: &MinimaxBuilder<T>,
+        root: &T,
+        plies: usize,
+        margin: f32,
+    ) -> Self {
+        // A separate engine for each player, so that their transposition tables
+        // can be reused between positions.
+        let engines = [Player::One, Player::Two].map(|p| builder.clone().optimize_for(p).build());
+        let z_data = ZobristData::for_state_like(root, engines[0].zobrist_seed());
+        let mut positions = FxHashMap::default();
+
+        let mut frontier = vec![root.clone()];
+        for _ in 0..plies {
+            let mut next = Vec::new();
+            let mut seen = FxHashSet::default();
+            for state in frontier {
+                let key = z_data.hash_of(&state);
+                if state.is_over() || positions.contains_key(&key) || !seen.insert(key) {
+                    continue;
+                }
+                let engine = &engines[usize::from(state.current_turn()) - 1];
+                let Some(result) = engine.search_utility_all(&state) else {
+                    continue;
+                };
+
+                let best = result
+                    .utilities
+                    .iter()
+                    .copied()
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let moves: Vec<BookMove> = result
+                    .found_moves
+                    .iter()
+                    .zip(&result.utilities)
+                    .filter(|(_, u)| best - **u <= margin)
+                    .map(|(m, u)| BookMove {
+                        selection: *m,
+                        weight: (1.0 + margin - (best - u))
+                            .round()
+                            .clamp(1.0, u16::MAX as f32) as u16,
+                        utility: *u,
+                    })
+                    .collect();
+                for m in &moves {
+                    next.push(state.make_move(m.selection).unwrap());
+                }
+                positions.insert(key, moves);
+            }
+            frontier = next;
+        }
+
+        Self { z_data, positions }
+    }
+
+    /// Returns the number of positions in the book.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns whether the book does not contain any positions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns the moves stored for the supplied state, if the state is in
+    /// the book.
+    pub fn moves(&self, state: &impl Mancala) -> Option<&[BookMove]> {
+        if !self.z_data.is_valid_for(state) {
+            return None;
+        }
+        self.positions
+            .get(&self.z_data.hash_of(state))
+            .map(|moves| moves.as_slice())
+    }
+
+    /// Randomly chooses one of the moves stored for the supplied state (based
+    /// on their weights), if the state is in the book.
+    ///
+    /// Moves which are not valid for the state (e.g., because another position
+    /// shares its Zobrist hash) are never chosen, so [`None`] is returned if
+    /// none of the stored moves are valid.
+    pub fn choose(&self, state: &impl Mancala) -> Option<BookMove> {
+        let moves: Vec<_> = self
+            .moves(state)?
+            .iter()
+            .filter(|m| state.is_valid_move(m.selection))
+            .collect();
+        moves
+            .choose_weighted(&mut rand::rng(), |m| m.weight)
+            .ok()
+            .map(|m| **m)
+    }
+
+    /// Saves the book to a file (see the [module documentation][self]).
+    ///
+    /// Fails without creating the file if a position has too many moves, or a
+    /// pit number is too large, to be stored in the file format.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        for moves in self.positions.values() {
+            if moves.len() > u8::MAX.into() {
+                return Err(PersistError::TooLarge {
+                    field: "number of moves",
+                    value: moves.len() as u64,
+                });
+            }
+            for m in moves {
+                match m.selection {
+                    Move::Pit(pit) if pit > u16::MAX.into() => {
+                        return Err(PersistError::TooLarge {
+                            field: "pit number",
+                            value: pit as u64,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut w = BufWriter::new(File::create(path)?);
+        ZobristHeader::of(&self.z_data).write(&mut w, MAGIC, VERSION)?;
+        w.write_all(&(self.positions.len() as u64).to_le_bytes())?;
+
+        // Positions are sorted, so that the same book always produces the same file.
+        let mut keys: Vec<_> = self.positions.keys().copied().collect();
+        keys.sort_unstable();
+        for key in keys {
+            let moves = &self.positions[&key];
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&[moves.len() as u8])?;
+            for m in moves {
+                let selection = match m.selection {
+                    Move::Swap => 0,
+                    Move::Pit(pit) => pit as u16,
+                };
+                w.write_all(&selection.to_le_bytes())?;
+                w.write_all(&m.weight.to_le_bytes())?;
+                w.write_all(&m.utility.to_le_bytes())?;
+            }
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Loads a book saved by [`save`][Self::save], for use with states like the
+    /// supplied state (i.e., with the same number of pits, total stones, and rule
+    /// set). The Zobrist data is regenerated from the seed stored in the file.
+    ///
+    /// Fails if the file was saved for states which are not like the supplied state.
+    pub fn load(path: impl AsRef<Path>, state: &impl Mancala) -> Result<Self, PersistError> {
+        let mut r = BufReader::new(File::open(path)?);
+        let header = ZobristHeader::read(&mut r, MAGIC, VERSION)?;
+        let z_data = ZobristData::for_state_like(state, header.seed());
+        ZobristHeader::of(&z_data).check(&header)?;
+
+        let len = read_u64(&mut r)?;
+        let mut positions = FxHashMap::default();
+        for _ in 0..len {
+            let key = read_u64(&mut r)?;
+            let mut count = [0; 1];
+            r.read_exact(&mut count)?;
+            let moves = (0..count[0])
+                .map(|_| {
+                    let mut bytes = [0; 4];
+                    r.read_exact(&mut bytes)?;
+                    let selection = match u16::from_le_bytes([bytes[0], bytes[1]]) {
+                        0 => Move::Swap,
+                        pit => Move::Pit(pit.into()),
+                    };
+                    Ok(BookMove {
+                        selection,
+                        weight: u16::from_le_bytes([bytes[2], bytes[3]]),
+                        utility: f32::from_bits(read_u32(&mut r)?),
+                    })
+                })
+                .collect::<Result<_, PersistError>>()?;
+            positions.insert(key, moves);
+        }
+        Ok(Self { z_data, positions })
+    }
+}
+
+impl Debug for OpeningBook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpeningBook")
+            .field("positions", &self.positions.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: MancalaZobrist> Minimax<T> {
+    /// Returns the opening book consulted before searching, if one is set.
+    #[inline]
+    pub fn opening_book(&self) -> Option<&OpeningBook> {
+        self.opening_book.as_deref()
+    }
+
+    /// Set the opening book consulted before searching.
+    ///
+    /// When the state passed to [`search_utility`][Self::search_utility] (or
+    /// [`search`][Self::search]) is in the book, a book move is chosen instead of
+    /// searching (unless none of its moves are valid for the state). [`search_utility_all`][Self::search_utility_all] never uses the book.
+    pub fn set_opening_book(&mut self, book: Option<Arc<OpeningBook>>) {
+        self.opening_book = book;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    fn book_move(pit: usize) -> BookMove {
+        BookMove {
+            selection: Move::Pit(pit),
+            weight: 1,
+            utility: 0.0,
+        }
+    }
+
+    #[test]
+    fn invalid_moves_are_not_chosen() {
+        let state = GameState::<6>::default().make_move_pit(3).unwrap();
+        let z_data = ZobristData::for_state_like(&state, 1);
+        let key = z_data.hash_of(&state);
+        let mut book = OpeningBook {
+            z_data,
+            positions: FxHashMap::default(),
+        };
+
+        // Pit 3 is empty, and there is no pit 7.
+        book.positions
+            .insert(key, vec![book_move(3), book_move(7), book_move(4)]);
+        for _ in 0..20 {
+            assert_eq!(book.choose(&state), Some(book_move(4)));
+        }
+
+        // Without any valid moves, the state is searched instead.
+        book.positions.insert(key, vec![book_move(3), book_move(7)]);
+        assert_eq!(book.choose(&state), None);
+        let mut minimax = MinimaxBuilder::default().max_depth(Some(2)).build();
+        minimax.set_opening_book(Some(Arc::new(book)));
+        let result = minimax.search_utility(&state).unwrap();
+        assert!(!result.from_book);
+        assert!(state.is_valid_move(result.found_move));
+    }
+
+    #[test]
+    fn save_rejects_unencodable_moves() {
+        let state = GameState::<6>::default();
+        let z_data = ZobristData::for_state_like(&state, 1);
+        let key = z_data.hash_of(&state);
+        let path = std::env::temp_dir().join(format!("mancalamax-book-{}.bin", std::process::id()));
+        let mut book = OpeningBook {
+            z_data,
+            positions: FxHashMap::default(),
+        };
+
+        book.positions.insert(key, vec![book_move(1); 256]);
+        assert!(matches!(
+            book.save(&path),
+            Err(PersistError::TooLarge { value: 256, .. })
+        ));
+        book.positions.insert(key, vec![book_move(70_000)]);
+        assert!(matches!(
+            book.save(&path),
+            Err(PersistError::TooLarge { value: 70_000, .. })
+        ));
+        assert!(!path.exists());
+
+        // The largest number of moves is saved and loaded intact.
+        book.positions.insert(key, vec![book_move(1); 255]);
+        book.save(&path).unwrap();
+        let loaded = OpeningBook::load(&path, &state).unwrap();
+        assert_eq!(loaded.moves(&state), Some(&[book_move(1); 255][..]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
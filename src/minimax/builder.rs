@@ -1,23 +1,33 @@
 //! Builder utilities for constructing [`Minimax`] instances.
 
 use super::Minimax;
-use super::{MancalaZobrist, MoveOrderFn, StateEvalFn};
-use crate::game::{Move, Player};
-use rustc_hash::FxHashMap;
+use super::endgame::EndgameDb;
+use super::stop::StopHandle;
+use super::t_table::TTable;
+use super::time::TimeManager;
+use super::{Evaluator, IterationCallback, IterationFn, MancalaZobrist, MoveOrderer};
+use crate::game::Player;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Helper for constructing [`Minimax`] instances based on certain specifications.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct MinimaxBuilder<T: MancalaZobrist> {
     optimize_for: Player,
     max_depth: Option<usize>,
     max_time: Option<Duration>,
     iterative_deepening: bool,
     use_t_table: bool,
-    move_orderer: MoveOrderFn<T>,
-    evaluator: StateEvalFn<T>,
-    heuristic: StateEvalFn<T>,
-    t_table_capacity: usize,
+    threads: usize,
+    move_orderer: Arc<dyn MoveOrderer<T>>,
+    evaluator: Arc<dyn Evaluator<T>>,
+    heuristic: Arc<dyn Evaluator<T>>,
+    on_iteration: Option<IterationCallback>,
+    t_table_megabytes: usize,
+    zobrist_seed: u64,
+    endgame_db: Option<Arc<EndgameDb>>,
+    stop_handle: Option<StopHandle>,
+    time_manager: TimeManager,
 }
 
 impl<T: MancalaZobrist> Default for MinimaxBuilder<T> {
@@ -27,40 +37,48 @@ impl<T: MancalaZobrist> Default for MinimaxBuilder<T> {
     /// - `max_time`: [`None`]
     /// - `iterative_deepening`: [`true`]
     /// - `use_t_table`: [`true`]
-    /// - `move_orderer`: A function that returns the valid moves in descending order by pit number.
+    /// - `threads`: `1`
+    /// - `move_orderer`: A function that returns the valid moves in descending order by pit number,
+    ///   followed by the swap move (if available).
     /// - `evaluator`: A function that returns the point differential between
     ///   the players (positive if the current player is winning).
     /// - `heuristic`: Same as evaluator.
-    /// - `t_table_capacity`: `0`
+    /// - `on_iteration`: [`None`]
+    /// - `t_table_megabytes`: `16`
+    /// - `zobrist_seed`: `0x49CB86856BB06133`
+    /// - `endgame_db`: [`None`]
+    /// - `stop_handle`: [`None`]
+    /// - `time_manager`: See [`TimeManager::default`].
     fn default() -> Self {
-        // Faster than sorting s.valid_moves() at each iteration.
+        // The swap move is listed first by s.valid_moves(), so reversing the
+        // moves puts the pits in descending order, followed by the swap move.
         let move_orderer = |s: &T| {
-            let mut moves = Vec::new();
-            if s.swap_allowed() {
-                moves.insert(0, Move::Swap);
-            }
-            for (i, pit) in s.board()[s.current_turn()].as_ref().iter().enumerate() {
-                if *pit > 0 {
-                    moves.insert(0, Move::Pit(i + 1));
-                }
-            }
+            let mut moves = s.valid_moves();
+            moves.reverse();
             moves
         };
         let evaluator = |s: &T, p: Player| match p {
             Player::One => (s.score(Player::One) as isize - s.score(Player::Two) as isize) as f32,
             Player::Two => (s.score(Player::Two) as isize - s.score(Player::One) as isize) as f32,
         };
-        let heuristic = evaluator;
+        let evaluator: Arc<dyn Evaluator<T>> = Arc::new(evaluator);
+        let heuristic = evaluator.clone();
         Self {
             optimize_for: Player::One,
             max_depth: Some(18),
             max_time: None,
             iterative_deepening: true,
             use_t_table: true,
-            move_orderer,
+            threads: 1,
+            move_orderer: Arc::new(move_orderer),
             evaluator,
             heuristic,
-            t_table_capacity: 0,
+            on_iteration: None,
+            t_table_megabytes: 16,
+            zobrist_seed: 0x49CB86856BB06133,
+            endgame_db: None,
+            stop_handle: None,
+            time_manager: TimeManager::default(),
         }
     }
 }
@@ -139,48 +157,116 @@ impl<T: MancalaZobrist> MinimaxBuilder<T> {
         self
     }
 
-    /// Set the move ordering function.
+    /// Set the number of threads used during search (at least one).
     ///
-    /// This function is used for each state checked by minimax, and
-    /// should be designed to supply moves in an optimal order (i.e., one
-    /// which avoids excessive future computation).
-    pub fn move_orderer(mut self, o: MoveOrderFn<T>) -> Self {
-        self.move_orderer = o;
+    /// When more than one thread is used, the helper threads search the same
+    /// state as the main thread, sharing the transposition table with it (i.e.,
+    /// "Lazy SMP"), which allows the main thread to reach greater depths in the
+    /// same time. Results may then vary between searches, since they depend on
+    /// the order in which threads fill the table. Helper threads are only used
+    /// if the transposition table is enabled.
+    ///
+    /// A single thread always produces the same results.
+    pub fn threads(mut self, n: usize) -> Self {
+        self.threads = n.max(1);
         self
     }
 
-    /// Set the evaluator function.
+    /// Set the move orderer (e.g., a [`MoveOrderFn`][super::MoveOrderFn], or any
+    /// other [`MoveOrderer`]).
     ///
-    /// This function is used to evaluate states only when it is a terminal state
+    /// This is used for each state checked by minimax, and should be
+    /// designed to supply moves in an optimal order (i.e., one which avoids
+    /// excessive future computation).
+    pub fn move_orderer(mut self, o: impl MoveOrderer<T> + 'static) -> Self {
+        self.move_orderer = Arc::new(o);
+        self
+    }
+
+    /// Set the evaluator (e.g., a [`StateEvalFn`][super::StateEvalFn], or any
+    /// other [`Evaluator`]).
+    ///
+    /// This is used to evaluate states only when it is a terminal state
     /// (i.e., when the game is over).
-    pub fn evaluator(mut self, e: StateEvalFn<T>) -> Self {
-        self.evaluator = e;
+    pub fn evaluator(mut self, e: impl Evaluator<T> + 'static) -> Self {
+        self.evaluator = Arc::new(e);
         self
     }
 
-    /// Set the heuristic function.
+    /// Set the heuristic (e.g., a [`StateEvalFn`][super::StateEvalFn], or any
+    /// other [`Evaluator`]).
     ///
-    /// This function is used to evaluate states only when the artificial limit
+    /// This is used to evaluate states only when the artificial limit
     /// (i.e., the time / depth limit) has been reached, and may be different
-    /// from the evaluator function.
-    pub fn heuristic(mut self, h: StateEvalFn<T>) -> Self {
-        self.heuristic = h;
+    /// from the evaluator.
+    pub fn heuristic(mut self, h: impl Evaluator<T> + 'static) -> Self {
+        self.heuristic = Arc::new(h);
+        self
+    }
+
+    /// Set the function called with the statistics for each search iteration
+    /// (or for the whole search, if iterative deepening is disabled). Closures
+    /// may capture state, such as a channel used to report progress.
+    ///
+    /// [`None`] means no function is called.
+    pub fn on_iteration(mut self, f: Option<IterationFn>) -> Self {
+        self.on_iteration = f.map(IterationCallback);
         self
     }
 
-    /// Set the initial transposition table capacity.
-    pub fn t_table_capacity(mut self, c: usize) -> Self {
-        self.t_table_capacity = c;
+    /// Set the amount of memory used by the transposition table, in megabytes.
+    ///
+    /// The table never grows beyond this size. Once it is full, entries for
+    /// shallower searches (and entries left over from earlier searches) are
+    /// replaced first. Each megabyte holds 65,536 entries.
+    pub fn t_table_megabytes(mut self, mb: usize) -> Self {
+        self.t_table_megabytes = mb;
+        self
+    }
+
+    /// Set the seed used to generate the Zobrist values which key the
+    /// transposition table.
+    ///
+    /// Transposition tables saved with one seed can only be loaded by instances
+    /// using the same seed (see [`Minimax::load_t_table`]).
+    pub fn zobrist_seed(mut self, seed: u64) -> Self {
+        self.zobrist_seed = seed;
+        self
+    }
+
+    /// Set the endgame database probed during search.
+    ///
+    /// [`None`] means no database is probed (see [`Minimax::set_endgame_db`]).
+    pub fn endgame_db(mut self, db: Option<Arc<EndgameDb>>) -> Self {
+        self.endgame_db = db;
+        self
+    }
+
+    /// Set the handle used to stop searches early (see [`Minimax::stop_handle`]).
+    ///
+    /// [`None`] means each instance built gets a new handle. Otherwise, every
+    /// instance built shares the supplied handle (as do builders created from
+    /// an instance), so a single handle can stop all of them.
+    pub fn stop_handle(mut self, handle: Option<StopHandle>) -> Self {
+        self.stop_handle = handle;
+        self
+    }
+
+    /// Set the policy used to budget the time for each move when playing under
+    /// a game clock (see [`Minimax::search_timed`]).
+    pub fn time_manager(mut self, m: TimeManager) -> Self {
+        self.time_manager = m;
         self
     }
 
     /// Construct a [`Minimax`] instance based on the set configuration.
     pub fn build(&self) -> Minimax<T> {
-        // Initially size the transposition table.
-        let mut t_table = FxHashMap::default();
-        if self.use_t_table {
-            t_table.reserve(self.t_table_capacity);
-        }
+        // Only allocate a minimal transposition table if it won't be used.
+        let megabytes = if self.use_t_table {
+            self.t_table_megabytes
+        } else {
+            0
+        };
 
         Minimax {
             optimize_for: self.optimize_for,
@@ -188,12 +274,23 @@ impl<T: MancalaZobrist> MinimaxBuilder<T> {
             max_time: self.max_time,
             iterative_deepening: self.iterative_deepening,
             use_t_table: self.use_t_table,
-            move_orderer: self.move_orderer,
-            evaluator: self.evaluator,
-            heuristic: self.heuristic,
+            threads: self.threads,
+            move_orderer: self.move_orderer.clone(),
+            evaluator: self.evaluator.clone(),
+            heuristic: self.heuristic.clone(),
+            on_iteration: self.on_iteration.clone(),
+            zobrist_seed: self.zobrist_seed,
+            opening_book: None,
+            endgame_db: self.endgame_db.clone(),
             start_time: None.into(),
-            t_table: t_table.into(),
+            stop: None,
+            stop_handle: self.stop_handle.clone().unwrap_or_default(),
+            time_manager: self.time_manager,
+            time_budget: None.into(),
+            t_table: TTable::new(megabytes),
             z_data: Default::default(),
+            pv_table: Default::default(),
+            stats: Default::default(),
         }
     }
 }
@@ -206,9 +303,15 @@ fn from_common<T: MancalaZobrist>(value: &Minimax<T>) -> MinimaxBuilder<T> {
         max_time: value.max_time,
         iterative_deepening: value.iterative_deepening,
         use_t_table: value.use_t_table,
-        move_orderer: value.move_orderer,
-        evaluator: value.evaluator,
-        heuristic: value.heuristic,
-        t_table_capacity: value.t_table.borrow().capacity(),
+        threads: value.threads,
+        move_orderer: value.move_orderer.clone(),
+        evaluator: value.evaluator.clone(),
+        heuristic: value.heuristic.clone(),
+        on_iteration: value.on_iteration.clone(),
+        t_table_megabytes: value.t_table.megabytes(),
+        zobrist_seed: value.zobrist_seed,
+        endgame_db: value.endgame_db.clone(),
+        stop_handle: Some(value.stop_handle.clone()),
+        time_manager: value.time_manager,
     }
 }
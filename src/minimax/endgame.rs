@@ -0,0 +1,401 @@
+//! Endgame databases, which store the exact outcome of every Kalah position with
+//! only a few stones left in the pits, computed by retrograde analysis.
+//!
+//! Under the [`Kalah`] rules, the outcome of the rest of the game only depends
+//! on the stones left in the pits and the player to move, since stones in the
+//! stores never leave them. Databases therefore store, for every arrangement of
+//! at most `max_stones` stones in the pits, the final score differential the
+//! player to move can force from that point onward (i.e., ignoring the stores).
+//!
+//! Databases are stored in a versioned binary file (all values little-endian):
+//!
+//! | Field      | Size     | Description                                        |
+//! |------------|----------|----------------------------------------------------|
+//! | Magic      | 4 bytes  | `MMEG`                                             |
+//! | Version    | 4 bytes  | The file format version (currently `1`)            |
+//! | Rules      | 8 bytes  | A hash of the rule set                             |
+//! | Pits       | 8 bytes  | The number of pits per player                      |
+//! | Max stones | 8 bytes  | The largest number of stones in the pits           |
+//! | Values     | 1 byte   | The value of each position, in index order (repeated) |
+//!
+//! Positions are indexed by the number of stones in each pit (starting with the
+//! pits of the player to move), in lexicographic order.
+
+use super::persist::{PersistError, read_preamble, read_u64, write_preamble};
+use super::zobrist::rules_key;
+use super::{MancalaZobrist, Minimax};
+use crate::game::{Kalah, Mancala, Player};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The bytes identifying an endgame database file.
+const MAGIC: [u8; 4] = *b"MMEG";
+
+/// The current version of the endgame database file format.
+const VERSION: u32 = 1;
+
+/// The exact values of every Kalah position with at most a certain number of
+/// stones left in the pits, for a certain number of pits and rule options.
+#[derive(Clone)]
+pub struct EndgameDb {
+    pits: usize,
+    max_stones: usize,
+    rules_key: u64,
+    indexer: Indexer,
+    values: Vec<i8>,
+}
+
+impl EndgameDb {
+    /// Generates a database for every position with at most `max_stones` stones
+    /// in the pits, with the same number of pits and rules as the supplied state.
+    ///
+    /// Positions are solved by retrograde analysis: a move either removes stones
+    /// from the pits (by sowing into a store, or capturing), or moves stones
+    /// closer to the mover's store, so positions are solved in order of the
+    /// number of stones in the pits, then the total distance of those stones
+    /// from their owners' stores. Every position a move leads to has then already
+    /// been solved.
+    ///
+    /// The number of positions grows quickly: `C(max_stones + 2 * pits, 2 * pits)`
+    /// (e.g., about 2.7 million for 6 pits and 12 stones).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_stones` is greater than `127`, since values are stored
+    /// in a single byte.
+    pub fn generate<T: Mancala<Rules = Kalah>>(template: &T, max_stones: usize) -> Self {
+        assert!(
+            max_stones <= i8::MAX as usize,
+            "endgame databases support at most {} stones",
+            i8::MAX
+        );
+        let pits = template.pits();
+        let indexer = Indexer::new(2 * pits, max_stones);
+        let len = indexer.len();
+
+        // Order positions by the number of stones in the pits, then by their
+        // total distance from the stores.
+        let max_distance = max_stones * pits;
+        let bucket = |position: &[usize]| {
+            let stones: usize = position.iter().sum();
+            let distance: usize = position
+                .iter()
+                .enumerate()
+                .map(|(i, s)| s * (pits - i % pits))
+                .sum();
+            stones * (max_distance + 1) + distance
+        };
+        let mut buckets = vec![Vec::new(); (max_stones + 1) * (max_distance + 1)];
+        let mut position = vec![0; 2 * pits];
+        for index in 0..len {
+            indexer.unrank(index, &mut position);
+            buckets[bucket(&position)].push(index);
+        }
+
+        // Player 1 is always the player to move, with their pits first.
+        let mut base = template.clone();
+        *base.stores_mut() = [0, 0];
+        *base.current_turn_mut() = Player::One;
+        base.set_p2_moved(true);
+
+        let mut values = vec![0; len];
+        for index in buckets.into_iter().flatten() {
+            indexer.unrank(index, &mut position);
+            let mut state = base.clone();
+            state.board_mut()[Player::One]
+                .as_mut()
+                .copy_from_slice(&position[..pits]);
+            state.board_mut()[Player::Two]
+                .as_mut()
+                .copy_from_slice(&position[pits..]);
+            // Positions where either side is empty are never reached, since the
+            // remaining stones are captured immediately.
+            if position[..pits].iter().all(|s| *s == 0) || position[pits..].iter().all(|s| *s == 0)
+            {
+                continue;
+            }
+
+            let mut best = i32::MIN;
+            for m in state.valid_moves() {
+                let next = state.make_move(m).unwrap();
+                let gained = next.stores()[Player::One] as i32 - next.stores()[Player::Two] as i32;
+                let value = if next.is_over() {
+                    gained
+                } else {
+                    let mover = next.current_turn();
+                    let rows = [&next.board()[mover], &next.board()[mover.other()]];
+                    let successor = indexer.rank(rows.iter().flat_map(|r| r.as_ref()));
+                    let rest = i32::from(values[successor]);
+                    if mover == Player::One {
+                        gained + rest
+                    } else {
+                        gained - rest
+                    }
+                };
+                best = best.max(value);
+            }
+            values[index] = best as i8;
+        }
+
+        Self {
+            pits,
+            max_stones,
+            rules_key: rules_key(template),
+            indexer,
+            values,
+        }
+    }
+
+    /// Returns the number of pits per player in every position.
+    #[inline]
+    pub fn pits(&self) -> usize {
+        self.pits
+    }
+
+    /// Returns the largest number of stones left in the pits of any position.
+    #[inline]
+    pub fn max_stones(&self) -> usize {
+        self.max_stones
+    }
+
+    /// Returns the number of positions in the database.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether the database does not contain any positions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the final score differential (i.e., the player to move's score
+    /// minus their opponent's score) that the player to move can force from the
+    /// supplied state, assuming both players play perfectly.
+    ///
+    /// Returns [`None`] if the state is not in the database (i.e., it has too
+    /// many stones left in the pits, a different number of pits, or different
+    /// rules), if either side has no stones left (i.e., the game is over), or if
+    /// the swap move is still available.
+    pub fn probe(&self, state: &impl Mancala) -> Option<i32> {
+        if state.pits() != self.pits || state.swap_allowed() {
+            return None;
+        }
+        let mover = state.current_turn();
+        let rows = [&state.board()[mover], &state.board()[mover.other()]];
+        if rows.iter().any(|r| r.as_ref().iter().all(|s| *s == 0)) {
+            return None;
+        }
+        let stones: usize = rows.iter().flat_map(|r| r.as_ref()).sum();
+        if stones > self.max_stones || rules_key(state) != self.rules_key {
+            return None;
+        }
+
+        let index = self.indexer.rank(rows.iter().flat_map(|r| r.as_ref()));
+        let stores = state.stores()[mover] as i32 - state.stores()[mover.other()] as i32;
+        Some(stores + i32::from(self.values[index]))
+    }
+
+    /// Saves the database to a file (see the [module documentation][self]).
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        let mut w = BufWriter::new(File::create(path)?);
+        write_preamble(&mut w, MAGIC, VERSION)?;
+        for value in [self.rules_key, self.pits as u64, self.max_stones as u64] {
+            w.write_all(&value.to_le_bytes())?;
+        }
+        let bytes: Vec<u8> = self.values.iter().map(|v| *v as u8).collect();
+        w.write_all(&bytes)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Loads a database saved by [`save`][Self::save].
+    ///
+    /// The rules are checked when the database is probed, so a database can be
+    /// loaded without a state to compare against.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistError> {
+        let mut r = BufReader::new(File::open(path)?);
+        read_preamble(&mut r, MAGIC, VERSION)?;
+        let rules_key = read_u64(&mut r)?;
+        let pits = read_u64(&mut r)? as usize;
+        let max_stones = read_u64(&mut r)? as usize;
+        if max_stones > i8::MAX as usize {
+            return Err(PersistError::Mismatch {
+                parameter: "max stones",
+                file: max_stones as u64,
+                current: i8::MAX as u64,
+            });
+        }
+
+        let indexer = Indexer::new(2 * pits, max_stones);
+        let mut bytes = vec![0; indexer.len()];
+        r.read_exact(&mut bytes)?;
+        Ok(Self {
+            pits,
+            max_stones,
+            rules_key,
+            indexer,
+            values: bytes.into_iter().map(|b| b as i8).collect(),
+        })
+    }
+}
+
+impl Debug for EndgameDb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EndgameDb")
+            .field("pits", &self.pits)
+            .field("max_stones", &self.max_stones)
+            .field("positions", &self.values.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Maps each arrangement of at most `max_stones` stones in a fixed number of
+/// locations to its index in lexicographic order (and back).
+#[derive(Debug, Clone)]
+struct Indexer {
+    locations: usize,
+    max_stones: usize,
+    /// `counts[n][s]` is the number of arrangements of at most `s` stones in
+    /// `n` locations (i.e., `C(s + n, n)`).
+    counts: Vec<Vec<usize>>,
+}
+
+impl Indexer {
+    fn new(locations: usize, max_stones: usize) -> Self {
+        let mut counts = vec![vec![1; max_stones + 1]; locations + 2];
+        for n in 1..locations + 2 {
+            for s in 1..=max_stones {
+                counts[n][s] = counts[n - 1][s] + counts[n][s - 1];
+            }
+        }
+        Self {
+            locations,
+            max_stones,
+            counts,
+        }
+    }
+
+    /// Returns the number of arrangements.
+    fn len(&self) -> usize {
+        self.counts[self.locations][self.max_stones]
+    }
+
+    /// Returns the number of arrangements whose next location holds fewer than
+    /// `stones` stones, when `remaining` stones are left for the next location
+    /// and the `after` locations following it.
+    #[inline]
+    fn skipped(&self, after: usize, remaining: usize, stones: usize) -> usize {
+        self.counts[after + 1][remaining] - self.counts[after + 1][remaining - stones]
+    }
+
+    /// Returns the index of an arrangement, which must hold at most `max_stones`
+    /// stones in total.
+    #[inline]
+    fn rank<'a>(&self, arrangement: impl IntoIterator<Item = &'a usize>) -> usize {
+        let mut index = 0;
+        let mut remaining = self.max_stones;
+        for (i, stones) in arrangement.into_iter().enumerate() {
+            index += self.skipped(self.locations - i - 1, remaining, *stones);
+            remaining -= stones;
+        }
+        index
+    }
+
+    /// Writes the arrangement with the supplied index into `arrangement`.
+    fn unrank(&self, mut index: usize, arrangement: &mut [usize]) {
+        let mut remaining = self.max_stones;
+        for (i, stones) in arrangement.iter_mut().enumerate() {
+            let after = self.locations - i - 1;
+            *stones = 0;
+            while *stones < remaining && self.skipped(after, remaining, *stones + 1) <= index {
+                *stones += 1;
+            }
+            index -= self.skipped(after, remaining, *stones);
+            remaining -= *stones;
+        }
+    }
+}
+
+impl<T: MancalaZobrist> Minimax<T> {
+    /// Returns the endgame database probed during search, if one is set.
+    #[inline]
+    pub fn endgame_db(&self) -> Option<&EndgameDb> {
+        self.endgame_db.as_deref()
+    }
+
+    /// Set the endgame database probed during search.
+    ///
+    /// Every state below the root which is in the database is scored using its
+    /// exact value (and treated as fully searched), instead of being searched.
+    /// Since database values are score differentials, the database should only
+    /// be used with evaluators which return the point differential (such as the
+    /// default evaluator).
+    pub fn set_endgame_db(&mut self, db: Option<Arc<EndgameDb>>) {
+        self.endgame_db = db;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::game::rules::{KalahOptions, Sweep};
+    use crate::minimax::Solver;
+
+    /// Checks every state probed during random games against the solver.
+    fn assert_matches_solver(start: GameState<3>, db: &EndgameDb) {
+        let mut solver = Solver::new(&start);
+        let mut probed = 0;
+        for _ in 0..100 {
+            let mut state = start;
+            while !state.is_over() {
+                if let Some(value) = db.probe(&state) {
+                    assert_eq!(value, solver.solve(&state).unwrap().value, "{state:?}");
+                    probed += 1;
+                }
+                state = state.make_move_rand().unwrap().0;
+            }
+        }
+        assert!(probed > 0);
+    }
+
+    #[test]
+    fn probe_matches_solver() {
+        let start = GameState::<3>::try_new(2, 0, 0, Player::One, 1, false).unwrap();
+        let db = EndgameDb::generate(&start, 8);
+        assert_eq!(db.len(), Indexer::new(6, 8).len());
+        assert_matches_solver(start, &db);
+
+        // States with too many stones in the pits, or with the swap move
+        // available, are not in the database.
+        assert_eq!(db.probe(&start), None);
+        let swap = GameState::<3>::try_new(1, 0, 0, Player::Two, 2, false).unwrap();
+        assert!(swap.swap_allowed());
+        assert_eq!(db.probe(&swap), None);
+
+        let emptied = start.with_rules(Kalah::new(KalahOptions {
+            empty_capture: false,
+            sweep: Sweep::Emptied,
+            pie_rule: false,
+        }));
+        assert_eq!(db.probe(&emptied.make_move_pit(1).unwrap()), None);
+        let emptied_db = EndgameDb::generate(&emptied, 12);
+        assert_matches_solver(emptied, &emptied_db);
+    }
+
+    #[test]
+    fn indexer_round_trip() {
+        let indexer = Indexer::new(4, 5);
+        let mut arrangement = [0; 4];
+        for index in 0..indexer.len() {
+            indexer.unrank(index, &mut arrangement);
+            assert!(arrangement.iter().sum::<usize>() <= 5);
+            assert_eq!(indexer.rank(&arrangement), index);
+        }
+    }
+}
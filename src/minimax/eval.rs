@@ -0,0 +1,85 @@
+//! Traits for the evaluation and move ordering used by [`Minimax`][super::Minimax].
+//!
+//! Both traits are implemented for any matching function or closure (including
+//! [`StateEvalFn`] and [`MoveOrderFn`] pointers), so they can also be used by
+//! evaluators which hold their own data, such as weights, lookup tables, or a
+//! neural network. Implementations are shared between search threads, so any
+//! mutable state (e.g., counters) must use interior mutability that is safe to
+//! share (e.g., atomics or a mutex).
+//!
+//! [`StateEvalFn`]: super::StateEvalFn
+//! [`MoveOrderFn`]: super::MoveOrderFn
+
+use crate::game::{Move, Player};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Evaluates a reference to a type (usually some kind of Mancala game state)
+/// for a player, producing a [`f32`] value indicating some level of utility.
+/// Positive values indicate higher utility.
+pub trait Evaluator<T>: Send + Sync {
+    /// Evaluates the state from the perspective of the supplied player.
+    fn evaluate(&self, state: &T, player: Player) -> f32;
+}
+
+/// Orders the valid moves for a reference to a type (usually some kind of
+/// Mancala game state). Every move returned should be valid for the state.
+pub trait MoveOrderer<T>: Send + Sync {
+    /// Returns the valid moves for the state, in the order they should be
+    /// searched.
+    fn order_moves(&self, state: &T) -> Vec<Move>;
+}
+
+impl<T, F: Fn(&T, Player) -> f32 + Send + Sync> Evaluator<T> for F {
+    #[inline]
+    fn evaluate(&self, state: &T, player: Player) -> f32 {
+        self(state, player)
+    }
+}
+
+impl<T, F: Fn(&T) -> Vec<Move> + Send + Sync> MoveOrderer<T> for F {
+    #[inline]
+    fn order_moves(&self, state: &T) -> Vec<Move> {
+        self(state)
+    }
+}
+
+impl<T> Evaluator<T> for Box<dyn Evaluator<T>> {
+    #[inline]
+    fn evaluate(&self, state: &T, player: Player) -> f32 {
+        (**self).evaluate(state, player)
+    }
+}
+
+impl<T> Evaluator<T> for Arc<dyn Evaluator<T>> {
+    #[inline]
+    fn evaluate(&self, state: &T, player: Player) -> f32 {
+        (**self).evaluate(state, player)
+    }
+}
+
+impl<T> MoveOrderer<T> for Box<dyn MoveOrderer<T>> {
+    #[inline]
+    fn order_moves(&self, state: &T) -> Vec<Move> {
+        (**self).order_moves(state)
+    }
+}
+
+impl<T> MoveOrderer<T> for Arc<dyn MoveOrderer<T>> {
+    #[inline]
+    fn order_moves(&self, state: &T) -> Vec<Move> {
+        (**self).order_moves(state)
+    }
+}
+
+impl<T> Debug for dyn Evaluator<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("Evaluator")
+    }
+}
+
+impl<T> Debug for dyn MoveOrderer<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("MoveOrderer")
+    }
+}
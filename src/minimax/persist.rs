@@ -0,0 +1,362 @@
+//! Saving and loading the data built up by searches (i.e., transposition
+//! tables, [opening books][super::book], and [endgame databases][super::endgame]),
+//! so that it can be reused across runs.
+//!
+//! Transposition tables are stored in a versioned binary file (all values little-endian):
+//!
+//! | Field          | Size     | Description                                         |
+//! |----------------|----------|-----------------------------------------------------|
+//! | Magic          | 4 bytes  | `MMTT`                                              |
+//! | Version        | 4 bytes  | The file format version (currently `1`)             |
+//! | Seed           | 8 bytes  | The seed used to generate the Zobrist data          |
+//! | Rules          | 8 bytes  | A hash of the rule set                              |
+//! | Pits           | 8 bytes  | The number of pits per player                       |
+//! | Stones         | 8 bytes  | The total number of stones                          |
+//! | Fingerprint    | 8 bytes  | A value combining every Zobrist value               |
+//! | Optimize for   | 1 byte   | The player utilities are relative to (`1` or `2`)   |
+//! | Entries        | 8 bytes  | The number of entries which follow                  |
+//! | Entry          | 16 bytes | The key, followed by the packed entry (repeated)    |
+//!
+//! Entries only make sense for the Zobrist data and player they were searched
+//! with, so files are rejected unless every parameter matches. The evaluator
+//! and heuristic functions cannot be checked, so tables should only be loaded
+//! by instances configured with the same functions as the one that saved them.
+
+use super::t_table::TTEntry;
+use super::{MancalaZobrist, Minimax, ZobristData};
+use crate::game::Player;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The bytes identifying a transposition table file.
+const MAGIC: [u8; 4] = *b"MMTT";
+
+/// The current version of the transposition table file format.
+const VERSION: u32 = 1;
+
+/// Describes why a transposition table, opening book, or endgame database
+/// could not be saved or loaded.
+#[derive(Debug)]
+pub enum PersistError {
+    /// The file could not be read or written.
+    Io(io::Error),
+    /// The file does not start with the expected magic bytes.
+    InvalidMagic([u8; 4]),
+    /// The file was written using an unsupported version of the format.
+    UnsupportedVersion(u32),
+    /// A parameter stored in the file does not match the current one.
+    Mismatch {
+        parameter: &'static str,
+        file: u64,
+        current: u64,
+    },
+    /// The player stored in the file was not `1` or `2`.
+    InvalidPlayer(u8),
+    /// An entry in the file could not be unpacked.
+    InvalidEntry { index: u64 },
+    /// A value is too large to be stored in its field of the file.
+    TooLarge { field: &'static str, value: u64 },
+    /// The table has not been used for any state yet, so there is no
+    /// Zobrist data to save alongside it.
+    Uninitialized,
+}
+
+impl Display for PersistError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PersistError::Io(e) => write!(f, "i/o error: {}", e),
+            PersistError::InvalidMagic(magic) => {
+                write!(f, "unrecognized file type (found magic {:?})", magic)
+            }
+            PersistError::UnsupportedVersion(v) => {
+                write!(f, "unsupported file version {}", v)
+            }
+            PersistError::Mismatch {
+                parameter,
+                file,
+                current,
+            } => write!(
+                f,
+                "{} does not match (file has {}, expected {})",
+                parameter, file, current
+            ),
+            PersistError::InvalidPlayer(p) => write!(f, "invalid player {}", p),
+            PersistError::InvalidEntry { index } => write!(f, "entry {} is invalid", index),
+            PersistError::TooLarge { field, value } => {
+                write!(f, "{} {} is too large to be stored", field, value)
+            }
+            PersistError::Uninitialized => {
+                write!(f, "the transposition table has not been used yet")
+            }
+        }
+    }
+}
+
+impl Error for PersistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PersistError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(value: io::Error) -> Self {
+        PersistError::Io(value)
+    }
+}
+
+/// The parameters of the Zobrist data used to key the entries of a file,
+/// stored (after the magic bytes and version) at the start of each file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ZobristHeader {
+    seed: u64,
+    rules: u64,
+    pits: u64,
+    stones: u64,
+    fingerprint: u64,
+}
+
+impl ZobristHeader {
+    /// Returns the header describing the supplied Zobrist data.
+    pub(super) fn of(data: &ZobristData) -> Self {
+        Self {
+            seed: data.seed(),
+            rules: data.rules_key(),
+            pits: data.num_pits() as u64,
+            stones: data.total_stones() as u64,
+            fingerprint: data.fingerprint(),
+        }
+    }
+
+    /// Returns the seed used to generate the Zobrist data.
+    #[inline]
+    pub(super) fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Writes the magic bytes and version of a file, followed by the header.
+    pub(super) fn write(&self, w: &mut impl Write, magic: [u8; 4], version: u32) -> io::Result<()> {
+        write_preamble(w, magic, version)?;
+        for value in [
+            self.seed,
+            self.rules,
+            self.pits,
+            self.stones,
+            self.fingerprint,
+        ] {
+            w.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a header written by [`write`][Self::write], after checking the
+    /// magic bytes and version of the file.
+    pub(super) fn read(
+        r: &mut impl Read,
+        magic: [u8; 4],
+        version: u32,
+    ) -> Result<Self, PersistError> {
+        read_preamble(r, magic, version)?;
+        Ok(Self {
+            seed: read_u64(r)?,
+            rules: read_u64(r)?,
+            pits: read_u64(r)?,
+            stones: read_u64(r)?,
+            fingerprint: read_u64(r)?,
+        })
+    }
+
+    /// Returns an error describing the first parameter which differs between the
+    /// supplied header (read from a file) and the current one.
+    pub(super) fn check(&self, file: &ZobristHeader) -> Result<(), PersistError> {
+        let parameters = [
+            ("number of pits", file.pits, self.pits),
+            ("total stones", file.stones, self.stones),
+            ("Zobrist seed", file.seed, self.seed),
+            ("rule set", file.rules, self.rules),
+            ("Zobrist fingerprint", file.fingerprint, self.fingerprint),
+        ];
+        match parameters
+            .into_iter()
+            .find(|(_, file, current)| file != current)
+        {
+            Some((parameter, file, current)) => Err(PersistError::Mismatch {
+                parameter,
+                file,
+                current,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: MancalaZobrist> Minimax<T> {
+    /// Saves the transposition table to a file, alongside the parameters of the
+    /// Zobrist data used to key it (see the [module documentation][self]).
+    ///
+    /// Returns [`PersistError::Uninitialized`] if no search has been run yet.
+    pub fn save_t_table(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        if self.z_data.borrow().num_pits() == 0 {
+            return Err(PersistError::Uninitialized);
+        }
+
+        let entries = self.t_table.entries();
+        let mut w = BufWriter::new(File::create(path)?);
+        ZobristHeader::of(&self.z_data.borrow()).write(&mut w, MAGIC, VERSION)?;
+        w.write_all(&[usize::from(self.optimize_for) as u8])?;
+        w.write_all(&(entries.len() as u64).to_le_bytes())?;
+        for (key, entry) in entries {
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&entry.to_bits().to_le_bytes())?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Replaces the contents of the transposition table with the entries saved
+    /// in a file by [`save_t_table`][Self::save_t_table], for searching states
+    /// like the supplied state.
+    ///
+    /// Fails without modifying the table if the file was saved for states with
+    /// a different number of pits, total stones, or rule set, using a different
+    /// Zobrist seed, or while optimizing for a different player.
+    pub fn load_t_table(&self, path: impl AsRef<Path>, state: &T) -> Result<(), PersistError> {
+        let entries = self.read_t_table(path.as_ref(), state)?;
+        self.t_table.clear();
+        for (key, entry) in entries {
+            self.t_table.store(key, entry);
+        }
+        Ok(())
+    }
+
+    /// Adds the entries saved in a file by [`save_t_table`][Self::save_t_table]
+    /// to the transposition table, for searching states like the supplied state.
+    /// Entries for states already in the table are only replaced if they were
+    /// searched less deeply.
+    ///
+    /// Merging the files saved by several runs (then saving the result) combines
+    /// their knowledge into a single table. Files are checked in the same way as
+    /// [`load_t_table`][Self::load_t_table].
+    pub fn merge_t_table(&self, path: impl AsRef<Path>, state: &T) -> Result<(), PersistError> {
+        for (key, entry) in self.read_t_table(path.as_ref(), state)? {
+            self.t_table.store(key, entry);
+        }
+        Ok(())
+    }
+
+    /// Reads every entry from a file, after checking that its parameters match
+    /// the Zobrist data for the supplied state. The current Zobrist data is only
+    /// prepared for the state once the whole file has been read successfully.
+    fn read_t_table(&self, path: &Path, state: &T) -> Result<Vec<(u64, TTEntry)>, PersistError> {
+        let mut r = BufReader::new(File::open(path)?);
+        let header = ZobristHeader::read(&mut r, MAGIC, VERSION)?;
+        let mut player = [0; 1];
+        r.read_exact(&mut player)?;
+        let optimize_for = match player[0] {
+            1 => Player::One,
+            2 => Player::Two,
+            p => return Err(PersistError::InvalidPlayer(p)),
+        };
+
+        // The header is checked against new Zobrist data, since preparing the
+        // current data for a different state would clear the table.
+        let z_data = ZobristData::for_state_like(state, self.zobrist_seed);
+        ZobristHeader::of(&z_data).check(&header)?;
+        if optimize_for != self.optimize_for {
+            return Err(PersistError::Mismatch {
+                parameter: "player to optimize for",
+                file: usize::from(optimize_for) as u64,
+                current: usize::from(self.optimize_for) as u64,
+            });
+        }
+
+        let len = read_u64(&mut r)?;
+        let mut entries = Vec::new();
+        for index in 0..len {
+            let key = read_u64(&mut r)?;
+            let entry = TTEntry::from_bits(read_u64(&mut r)?)
+                .ok_or(PersistError::InvalidEntry { index })?;
+            entries.push((key, entry));
+        }
+        self.prepare_z_data(state);
+        Ok(entries)
+    }
+}
+
+/// Writes the magic bytes and version which start every file.
+pub(super) fn write_preamble(w: &mut impl Write, magic: [u8; 4], version: u32) -> io::Result<()> {
+    w.write_all(&magic)?;
+    w.write_all(&version.to_le_bytes())
+}
+
+/// Reads the magic bytes and version which start every file, returning an
+/// error unless both match the supplied values.
+pub(super) fn read_preamble(
+    r: &mut impl Read,
+    magic: [u8; 4],
+    version: u32,
+) -> Result<(), PersistError> {
+    let mut found = [0; 4];
+    r.read_exact(&mut found)?;
+    if found != magic {
+        return Err(PersistError::InvalidMagic(found));
+    }
+    match read_u32(r)? {
+        v if v == version => Ok(()),
+        v => Err(PersistError::UnsupportedVersion(v)),
+    }
+}
+
+pub(super) fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub(super) fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::minimax::MinimaxBuilder;
+
+    #[test]
+    fn mismatched_file_keeps_table() {
+        let path = std::env::temp_dir().join(format!("mancalamax-tt-{}.bin", std::process::id()));
+        let builder = MinimaxBuilder::default().max_depth(Some(4));
+
+        let three = GameState::<6>::try_new(3, 0, 0, Player::One, 1, false).unwrap();
+        let saved = builder.build();
+        saved.search_utility(&three).unwrap();
+        saved.save_t_table(&path).unwrap();
+
+        let state = GameState::<6>::default();
+        let minimax = builder.build();
+        minimax.search_utility(&state).unwrap();
+        let before = minimax.t_table.entries().len();
+        let five = GameState::<6>::try_new(5, 0, 0, Player::One, 1, false).unwrap();
+        assert!(matches!(
+            minimax.merge_t_table(&path, &five),
+            Err(PersistError::Mismatch { .. })
+        ));
+        assert_eq!(minimax.t_table.entries().len(), before);
+
+        // Merging the file for a matching state switches the Zobrist data.
+        minimax.merge_t_table(&path, &three).unwrap();
+        assert_eq!(
+            minimax.t_table.entries().len(),
+            saved.t_table.entries().len()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
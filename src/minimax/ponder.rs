@@ -0,0 +1,313 @@
+//! Pondering: searching on the opponent's time.
+//!
+//! After the engine moves, [`Minimax::ponder`] continues searching in a
+//! background thread while the opponent thinks, filling the shared
+//! transposition table. Once the opponent's reply is known, the next search
+//! is run using [`Minimax::search_after_ponder`], which either continues the
+//! background search (if it was searching the state which was reached, i.e.,
+//! a "ponderhit") or discards it and searches normally, using the warmed table.
+
+use super::algorithm::SearchResult;
+use super::time::TimeBudget;
+use super::{MancalaZobrist, Minimax};
+use crate::game::Move;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Determines what is searched while pondering.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum PonderMode {
+    /// Predict the opponent's reply (using the transposition table), and
+    /// search the state it leads to as if it had already been played. If the
+    /// prediction is correct, the search continues once the reply is made.
+    #[default]
+    Predicted,
+    /// Search the state before the opponent's reply, covering every reply. The
+    /// search is always discarded once the reply is made, but the
+    /// transposition table remains warm.
+    AllReplies,
+}
+
+/// A search running in a background thread on the opponent's time.
+///
+/// Created using [`Minimax::ponder`], and consumed by either
+/// [`Minimax::search_after_ponder`] or [`stop`][Self::stop]. Dropping a
+/// [`Ponder`] stops the background search, and waits for its thread to exit.
+///
+/// The background search shares the [`stop_handle`][Minimax::stop_handle] of
+/// the instance which started it, so stopping the handle also stops pondering
+/// (as well as any search continuing it after a ponderhit).
+#[derive(Debug)]
+pub struct Ponder<T> {
+    mode: PonderMode,
+    predicted_moves: Vec<Move>,
+    target: Option<(T, u64)>,
+    started: Instant,
+    stop: Arc<AtomicBool>,
+    result: Receiver<Option<SearchResult>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<T> Ponder<T> {
+    /// Returns what is being searched.
+    ///
+    /// If [`PonderMode::Predicted`] was requested, but no reply could be
+    /// predicted, [`PonderMode::AllReplies`] is used instead.
+    #[inline]
+    pub fn mode(&self) -> PonderMode {
+        self.mode
+    }
+
+    /// Returns the predicted moves of the opponent (more than one if the
+    /// opponent is predicted to take extra turns). Empty unless the mode is
+    /// [`PonderMode::Predicted`].
+    #[inline]
+    pub fn predicted_moves(&self) -> &[Move] {
+        &self.predicted_moves
+    }
+
+    /// Returns the state being searched if the mode is [`PonderMode::Predicted`]
+    /// (i.e., the state after the predicted moves).
+    #[inline]
+    pub fn predicted_state(&self) -> Option<&T> {
+        self.target.as_ref().map(|(s, _)| s)
+    }
+
+    /// Returns the time at which pondering started.
+    #[inline]
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    /// Returns whether the background search has finished (e.g., because the
+    /// state was fully searched, or the depth limit was reached).
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(|h| h.is_finished())
+    }
+
+    /// Stops the background search and waits for its thread to exit,
+    /// discarding any result.
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl<T> Drop for Ponder<T> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<T: MancalaZobrist + 'static> Minimax<T> {
+    /// Starts pondering in a background thread, from the supplied state (i.e.,
+    /// the state after the engine's move, with the opponent to move).
+    ///
+    /// The background search shares the transposition table and the
+    /// [`stop_handle`][Self::stop_handle], and uses the same configuration as
+    /// this instance, except that it has no time limit (it runs until it
+    /// completes, or is stopped). Any earlier stop request is cleared first.
+    ///
+    /// Returns [`None`] if the game is over, or if it is the engine's turn
+    /// (i.e., there is nothing to ponder).
+    pub fn ponder(&self, state: &T, mode: PonderMode) -> Option<Ponder<T>> {
+        if state.is_over() || state.current_turn() == self.optimize_for {
+            return None;
+        }
+
+        self.prepare_z_data(state);
+        let mut root = state.clone();
+        root.set_zobrist_hash(self.z_data.borrow().hash_of(state));
+
+        let (predicted_moves, target) = match mode {
+            PonderMode::Predicted => self.predict_replies(&root),
+            PonderMode::AllReplies => (Vec::new(), None),
+        };
+        let mode = match target {
+            Some(_) => PonderMode::Predicted,
+            None => PonderMode::AllReplies,
+        };
+
+        self.stop_handle.reset();
+        let stop = Arc::new(AtomicBool::new(false));
+        let started = Instant::now();
+        let worker = Minimax {
+            max_time: None,
+            threads: self.threads,
+            ..self.helper(stop.clone())
+        };
+        let (sender, result) = mpsc::channel();
+        let handle = match target.clone() {
+            Some((target, _)) => thread::spawn(move || {
+                let _ = sender.send(worker.search_without_reset(&target));
+            }),
+            None => thread::spawn(move || {
+                worker.start_time.set(Some(started));
+                worker.help(root, 0, false);
+                let _ = sender.send(None);
+            }),
+        };
+
+        Some(Ponder {
+            mode,
+            predicted_moves,
+            target,
+            started,
+            stop,
+            result,
+            handle: Some(handle),
+        })
+    }
+
+    /// Searches the supplied state (i.e., the state after the opponent's reply),
+    /// making use of a [`Ponder`] started after the engine's previous move.
+    ///
+    /// On a ponderhit (i.e., the state is the one being searched), the
+    /// background search continues for up to [`max_time`][Self::max_time] from
+    /// now, and its result is returned. Otherwise, the background search is
+    /// stopped, and a new search is run using
+    /// [`search_utility`][Self::search_utility].
+    ///
+    /// If no move was found successfully, returns [`None`].
+    pub fn search_after_ponder(&self, ponder: Ponder<T>, state: &T) -> Option<SearchResult> {
+        if !self.is_ponderhit(&ponder, state) {
+            ponder.stop();
+            return self.search_utility(state);
+        }
+
+        let wait = match self.time_budget.get() {
+            Some(budget) => Some(budget.target),
+            None => self.max_time,
+        };
+        let result = match wait {
+            Some(max) => match ponder.result.recv_timeout(max) {
+                Ok(r) => r,
+                Err(RecvTimeoutError::Timeout) => {
+                    ponder.stop.store(true, Ordering::Relaxed);
+                    ponder.result.recv().ok().flatten()
+                }
+                Err(RecvTimeoutError::Disconnected) => None,
+            },
+            None => ponder.result.recv().ok().flatten(),
+        };
+        drop(ponder);
+        result.or_else(|| self.search_utility(state))
+    }
+
+    /// Same as [`search_after_ponder`][Self::search_after_ponder], but within
+    /// the supplied time budget (see [`search_timed`][Self::search_timed]).
+    ///
+    /// On a ponderhit, the background search continues for up to the target
+    /// time of the budget from now.
+    pub fn search_timed_after_ponder(
+        &self,
+        ponder: Ponder<T>,
+        state: &T,
+        budget: TimeBudget,
+    ) -> Option<SearchResult> {
+        self.time_budget.set(Some(budget));
+        let result = self.search_after_ponder(ponder, state);
+        self.time_budget.set(None);
+        result
+    }
+
+    /// Returns whether the supplied state is the one being searched by the
+    /// [`Ponder`] (i.e., whether their Zobrist hashes match).
+    fn is_ponderhit(&self, ponder: &Ponder<T>, state: &T) -> bool {
+        let Some((_, hash)) = &ponder.target else {
+            return false;
+        };
+        let z_data = self.z_data.borrow();
+        z_data.is_valid_for(state) && z_data.hash_of(state) == *hash
+    }
+
+    /// Predicts the opponent's moves from the supplied root state using the
+    /// transposition table, until it is the engine's turn.
+    ///
+    /// Returns the moves, along with the resulting state and its Zobrist hash,
+    /// or no state if any move could not be predicted.
+    fn predict_replies(&self, root: &T) -> (Vec<Move>, Option<(T, u64)>) {
+        let z_data = self.z_data.borrow();
+        let mut moves = Vec::new();
+        let mut state = root.clone();
+        while state.current_turn() != self.optimize_for {
+            let next = self
+                .get_tt_move(&state)
+                .and_then(|m| Some((m, state.make_move_zobrist(&z_data, m).ok()?)));
+            match next {
+                Some((m, s)) if !s.is_over() => {
+                    moves.push(m);
+                    state = s;
+                }
+                _ => return (Vec::new(), None),
+            }
+        }
+        let hash = z_data.hash_of(&state);
+        (moves, Some((state, hash)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Mancala, Player};
+    use crate::minimax::MinimaxBuilder;
+    use std::time::Duration;
+
+    /// Returns the state after the engine's moves from the start of the game.
+    fn after_engine_moves(engine: &Minimax<GameState<6>>) -> GameState<6> {
+        let mut state = GameState::<6>::default();
+        while state.current_turn() == Player::One {
+            let m = engine.search_utility(&state).unwrap().found_move;
+            state = state.make_move(m).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn ponderhit() {
+        let engine = MinimaxBuilder::default().max_depth(Some(6)).build();
+        let state = after_engine_moves(&engine);
+        let ponder = engine.ponder(&state, PonderMode::Predicted).unwrap();
+        assert_eq!(ponder.mode(), PonderMode::Predicted);
+        assert!(!ponder.predicted_moves().is_empty());
+
+        let predicted = *ponder.predicted_state().unwrap();
+        let result = engine.search_after_ponder(ponder, &predicted).unwrap();
+        assert!(predicted.is_valid_move(result.found_move));
+        assert_eq!(result.depth_searched, Some(6));
+    }
+
+    #[test]
+    fn stopping() {
+        let mut engine = MinimaxBuilder::default().max_depth(Some(4)).build();
+        let state = after_engine_moves(&engine);
+        // Keeps the transposition table, so that the reply can be predicted.
+        engine.max_depth = None;
+
+        // Dropping stops the background search.
+        let ponder = engine.ponder(&state, PonderMode::AllReplies).unwrap();
+        let stop = ponder.stop.clone();
+        drop(ponder);
+        assert!(stop.load(Ordering::Relaxed));
+
+        // So does the stop handle of the instance which started it.
+        for mode in [PonderMode::Predicted, PonderMode::AllReplies] {
+            let ponder = engine.ponder(&state, mode).unwrap();
+            assert_eq!(ponder.mode(), mode);
+            engine.stop_handle().stop();
+            let deadline = Instant::now() + Duration::from_secs(30);
+            while !ponder.is_finished() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(1));
+            }
+            assert!(ponder.is_finished());
+            assert!(!ponder.stop.load(Ordering::Relaxed));
+        }
+    }
+}
@@ -0,0 +1,590 @@
+//! A strong solver, which proves the exact game-theoretic value of small
+//! configurations (e.g., 3–4 pits with 1–3 stones per pit) by searching every
+//! position until the end of the game.
+//!
+//! Values are final score differentials (i.e., the score of the player to move
+//! minus their opponent's score, once the game is over). Each position is
+//! solved using MTD(f): a series of null-window alpha-beta searches, each of
+//! which proves either a lower or an upper bound on the value, until the bounds
+//! meet. Since stones never leave the stores, the value of every position is
+//! bounded by its store differential, plus or minus the stones left in the
+//! pits, which narrows the search before anything is proven.
+//!
+//! The proven bounds of every position searched are kept (keyed by an exact
+//! encoding of the position, so there are no hash collisions), and the solved
+//! positions can be saved to a versioned binary file (all values little-endian):
+//!
+//! | Field        | Size     | Description                                      |
+//! |--------------|----------|--------------------------------------------------|
+//! | Magic        | 4 bytes  | `MMSV`                                           |
+//! | Version      | 4 bytes  | The file format version (currently `1`)          |
+//! | Rules        | 8 bytes  | A hash of the rule set                           |
+//! | Pits         | 8 bytes  | The number of pits per player                    |
+//! | Total stones | 8 bytes  | The total number of stones in play               |
+//! | Positions    | 8 bytes  | The number of positions which follow             |
+//! | Key          | 16 bytes | The encoded position                             |
+//! | Value        | 2 bytes  | The value of the position                        |
+//! | Move         | 2 bytes  | An optimal move (pit number, `0` for the swap move, or `0xFFFF` if unknown) |
+//!
+//! The fields from `Key` onward are repeated for each position.
+
+use super::persist::{PersistError, read_preamble, read_u64, write_preamble};
+use super::zobrist::rules_key;
+use crate::game::{Mancala, Move, Player};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The bytes identifying a solved position file.
+const MAGIC: [u8; 4] = *b"MMSV";
+
+/// The current version of the solved position file format.
+const VERSION: u32 = 1;
+
+/// The move stored for a solved position when no optimal move is known.
+const NO_MOVE: u16 = u16::MAX;
+
+/// The proven value of a position, from the perspective of the player to move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveResult {
+    /// The final score differential the player to move can force, assuming
+    /// both players play perfectly.
+    pub value: i32,
+    /// A move which achieves the value ([`None`] if the game is over).
+    pub best_move: Option<Move>,
+    /// The line of play (from both players) which achieves the value, until
+    /// the end of the game.
+    pub principal_variation: Vec<Move>,
+    /// The number of states visited while solving.
+    pub nodes: u64,
+}
+
+/// The bounds proven for the value of a position.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    lower: i16,
+    upper: i16,
+    best_move: Option<Move>,
+}
+
+impl Bounds {
+    #[inline]
+    fn is_exact(&self) -> bool {
+        self.lower == self.upper
+    }
+}
+
+/// Proves the exact values of positions with a certain number of pits, total
+/// stones, and rule set, remembering every position it has searched.
+///
+/// Solving requires the game to end without any position repeating, which is
+/// guaranteed by [`Kalah`][crate::game::Kalah] (since stones only leave a
+/// player's side by passing through their store), but not by every rule set.
+/// Positions are solved depth-first, so solving a large configuration may
+/// exhaust the memory or stack before it finishes.
+#[derive(Clone)]
+pub struct Solver {
+    pits: usize,
+    total_stones: usize,
+    rules_key: u64,
+    bits: u32,
+    table: FxHashMap<u128, Bounds>,
+    path: FxHashSet<u128>,
+    nodes: u64,
+    repeated: bool,
+}
+
+impl Solver {
+    /// Creates a solver for states like the supplied state (i.e., with the same
+    /// number of pits, total stones, and rule set).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the positions cannot be encoded in 128 bits (i.e., if there
+    /// are too many pits or stones to be solved in a reasonable amount of time).
+    pub fn new(template: &impl Mancala) -> Self {
+        let pits = template.pits();
+        let total_stones = template.total_stones();
+        let bits = usize::BITS - total_stones.leading_zeros();
+        assert!(
+            (2 * pits + 2) * bits as usize + 2 <= u128::BITS as usize,
+            "positions with {} pits and {} stones are too large to solve",
+            pits,
+            total_stones
+        );
+        Self {
+            pits,
+            total_stones,
+            rules_key: rules_key(template),
+            bits,
+            table: FxHashMap::default(),
+            path: FxHashSet::default(),
+            nodes: 0,
+            repeated: false,
+        }
+    }
+
+    /// Returns the number of pits per player in every position.
+    #[inline]
+    pub fn pits(&self) -> usize {
+        self.pits
+    }
+
+    /// Returns the total number of stones in play in every position.
+    #[inline]
+    pub fn total_stones(&self) -> usize {
+        self.total_stones
+    }
+
+    /// Returns the number of positions with proven bounds.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns whether no positions have been searched.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Returns the number of positions whose exact value has been proven.
+    pub fn solved(&self) -> usize {
+        self.table.values().filter(|b| b.is_exact()).count()
+    }
+
+    /// Forgets every position searched so far.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Returns whether the supplied state has the same number of pits, total
+    /// stones, and rule set as the solver.
+    pub fn is_valid_for(&self, state: &impl Mancala) -> bool {
+        state.pits() == self.pits
+            && state.total_stones() == self.total_stones
+            && rules_key(state) == self.rules_key
+    }
+
+    /// Returns the proven value of the supplied state, if it has already been
+    /// solved (without searching).
+    pub fn value(&self, state: &impl Mancala) -> Option<i32> {
+        if !self.is_valid_for(state) {
+            return None;
+        }
+        if state.is_over() {
+            return Some(store_differential(state));
+        }
+        self.table
+            .get(&self.key(state))
+            .filter(|b| b.is_exact())
+            .map(|b| i32::from(b.lower))
+    }
+
+    /// Proves the exact value of the supplied state, along with an optimal move
+    /// and the line of play which follows it.
+    ///
+    /// Returns [`None`] if a position repeated during the search (see the
+    /// [type documentation][Self]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is not like the state the solver was created for.
+    pub fn solve<T: Mancala>(&mut self, state: &T) -> Option<SolveResult> {
+        assert!(
+            self.is_valid_for(state),
+            "the state must have the same number of pits, total stones, and rules as the solver"
+        );
+        self.nodes = 0;
+        let mut state = state.clone();
+        let value = self.solve_exact(&mut state)?;
+
+        // Follow optimal moves until the end of the game.
+        let mut principal_variation = Vec::new();
+        let mut line = state.clone();
+        while let Some(m) = self.optimal_moves_of(&mut line)?.first().copied() {
+            principal_variation.push(m);
+            line.apply_move(m).unwrap();
+        }
+
+        Some(SolveResult {
+            value,
+            best_move: principal_variation.first().copied(),
+            principal_variation,
+            nodes: self.nodes,
+        })
+    }
+
+    /// Returns every move which achieves the proven value of the supplied state
+    /// (i.e., the optimal strategy from the state), solving it if necessary.
+    ///
+    /// Returns [`None`] if a position repeated during the search.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is not like the state the solver was created for.
+    pub fn optimal_moves<T: Mancala>(&mut self, state: &T) -> Option<Vec<Move>> {
+        assert!(
+            self.is_valid_for(state),
+            "the state must have the same number of pits, total stones, and rules as the solver"
+        );
+        self.optimal_moves_of(&mut state.clone())
+    }
+
+    /// Saves every solved position to a file (see the [module documentation][self]).
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        let mut w = BufWriter::new(File::create(path)?);
+        write_preamble(&mut w, MAGIC, VERSION)?;
+
+        // Positions are sorted, so that the same solver always produces the same file.
+        let mut solved: Vec<_> = self.table.iter().filter(|(_, b)| b.is_exact()).collect();
+        solved.sort_unstable_by_key(|(key, _)| **key);
+        for value in [
+            self.rules_key,
+            self.pits as u64,
+            self.total_stones as u64,
+            solved.len() as u64,
+        ] {
+            w.write_all(&value.to_le_bytes())?;
+        }
+        for (key, bounds) in solved {
+            let selection = match bounds.best_move {
+                Some(Move::Swap) => 0,
+                Some(Move::Pit(pit)) => pit as u16,
+                None => NO_MOVE,
+            };
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&bounds.lower.to_le_bytes())?;
+            w.write_all(&selection.to_le_bytes())?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Loads the positions saved by [`save`][Self::save], for solving states like
+    /// the supplied state (i.e., with the same number of pits, total stones, and
+    /// rule set).
+    ///
+    /// Fails if the file was saved for states which are not like the supplied state.
+    pub fn load(path: impl AsRef<Path>, state: &impl Mancala) -> Result<Self, PersistError> {
+        let mut r = BufReader::new(File::open(path)?);
+        read_preamble(&mut r, MAGIC, VERSION)?;
+        let mut solver = Self::new(state);
+        let parameters = [
+            ("rule set", read_u64(&mut r)?, solver.rules_key),
+            ("number of pits", read_u64(&mut r)?, solver.pits as u64),
+            (
+                "total stones",
+                read_u64(&mut r)?,
+                solver.total_stones as u64,
+            ),
+        ];
+        if let Some((parameter, file, current)) = parameters
+            .into_iter()
+            .find(|(_, file, current)| file != current)
+        {
+            return Err(PersistError::Mismatch {
+                parameter,
+                file,
+                current,
+            });
+        }
+
+        let len = read_u64(&mut r)?;
+        for _ in 0..len {
+            let mut bytes = [0; 20];
+            r.read_exact(&mut bytes)?;
+            let key = u128::from_le_bytes(bytes[..16].try_into().unwrap());
+            let value = i16::from_le_bytes([bytes[16], bytes[17]]);
+            let best_move = match u16::from_le_bytes([bytes[18], bytes[19]]) {
+                NO_MOVE => None,
+                0 => Some(Move::Swap),
+                pit => Some(Move::Pit(pit.into())),
+            };
+            solver.table.insert(
+                key,
+                Bounds {
+                    lower: value,
+                    upper: value,
+                    best_move,
+                },
+            );
+        }
+        Ok(solver)
+    }
+
+    /// Encodes a position (excluding the ply) as an integer, with a fixed
+    /// number of bits for each pit and store.
+    fn key(&self, state: &impl Mancala) -> u128 {
+        let mut key =
+            u128::from(state.current_turn() == Player::Two) << 1 | u128::from(state.p2_moved());
+        let board = state.board();
+        let locations = board[Player::One]
+            .as_ref()
+            .iter()
+            .chain(board[Player::Two].as_ref())
+            .chain(state.stores());
+        for stones in locations {
+            key = key << self.bits | *stones as u128;
+        }
+        key
+    }
+
+    /// Returns the moves which achieve the proven value of a state.
+    fn optimal_moves_of<T: Mancala>(&mut self, state: &mut T) -> Option<Vec<Move>> {
+        if state.is_over() {
+            return Some(Vec::new());
+        }
+        let value = self.solve_exact(state)?;
+        let mover = state.current_turn();
+
+        // Try the stored move first, since it is usually optimal.
+        let mut moves = state.valid_moves();
+        if let Some(best) = self.table.get(&self.key(state)).and_then(|b| b.best_move)
+            && let Some(pos) = moves.iter().position(|m| *m == best)
+        {
+            moves.swap(0, pos);
+        }
+
+        let mut optimal = Vec::new();
+        for m in moves {
+            let undo = state.apply_move(m).unwrap();
+            let child = self.solve_exact(state);
+            let same_mover = state.current_turn() == mover;
+            state.undo_move(undo);
+            let child = child?;
+            if (same_mover && child == value) || (!same_mover && -child == value) {
+                optimal.push(m);
+            }
+        }
+        Some(optimal)
+    }
+
+    /// Proves the exact value of a state using MTD(f).
+    fn solve_exact<T: Mancala>(&mut self, state: &mut T) -> Option<i32> {
+        if state.is_over() {
+            return Some(store_differential(state));
+        }
+
+        let (mut lower, mut upper) = self.bounds(state);
+        let mut guess = store_differential(state).clamp(lower, upper);
+        while lower < upper {
+            let beta = if guess == lower { guess + 1 } else { guess };
+            guess = self.search(state, beta - 1, beta);
+            if self.repeated {
+                self.repeated = false;
+                self.path.clear();
+                return None;
+            }
+            if guess < beta {
+                upper = guess;
+            } else {
+                lower = guess;
+            }
+        }
+        Some(lower)
+    }
+
+    /// Returns the proven bounds on the value of a state, or the bounds implied
+    /// by the stones left in the pits if nothing has been proven.
+    fn bounds(&self, state: &impl Mancala) -> (i32, i32) {
+        match self.table.get(&self.key(state)) {
+            Some(b) => (i32::from(b.lower), i32::from(b.upper)),
+            None => {
+                let stores = store_differential(state);
+                let remaining =
+                    (state.total_stones() - state.stores().iter().sum::<usize>()) as i32;
+                (stores - remaining, stores + remaining)
+            }
+        }
+    }
+
+    /// Fail-soft alpha-beta search which proves bounds on the value of a state,
+    /// from the perspective of the player to move.
+    fn search<T: Mancala>(&mut self, state: &mut T, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        if state.is_over() {
+            return store_differential(state);
+        }
+
+        // Narrow the bounds using the proven (or implied) bounds of the state.
+        let key = self.key(state);
+        let (lower, upper) = self.bounds(state);
+        if lower >= beta || lower == upper {
+            return lower;
+        }
+        if upper <= alpha {
+            return upper;
+        }
+        alpha = alpha.max(lower);
+        beta = beta.min(upper);
+        let (alpha_orig, beta_orig) = (alpha, beta);
+
+        if !self.path.insert(key) {
+            self.repeated = true;
+            return alpha;
+        }
+
+        // Try the stored move first, then the remaining moves in descending
+        // order by pit number.
+        let stored = self.table.get(&key).and_then(|b| b.best_move);
+        let mut moves = state.valid_moves();
+        moves.reverse();
+        if let Some(best) = stored
+            && let Some(pos) = moves.iter().position(|m| *m == best)
+        {
+            let m = moves.remove(pos);
+            moves.insert(0, m);
+        }
+
+        if moves.is_empty() {
+            self.path.remove(&key);
+            return store_differential(state);
+        }
+
+        let mover = state.current_turn();
+        let mut best = i32::MIN;
+        let mut best_move = None;
+        for m in moves {
+            let undo = state.apply_move(m).unwrap();
+            let v = if state.current_turn() == mover {
+                self.search(state, alpha, beta)
+            } else {
+                -self.search(state, -beta, -alpha)
+            };
+            state.undo_move(undo);
+            if self.repeated {
+                return alpha;
+            }
+
+            if v > best {
+                best = v;
+                best_move = Some(m);
+                alpha = alpha.max(v);
+            }
+            if best >= beta {
+                break;
+            }
+        }
+        self.path.remove(&key);
+
+        // Record the proven bound.
+        let mut bounds = self.table.get(&key).copied().unwrap_or(Bounds {
+            lower: lower as i16,
+            upper: upper as i16,
+            best_move: None,
+        });
+        if best <= alpha_orig {
+            bounds.upper = bounds.upper.min(best as i16);
+        } else {
+            bounds.lower = bounds.lower.max(best as i16);
+            bounds.best_move = best_move;
+            if best < beta_orig {
+                bounds.upper = best as i16;
+            }
+        }
+        self.table.insert(key, bounds);
+        best
+    }
+}
+
+impl Debug for Solver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Solver")
+            .field("pits", &self.pits)
+            .field("total_stones", &self.total_stones)
+            .field("positions", &self.table.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the store differential from the perspective of the player to move.
+fn store_differential(state: &impl Mancala) -> i32 {
+    let mover = state.current_turn();
+    state.stores()[mover] as i32 - state.stores()[mover.other()] as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::{Kalah, KalahOptions, Sweep};
+    use crate::game::{GameState, Player};
+
+    /// Returns the value of the state from the perspective of the player to
+    /// move, by searching every line of play without any pruning.
+    fn brute_force<T: Mancala>(state: &T) -> i32 {
+        if state.is_over() {
+            return store_differential(state);
+        }
+        state
+            .valid_moves()
+            .into_iter()
+            .map(|m| {
+                let next = state.make_move(m).unwrap();
+                let v = brute_force(&next);
+                if next.current_turn() == state.current_turn() {
+                    v
+                } else {
+                    -v
+                }
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// Checks the solved value of the state, and of the states following every
+    /// move from it, against a brute-force search.
+    fn assert_solves<T: Mancala>(state: &T) {
+        let mut solver = Solver::new(state);
+        let result = solver.solve(state).unwrap();
+        assert_eq!(result.value, brute_force(state));
+        assert_eq!(solver.value(state), Some(result.value));
+
+        // Playing out the principal variation achieves the value.
+        let mut line = state.clone();
+        for m in &result.principal_variation {
+            line = line.make_move(*m).unwrap();
+        }
+        assert!(line.is_over());
+        let mover = state.current_turn();
+        let final_value = line.stores()[mover] as i32 - line.stores()[mover.other()] as i32;
+        assert_eq!(final_value, result.value);
+
+        for m in state.valid_moves() {
+            let next = state.make_move(m).unwrap();
+            assert_eq!(solver.solve(&next).unwrap().value, brute_force(&next));
+        }
+        for m in solver.optimal_moves(state).unwrap() {
+            let next = state.make_move(m).unwrap();
+            let v = brute_force(&next);
+            let v = if next.current_turn() == mover { v } else { -v };
+            assert_eq!(v, result.value);
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let emptied = Kalah::new(KalahOptions {
+            empty_capture: false,
+            sweep: Sweep::Emptied,
+            pie_rule: false,
+        });
+        for stones in 1..=2 {
+            let state = GameState::<3>::try_new(stones, 0, 0, Player::One, 1, false).unwrap();
+            assert_solves(&state);
+            assert_solves(&state.with_rules(emptied));
+        }
+        assert_solves(&GameState::<2>::try_new(3, 0, 0, Player::One, 1, false).unwrap());
+
+        // Positions later in games with more stones.
+        for _ in 0..10 {
+            let mut state = GameState::<3>::try_new(3, 0, 0, Player::One, 1, false).unwrap();
+            while !state.is_over() && state.ply() < 6 {
+                state = state.make_move_rand().unwrap().0;
+            }
+            assert_solves(&state);
+        }
+    }
+}
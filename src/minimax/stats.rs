@@ -0,0 +1,166 @@
+//! Statistics describing the work done during minimax searches.
+
+use std::fmt::Display;
+use std::iter::Sum;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// Counts of the work done by [`Minimax`][super::Minimax] during a search, or
+/// during a single iteration of iterative deepening.
+///
+/// Statistics for a whole search are the sum of the statistics for each of its
+/// iterations (with the [`depth`][Self::depth] of the deepest one).
+/// When several threads are used, only the work done by the main search thread
+/// is counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// The depth limit used for the search ([`None`] if unlimited).
+    pub depth: Option<usize>,
+    /// Whether the search finished before the time limit expired.
+    pub completed: bool,
+    /// The number of states visited.
+    pub nodes: u64,
+    /// The number of states scored exactly, i.e., terminal states scored using
+    /// the evaluator function, and states found in the endgame database.
+    pub exact_evaluations: u64,
+    /// The number of non-terminal states scored using the heuristic function,
+    /// after reaching the depth limit.
+    pub heuristic_evaluations: u64,
+    /// The number of times the transposition table was checked for a state.
+    pub tt_probes: u64,
+    /// The number of probes which found an entry for the state.
+    pub tt_hits: u64,
+    /// The number of probes which found an entry that could be used in place of
+    /// searching the state.
+    pub tt_cutoffs: u64,
+    /// The number of cutoffs (i.e., pruned states) caused by the move at each
+    /// index of the move order. Both alpha and beta cutoffs are counted, since
+    /// each is a beta cutoff from the perspective of the player moving.
+    pub beta_cutoffs: Vec<u64>,
+    /// The time spent searching.
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    /// Create a new, empty set of statistics for a search with the supplied
+    /// depth limit.
+    pub fn new(depth: Option<usize>) -> Self {
+        Self {
+            depth,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of states scored using either the evaluator or
+    /// heuristic function.
+    #[inline]
+    pub fn leaf_evaluations(&self) -> u64 {
+        self.exact_evaluations + self.heuristic_evaluations
+    }
+
+    /// Returns the total number of cutoffs, regardless of move index.
+    #[inline]
+    pub fn total_cutoffs(&self) -> u64 {
+        self.beta_cutoffs.iter().sum()
+    }
+
+    /// Returns the fraction of cutoffs caused by the first move tried, which
+    /// indicates the quality of the move ordering. Returns [`None`] if there
+    /// were no cutoffs.
+    pub fn first_move_cutoff_rate(&self) -> Option<f64> {
+        let total = self.total_cutoffs();
+        (total > 0).then(|| self.beta_cutoffs[0] as f64 / total as f64)
+    }
+
+    /// Returns the number of states visited per second.
+    pub fn nodes_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.nodes as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Records a cutoff caused by the move at the supplied index.
+    #[inline]
+    pub(super) fn record_cutoff(&mut self, index: usize) {
+        if self.beta_cutoffs.len() <= index {
+            self.beta_cutoffs.resize(index + 1, 0);
+        }
+        self.beta_cutoffs[index] += 1;
+    }
+
+    /// Adds the counts from another set of statistics, without changing the
+    /// depth, completion status, or elapsed time.
+    pub(super) fn add_counts(&mut self, rhs: &SearchStats) {
+        self.nodes += rhs.nodes;
+        self.exact_evaluations += rhs.exact_evaluations;
+        self.heuristic_evaluations += rhs.heuristic_evaluations;
+        self.tt_probes += rhs.tt_probes;
+        self.tt_hits += rhs.tt_hits;
+        self.tt_cutoffs += rhs.tt_cutoffs;
+        if self.beta_cutoffs.len() < rhs.beta_cutoffs.len() {
+            self.beta_cutoffs.resize(rhs.beta_cutoffs.len(), 0);
+        }
+        for (total, count) in self.beta_cutoffs.iter_mut().zip(&rhs.beta_cutoffs) {
+            *total += count;
+        }
+    }
+}
+
+impl AddAssign<&SearchStats> for SearchStats {
+    /// Adds the counts from another set of statistics, keeping the deeper of
+    /// the two depth limits, and the completion status of the other set.
+    fn add_assign(&mut self, rhs: &SearchStats) {
+        self.depth = match (self.depth, rhs.depth) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+        self.completed = rhs.completed;
+        self.add_counts(rhs);
+        self.elapsed += rhs.elapsed;
+    }
+}
+
+impl<'a> Sum<&'a SearchStats> for SearchStats {
+    /// Combines the statistics for each iteration of a search, in order.
+    fn sum<I: Iterator<Item = &'a SearchStats>>(mut iter: I) -> Self {
+        let mut total = iter.next().cloned().unwrap_or_default();
+        for stats in iter {
+            total += stats;
+        }
+        total
+    }
+}
+
+impl Display for SearchStats {
+    /// Formats the statistics as a single line, for logging.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.depth {
+            Some(d) => write!(f, "depth {d}")?,
+            None => write!(f, "depth -")?,
+        }
+        if !self.completed {
+            write!(f, " (incomplete)")?;
+        }
+        write!(
+            f,
+            ": {} nodes in {:.3?} ({:.0} nodes/s), {} exact / {} heuristic evals, \
+             tt {} probes / {} hits / {} cutoffs, {} cutoffs",
+            self.nodes,
+            self.elapsed,
+            self.nodes_per_second(),
+            self.exact_evaluations,
+            self.heuristic_evaluations,
+            self.tt_probes,
+            self.tt_hits,
+            self.tt_cutoffs,
+            self.total_cutoffs(),
+        )?;
+        if let Some(rate) = self.first_move_cutoff_rate() {
+            write!(f, " ({:.1}% on first move)", rate * 100.0)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+//! Cooperative cancellation of searches which are running in another thread.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A cloneable, thread-safe flag used to stop a search early (e.g., when a
+/// user moves, resigns, or disconnects while the engine is thinking).
+///
+/// Every clone of a handle shares the same flag, so a handle obtained from
+/// [`Minimax::stop_handle`][super::Minimax::stop_handle] can be moved to another
+/// thread and used to stop the searches run by that instance.
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    /// Create a new handle, which has not been stopped.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that the current search stops as soon as possible.
+    #[inline]
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether a stop has been requested since the handle was last reset.
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears any stop request, so that the next search runs normally.
+    #[inline]
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns whether both handles share the same flag.
+    #[inline]
+    pub fn ptr_eq(&self, other: &StopHandle) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Mancala};
+    use crate::minimax::MinimaxBuilder;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn clones_share_flag() {
+        let handle = StopHandle::new();
+        let clone = handle.clone();
+        assert!(handle.ptr_eq(&clone));
+        assert!(!handle.ptr_eq(&StopHandle::new()));
+
+        clone.stop();
+        assert!(handle.is_stopped());
+        handle.reset();
+        assert!(!clone.is_stopped());
+    }
+
+    #[test]
+    fn stopped_search_returns_best_move() {
+        let state = GameState::<6>::default();
+        let minimax = MinimaxBuilder::default().max_depth(None).build();
+        let handle = minimax.stop_handle();
+
+        // A stop requested before the search starts is cleared.
+        handle.stop();
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            handle.stop();
+        });
+        let start = Instant::now();
+        let result = minimax.search_utility(&state).unwrap();
+        let elapsed = start.elapsed();
+        stopper.join().unwrap();
+
+        assert!(elapsed >= Duration::from_millis(200));
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+        assert!(!result.fully_searched);
+        assert!(result.depth_searched.is_some_and(|d| d > 1));
+        assert!(state.is_valid_move(result.found_move));
+        assert_eq!(result.principal_variation.first(), Some(&result.found_move));
+    }
+}
@@ -0,0 +1,631 @@
+//! Transposition tables used to store the results of previously searched states.
+
+use crate::game::Move;
+#[cfg(feature = "parallel")]
+use rustc_hash::FxHashMap;
+#[cfg(feature = "parallel")]
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+/// The number of slots in each bucket of a [`FixedTable`]. The last slot in each
+/// bucket is always replaced, while the others prefer deeper entries.
+const SLOTS_PER_BUCKET: usize = 4;
+
+/// Helper enum to store transposition table entry bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValueBound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// Helper struct for storing data in the transposition table.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TTEntry {
+    pub(super) utility: f32,
+    pub(super) bound: ValueBound,
+    pub(super) found_move: Option<Move>,
+    pub(super) fully_searched: bool,
+    pub(super) remaining: usize,
+}
+
+impl TTEntry {
+    pub(super) fn new(
+        value: f32,
+        remaining: usize,
+        found_move: Option<Move>,
+        fully_searched: bool,
+        alpha_orig: f32,
+        beta_orig: f32,
+    ) -> Self {
+        let bound = if value <= alpha_orig {
+            ValueBound::Upper
+        } else if value >= beta_orig {
+            ValueBound::Lower
+        } else {
+            ValueBound::Exact
+        };
+
+        Self {
+            utility: value,
+            remaining,
+            bound,
+            found_move,
+            fully_searched,
+        }
+    }
+
+    /// Determines whether the entry should replace an existing entry for the
+    /// same state (i.e., it was searched more deeply, or more completely).
+    #[inline]
+    fn replaces(&self, old: &TTEntry) -> bool {
+        (self.remaining >= old.remaining) || (self.fully_searched && !old.fully_searched)
+    }
+
+    /// Packs the entry into 64 bits, for saving to a file.
+    #[inline]
+    pub(super) fn to_bits(self) -> u64 {
+        pack(&self, 0)
+    }
+
+    /// Unpacks an entry packed with [`to_bits`][Self::to_bits], returning
+    /// [`None`] if the bits do not describe an entry.
+    #[inline]
+    pub(super) fn from_bits(bits: u64) -> Option<Self> {
+        unpack(bits).map(|(entry, _)| entry)
+    }
+
+    /// Returns the depth to which the entry is valid, which is unlimited for
+    /// fully searched states.
+    #[inline]
+    fn effective_depth(&self) -> usize {
+        if self.fully_searched {
+            usize::MAX
+        } else {
+            self.remaining
+        }
+    }
+}
+
+/// The transposition table owned by a [`Minimax`][super::Minimax] instance.
+///
+/// Entries are stored in a [`FixedTable`], which can be shared with helper
+/// threads (see [`share`][Self::share]), since every thread can access it at
+/// the same time.
+///
+/// Workers evaluating root moves in parallel use an overlay, which reads from
+/// another table without modifying it, but only writes to its own map.
+#[derive(Debug)]
+pub(crate) enum TTable {
+    Fixed(Arc<FixedTable>),
+    #[cfg(feature = "parallel")]
+    Overlay {
+        base: Arc<FixedTable>,
+        local: RefCell<FxHashMap<u64, TTEntry>>,
+    },
+}
+
+impl TTable {
+    /// Create a new, empty table using (at most) the supplied number of megabytes.
+    pub(super) fn new(megabytes: usize) -> Self {
+        TTable::Fixed(Arc::new(FixedTable::new(megabytes)))
+    }
+
+    /// Returns a handle to the same table (rather than a copy of it), for use
+    /// by a helper thread.
+    pub(super) fn share(&self) -> Self {
+        match self {
+            TTable::Fixed(table) => TTable::Fixed(table.clone()),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { .. } => unreachable!("overlays are never shared"),
+        }
+    }
+
+    /// Create a new, empty overlay which reads from this table. The table
+    /// must not be modified until the overlay is merged back with
+    /// [`merge`][Self::merge], so that the results of each worker never depend
+    /// on the others.
+    #[cfg(feature = "parallel")]
+    pub(super) fn overlay(&self) -> Self {
+        match self {
+            TTable::Fixed(table) => TTable::Overlay {
+                base: table.clone(),
+                local: Default::default(),
+            },
+            TTable::Overlay { .. } => unreachable!("overlays are never stacked"),
+        }
+    }
+
+    /// Returns the entry stored for the supplied key, if one exists.
+    #[inline]
+    pub(super) fn get(&self, key: u64) -> Option<TTEntry> {
+        match self {
+            TTable::Fixed(table) => table.get(key),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { base, local } => {
+                local.borrow().get(&key).copied().or_else(|| base.get(key))
+            }
+        }
+    }
+
+    /// Stores an entry for the supplied key, unless an existing entry for the
+    /// same key was searched more deeply.
+    #[inline]
+    pub(super) fn store(&self, key: u64, entry: TTEntry) {
+        match self {
+            TTable::Fixed(table) => table.store(key, entry),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { local, .. } => {
+                if self.get(key).is_none_or(|old| entry.replaces(&old)) {
+                    local.borrow_mut().insert(key, entry);
+                }
+            }
+        }
+    }
+
+    /// Marks the start of a new search, so that entries stored during earlier
+    /// searches are replaced first.
+    pub(super) fn new_search(&self) {
+        match self {
+            TTable::Fixed(table) => table.new_search(),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { .. } => {}
+        }
+    }
+
+    /// Removes every entry from the table.
+    pub(super) fn clear(&self) {
+        match self {
+            TTable::Fixed(table) => table.clear(),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { local, .. } => local.borrow_mut().clear(),
+        }
+    }
+
+    /// Returns every entry stored in the table, alongside its key.
+    pub(super) fn entries(&self) -> Vec<(u64, TTEntry)> {
+        match self {
+            TTable::Fixed(table) => table.entries().collect(),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { base, local } => {
+                let mut entries: FxHashMap<_, _> = base.entries().collect();
+                entries.extend(local.borrow().iter());
+                entries.into_iter().collect()
+            }
+        }
+    }
+
+    /// Returns the size of the table in megabytes (rounded down).
+    pub(super) fn megabytes(&self) -> usize {
+        match self {
+            TTable::Fixed(table) => table.megabytes(),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { base, .. } => base.megabytes(),
+        }
+    }
+
+    /// Stores the entries written to each of the supplied overlays (created
+    /// with [`overlay`][Self::overlay]), in order.
+    #[cfg(feature = "parallel")]
+    pub(super) fn merge(&self, overlays: Vec<TTable>) {
+        for overlay in overlays {
+            let TTable::Overlay { local, .. } = overlay else {
+                unreachable!("only overlays can be merged");
+            };
+            for (key, entry) in local.into_inner() {
+                self.store(key, entry);
+            }
+        }
+    }
+}
+
+impl Clone for TTable {
+    /// Copies every entry into a new table (which is not shared with the original).
+    fn clone(&self) -> Self {
+        match self {
+            TTable::Fixed(table) => TTable::Fixed(Arc::new(FixedTable::clone(table))),
+            #[cfg(feature = "parallel")]
+            TTable::Overlay { base, local } => TTable::Overlay {
+                base: base.clone(),
+                local: local.clone(),
+            },
+        }
+    }
+}
+
+/// A fixed-size transposition table which can be read and written by several
+/// threads at once without locking.
+///
+/// States are mapped to buckets of [`SLOTS_PER_BUCKET`] slots. Each slot stores
+/// a packed entry alongside the XOR of the entry and its key, which verifies
+/// every bit of the key, so that neither states sharing a bucket nor entries
+/// torn by concurrent writes are returned for the wrong state.
+///
+/// New entries first replace an existing entry for the same state (unless it
+/// was searched more deeply), then the
+/// shallowest of the depth-preferred slots (if the new entry is at least as
+/// deep), and otherwise the always-replace slot. Entries stored during earlier
+/// searches are treated as shallower than any entry from the current search,
+/// so that tables reused across many searches do not fill up with stale entries.
+pub(crate) struct FixedTable {
+    buckets: Box<[Bucket]>,
+    generation: AtomicU8,
+}
+
+/// A group of slots in a [`FixedTable`], aligned to fit in a single cache line.
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket {
+    slots: [Slot; SLOTS_PER_BUCKET],
+}
+
+/// A single slot in a [`FixedTable`].
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    /// Returns the entry stored in the slot, and the search generation in which it
+    /// was stored, if the slot holds an entry for the supplied key.
+    #[inline]
+    fn get(&self, key: u64) -> Option<(TTEntry, u8)> {
+        let data = self.data.load(Ordering::Relaxed);
+        let check = self.check.load(Ordering::Relaxed);
+        if check ^ data != key {
+            return None;
+        }
+        unpack(data)
+    }
+
+    /// Returns how valuable the entry stored in the slot is, relative to the
+    /// supplied search generation. Empty slots are the least valuable.
+    #[inline]
+    fn priority(&self, generation: u8) -> (bool, usize) {
+        match unpack(self.data.load(Ordering::Relaxed)) {
+            Some((entry, g)) => (g == generation, entry.effective_depth()),
+            None => (false, 0),
+        }
+    }
+
+    #[inline]
+    fn set(&self, key: u64, entry: &TTEntry, generation: u8) {
+        let data = pack(entry, generation);
+        self.data.store(data, Ordering::Relaxed);
+        self.check.store(key ^ data, Ordering::Relaxed);
+    }
+}
+
+impl FixedTable {
+    /// Create a new, empty table using (at most) the supplied number of
+    /// megabytes, but with at least one bucket.
+    fn new(megabytes: usize) -> Self {
+        let len = (megabytes << 20) / size_of::<Bucket>();
+        Self {
+            buckets: (0..len.max(1)).map(|_| Bucket::default()).collect(),
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    fn megabytes(&self) -> usize {
+        (self.buckets.len() * size_of::<Bucket>()) >> 20
+    }
+
+    #[inline]
+    fn bucket(&self, key: u64) -> &Bucket {
+        // Maps the key onto the buckets without requiring a power-of-two length.
+        let index = (u128::from(key) * self.buckets.len() as u128) >> 64;
+        &self.buckets[index as usize]
+    }
+
+    #[inline]
+    fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    fn get(&self, key: u64) -> Option<TTEntry> {
+        self.bucket(key)
+            .slots
+            .iter()
+            .find_map(|slot| slot.get(key))
+            .map(|(entry, _)| entry)
+    }
+
+    fn store(&self, key: u64, entry: TTEntry) {
+        let generation = self.generation();
+        let slots = &self.bucket(key).slots;
+
+        // Update the existing entry for the state, if there is one. Deeper
+        // entries from earlier searches are kept, but marked as current, since
+        // the state has been reached again.
+        for slot in slots {
+            if let Some((old, g)) = slot.get(key) {
+                if entry.replaces(&old) {
+                    slot.set(key, &entry, generation);
+                } else if g != generation {
+                    slot.set(key, &old, generation);
+                }
+                return;
+            }
+        }
+
+        let (depth_preferred, always_replace) = slots.split_at(SLOTS_PER_BUCKET - 1);
+        let shallowest = depth_preferred
+            .iter()
+            .min_by_key(|slot| slot.priority(generation))
+            .expect("buckets have depth-preferred slots");
+        if shallowest.priority(generation) <= (true, entry.effective_depth()) {
+            shallowest.set(key, &entry, generation);
+        } else {
+            always_replace[0].set(key, &entry, generation);
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (u64, TTEntry)> {
+        self.buckets
+            .iter()
+            .flat_map(|b| &b.slots)
+            .filter_map(|slot| {
+                let data = slot.data.load(Ordering::Relaxed);
+                let key = slot.check.load(Ordering::Relaxed) ^ data;
+                unpack(data).map(|(entry, _)| (key, entry))
+            })
+    }
+
+    fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        for slot in self.buckets.iter().flat_map(|b| &b.slots) {
+            slot.data.store(0, Ordering::Relaxed);
+            slot.check.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Clone for FixedTable {
+    /// Copies every entry into a new table (which is not shared with the original).
+    fn clone(&self) -> Self {
+        let buckets = self
+            .buckets
+            .iter()
+            .map(|b| Bucket {
+                slots: std::array::from_fn(|i| Slot {
+                    check: AtomicU64::new(b.slots[i].check.load(Ordering::Relaxed)),
+                    data: AtomicU64::new(b.slots[i].data.load(Ordering::Relaxed)),
+                }),
+            })
+            .collect();
+        Self {
+            buckets,
+            generation: AtomicU8::new(self.generation()),
+        }
+    }
+}
+
+impl Debug for FixedTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixedTable")
+            .field("buckets", &self.buckets.len())
+            .field("generation", &self.generation())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The packed remaining depth used for entries with unlimited remaining depth.
+const UNLIMITED_REMAINING: u64 = 0xFF;
+
+/// Packs an entry into 64 bits: the utility (32 bits), remaining depth (8
+/// bits), the search generation in which it was stored (8 bits), bound (2
+/// bits, where `0` marks an empty slot), whether the state was fully searched
+/// (1 bit), and the found move (13 bits).
+///
+/// Remaining depths which don't fit are understated, so that the entry is
+/// never trusted beyond the depth it was searched to.
+fn pack(entry: &TTEntry, generation: u8) -> u64 {
+    let remaining = match entry.remaining {
+        usize::MAX => UNLIMITED_REMAINING,
+        r => r.min(UNLIMITED_REMAINING as usize - 1) as u64,
+    };
+    let bound = match entry.bound {
+        ValueBound::Exact => 1,
+        ValueBound::Lower => 2,
+        ValueBound::Upper => 3,
+    };
+    // Moves which don't fit are dropped, since they are only used for ordering.
+    let found_move = match entry.found_move {
+        Some(Move::Swap) => 1,
+        Some(Move::Pit(pit)) if pit + 1 < (1 << 13) => pit as u64 + 1,
+        _ => 0,
+    };
+    (u64::from(entry.utility.to_bits()) << 32)
+        | (remaining << 24)
+        | (u64::from(generation) << 16)
+        | (bound << 14)
+        | (u64::from(entry.fully_searched) << 13)
+        | found_move
+}
+
+/// Unpacks an entry (and its generation) packed with [`pack`], returning
+/// [`None`] for empty slots.
+fn unpack(data: u64) -> Option<(TTEntry, u8)> {
+    let bound = match (data >> 14) & 0b11 {
+        1 => ValueBound::Exact,
+        2 => ValueBound::Lower,
+        3 => ValueBound::Upper,
+        _ => return None,
+    };
+    let remaining = match (data >> 24) & 0xFF {
+        UNLIMITED_REMAINING => usize::MAX,
+        r => r as usize,
+    };
+    let found_move = match data & 0x1FFF {
+        0 => None,
+        1 => Some(Move::Swap),
+        m => Some(Move::Pit(m as usize - 1)),
+    };
+    let entry = TTEntry {
+        utility: f32::from_bits((data >> 32) as u32),
+        bound,
+        found_move,
+        fully_searched: (data >> 13) & 1 == 1,
+        remaining,
+    };
+    Some((entry, (data >> 16) as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(remaining: usize) -> TTEntry {
+        TTEntry {
+            utility: remaining as f32 - 0.5,
+            bound: ValueBound::Exact,
+            found_move: Some(Move::Pit(remaining % 6 + 1)),
+            fully_searched: false,
+            remaining,
+        }
+    }
+
+    fn depth_of(table: &FixedTable, key: u64) -> Option<usize> {
+        table.get(key).map(|e| e.remaining)
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        let entries = [
+            entry(0),
+            entry(17),
+            TTEntry {
+                utility: f32::NEG_INFINITY,
+                bound: ValueBound::Upper,
+                found_move: Some(Move::Swap),
+                fully_searched: true,
+                remaining: usize::MAX,
+            },
+            TTEntry {
+                utility: 3.25,
+                bound: ValueBound::Lower,
+                found_move: None,
+                fully_searched: false,
+                remaining: 300,
+            },
+        ];
+        for e in entries {
+            let (unpacked, generation) = unpack(pack(&e, 42)).unwrap();
+            assert_eq!(generation, 42);
+            assert_eq!(unpacked.utility, e.utility);
+            assert_eq!(unpacked.bound, e.bound);
+            assert_eq!(unpacked.found_move, e.found_move);
+            assert_eq!(unpacked.fully_searched, e.fully_searched);
+            // Remaining depths which don't fit are understated.
+            let remaining = match e.remaining {
+                usize::MAX => usize::MAX,
+                r => r.min(254),
+            };
+            assert_eq!(unpacked.remaining, remaining);
+        }
+        assert!(unpack(0).is_none());
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let table = FixedTable::new(1);
+        let keys = [1, 0xDEAD_BEEF, u64::MAX, 1 << 63];
+        for (i, key) in keys.into_iter().enumerate() {
+            assert!(table.get(key).is_none());
+            table.store(key, entry(i + 1));
+        }
+        for (i, key) in keys.into_iter().enumerate() {
+            let found = table.get(key).unwrap();
+            assert_eq!(found.remaining, i + 1);
+            assert_eq!(found.utility, entry(i + 1).utility);
+            assert_eq!(found.found_move, entry(i + 1).found_move);
+        }
+        assert!(table.get(2).is_none());
+        assert_eq!(table.entries().count(), keys.len());
+
+        // Shallower entries for the same state are ignored.
+        table.store(1, entry(0));
+        assert_eq!(depth_of(&table, 1), Some(1));
+        table.store(1, entry(9));
+        assert_eq!(depth_of(&table, 1), Some(9));
+
+        table.clear();
+        assert!(keys.into_iter().all(|key| table.get(key).is_none()));
+    }
+
+    #[test]
+    fn collisions() {
+        // A table with a single bucket, so every key collides.
+        let table = FixedTable::new(0);
+        assert_eq!(table.buckets.len(), 1);
+
+        // The depth-preferred slots are filled first.
+        for key in 1..=3 {
+            table.store(key, entry(key as usize + 4));
+        }
+        // Shallower entries then go to the always-replace slot.
+        table.store(4, entry(1));
+        assert_eq!(depth_of(&table, 4), Some(1));
+        table.store(5, entry(2));
+        assert_eq!(depth_of(&table, 4), None);
+        assert_eq!(depth_of(&table, 5), Some(2));
+        assert_eq!(
+            (1..=3).map(|key| depth_of(&table, key)).collect::<Vec<_>>(),
+            [Some(5), Some(6), Some(7)]
+        );
+
+        // Deeper entries replace the shallowest depth-preferred entry.
+        table.store(6, entry(9));
+        assert_eq!(depth_of(&table, 1), None);
+        assert_eq!(depth_of(&table, 6), Some(9));
+        assert_eq!(depth_of(&table, 5), Some(2));
+
+        // Entries whose check doesn't match (e.g., torn by concurrent writes)
+        // are never returned.
+        let slot = &table.buckets[0].slots[0];
+        let data = slot.data.load(Ordering::Relaxed);
+        slot.data.store(data ^ 1, Ordering::Relaxed);
+        assert_eq!(depth_of(&table, 6), None);
+        assert_eq!(depth_of(&table, 2), Some(6));
+    }
+
+    #[test]
+    fn aging() {
+        let table = FixedTable::new(0);
+        for key in 1..=4 {
+            table.store(key, entry(15 - key as usize));
+        }
+        assert_eq!(depth_of(&table, 4), Some(11));
+
+        // Entries from the previous search are replaced before shallower
+        // entries from the current one.
+        table.new_search();
+        table.store(5, entry(1));
+        assert_eq!(depth_of(&table, 5), Some(1));
+        assert_eq!(depth_of(&table, 3), None);
+
+        // Reaching a state again keeps its deeper entry, but marks it current.
+        table.store(2, entry(0));
+        assert_eq!(depth_of(&table, 2), Some(13));
+        table.store(6, entry(2));
+        assert_eq!(depth_of(&table, 6), Some(2));
+        assert_eq!(depth_of(&table, 1), None);
+
+        // Once every depth-preferred entry is current, shallower entries only
+        // replace the always-replace slot.
+        table.store(7, entry(0));
+        assert_eq!(depth_of(&table, 7), Some(0));
+        assert_eq!(depth_of(&table, 4), None);
+        assert_eq!(
+            [2, 5, 6].map(|key| depth_of(&table, key)),
+            [Some(13), Some(1), Some(2)]
+        );
+    }
+}
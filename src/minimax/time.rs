@@ -0,0 +1,165 @@
+//! Time management for searches played under a game clock.
+//!
+//! A [`TimeManager`] divides the time remaining on a player's clock between
+//! their remaining moves, producing a [`TimeBudget`] for each move. The budget
+//! is used by [`Minimax::search_timed`][super::Minimax::search_timed], which
+//! stops iterative deepening early once the best move has been stable for
+//! several iterations, and continues for longer while it keeps changing.
+
+use std::time::Duration;
+
+/// The time allowed for a single move.
+///
+/// The search aims to stop after [`target`][Self::target] (scaled by the
+/// stability of the best move; see [`should_stop`][Self::should_stop]), and
+/// never runs longer than [`maximum`][Self::maximum].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeBudget {
+    pub target: Duration,
+    pub maximum: Duration,
+}
+
+impl TimeBudget {
+    /// Determines whether the search should stop before starting another
+    /// iteration, given the time elapsed so far, and the number of consecutive
+    /// iterations for which the best move has not changed (i.e., `0` if the
+    /// last iteration changed it).
+    ///
+    /// The target is scaled by `1.5` if the best move has just changed, and is
+    /// reduced by `10%` for each further iteration it stays the same, down to
+    /// half of the target.
+    pub fn should_stop(&self, elapsed: Duration, stable_iterations: usize) -> bool {
+        let scale = match stable_iterations {
+            0 => 1.5,
+            n => (1.0 - 0.1 * (n - 1) as f64).max(0.5),
+        };
+        elapsed >= self.target.mul_f64(scale).min(self.maximum)
+    }
+}
+
+/// A policy for dividing the time remaining on a player's clock between their
+/// remaining moves.
+///
+/// The number of moves left in the game is estimated from the ply, as the
+/// [`expected_moves`][Self::expected_moves] a player makes in a game, minus the
+/// moves they have already made, but never fewer than
+/// [`min_moves_to_go`][Self::min_moves_to_go]. The target time for a move is
+/// the remaining time (minus the [`overhead`][Self::overhead]) divided by the
+/// moves left, plus [`increment_share`][Self::increment_share] of the
+/// increment. The maximum time is [`max_factor`][Self::max_factor] times the
+/// target, but never more than [`max_share`][Self::max_share] of the
+/// remaining time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeManager {
+    pub expected_moves: usize,
+    pub min_moves_to_go: usize,
+    pub increment_share: f64,
+    pub max_factor: f64,
+    pub max_share: f64,
+    pub overhead: Duration,
+}
+
+impl Default for TimeManager {
+    /// The default policy is the following:
+    /// - `expected_moves`: `25`
+    /// - `min_moves_to_go`: `8`
+    /// - `increment_share`: `0.75`
+    /// - `max_factor`: `3.0`
+    /// - `max_share`: `0.25`
+    /// - `overhead`: `20ms`
+    fn default() -> Self {
+        Self {
+            expected_moves: 25,
+            min_moves_to_go: 8,
+            increment_share: 0.75,
+            max_factor: 3.0,
+            max_share: 0.25,
+            overhead: Duration::from_millis(20),
+        }
+    }
+}
+
+impl TimeManager {
+    /// Computes the budget for a move, given the time remaining on the player's
+    /// clock, the increment they gain after the move, and the ply (i.e., the
+    /// number of moves made so far by both players).
+    pub fn budget(&self, remaining: Duration, increment: Duration, ply: usize) -> TimeBudget {
+        let available = remaining.saturating_sub(self.overhead);
+        let moves_to_go = self
+            .expected_moves
+            .saturating_sub(ply / 2)
+            .max(self.min_moves_to_go)
+            .max(1);
+        let target = (available / moves_to_go as u32 + increment.mul_f64(self.increment_share))
+            .min(available);
+        let maximum = target
+            .mul_f64(self.max_factor)
+            .min(available.mul_f64(self.max_share))
+            .max(target);
+        TimeBudget { target, maximum }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn budget() {
+        let manager = TimeManager::default();
+
+        // Early in the game, the time is divided between the expected moves.
+        let budget = manager.budget(60 * SECOND, Duration::ZERO, 1);
+        let available = 60 * SECOND - manager.overhead;
+        assert_eq!(budget.target, available / 25);
+        assert_eq!(budget.maximum, budget.target.mul_f64(3.0));
+
+        // Part of the increment is added to the target.
+        let with_increment = manager.budget(60 * SECOND, 2 * SECOND, 1);
+        assert_eq!(with_increment.target, budget.target + SECOND.mul_f64(1.5));
+
+        // Later on, at least `min_moves_to_go` moves are assumed to remain, and
+        // the maximum never exceeds `max_share` of the remaining time.
+        let late = manager.budget(60 * SECOND, Duration::ZERO, 100);
+        assert_eq!(late.target, available / 8);
+        assert_eq!(late.maximum, available.mul_f64(0.25));
+
+        // The overhead is reserved, even if it leaves no time at all.
+        let empty = manager.budget(manager.overhead, SECOND, 1);
+        assert_eq!(empty.target, Duration::ZERO);
+        assert_eq!(empty.maximum, Duration::ZERO);
+        let short = manager.budget(manager.overhead * 2, 10 * SECOND, 1);
+        assert_eq!(short.target, manager.overhead);
+        assert!(short.maximum <= manager.overhead);
+    }
+
+    #[test]
+    fn should_stop() {
+        let budget = TimeBudget {
+            target: SECOND,
+            maximum: 2 * SECOND,
+        };
+
+        // The target is extended while the best move keeps changing...
+        assert!(!budget.should_stop(SECOND.mul_f64(1.4), 0));
+        assert!(budget.should_stop(SECOND.mul_f64(1.5), 0));
+
+        // ...and shortened once it has been stable for several iterations.
+        assert!(!budget.should_stop(SECOND.mul_f64(0.95), 1));
+        assert!(budget.should_stop(SECOND, 1));
+        assert!(!budget.should_stop(SECOND.mul_f64(0.75), 3));
+        assert!(budget.should_stop(SECOND.mul_f64(0.85), 3));
+        assert!(!budget.should_stop(SECOND.mul_f64(0.45), 50));
+        assert!(budget.should_stop(SECOND.mul_f64(0.5), 50));
+
+        // The maximum is never exceeded.
+        let tight = TimeBudget {
+            maximum: SECOND.mul_f64(1.2),
+            ..budget
+        };
+        assert!(tight.should_stop(SECOND.mul_f64(1.2), 0));
+    }
+}
@@ -1,8 +1,11 @@
 //! Components used for Zobrist hashing during minimax.
 
-use crate::game::{Mancala, Move, Player};
+use crate::game::{Landing, Mancala, Move, MoveError, Player, Undo};
 use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
 use rand::{Rng, SeedableRng};
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
 
 /// Enum used to represent an action that should be recorded by the
 /// Zobrist hashing system.
@@ -16,8 +19,14 @@ pub enum ZobristAction {
 
 /// Struct used to store appropriately sized tables of Zobrist values
 /// which can be used to update the Zobrist hash of a game state.
+///
+/// The values are derived from both the seed and the rule set of the state
+/// used to create them, so that states played under different rules never
+/// share hashes (and therefore transposition table entries).
 #[derive(Debug, Clone)]
 pub struct ZobristData {
+    seed: u64,
+    rules_key: u64,
     pit_vals: Vec<u64>,
     store_vals: Vec<u64>,
     switch_turn_val: u64,
@@ -33,6 +42,8 @@ impl Default for ZobristData {
     fn default() -> Self {
         let mut rng = rand::rng();
         Self {
+            seed: 0,
+            rules_key: 0,
             pit_vals: vec![],
             store_vals: vec![],
             switch_turn_val: rng.next_u64(),
@@ -46,35 +57,71 @@ impl ZobristData {
     /// game state that makes use of the current data.
     #[inline]
     pub fn total_stones(&self) -> usize {
-        self.store_vals.len() / 2
+        self.values_per_location().saturating_sub(1)
     }
 
     /// Returns the number of pits assumed to be present for any
     /// game state that makes use of the current data.
     #[inline]
     pub fn num_pits(&self) -> usize {
-        self.pit_vals.len() / self.total_stones() / 2
+        (self.pit_vals.len() / 2)
+            .checked_div(self.values_per_location())
+            .unwrap_or(0)
+    }
+
+    /// Returns the seed used to generate the current data.
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the hash of the rule set the current data was generated for.
+    #[inline]
+    pub(crate) fn rules_key(&self) -> u64 {
+        self.rules_key
+    }
+
+    /// Returns a value combining every Zobrist value, which detects data that
+    /// was generated differently from the same parameters (e.g., by a different
+    /// version of the random number generator).
+    pub(crate) fn fingerprint(&self) -> u64 {
+        self.pit_vals
+            .iter()
+            .chain(&self.store_vals)
+            .fold(self.switch_turn_val ^ self.p2_moved_val, |acc, v| {
+                acc.rotate_left(1) ^ v
+            })
+    }
+
+    /// Returns the number of Zobrist values stored for each pit or store
+    /// (one for every possible stone count, including zero and all stones).
+    #[inline]
+    fn values_per_location(&self) -> usize {
+        self.store_vals.len() / 2
     }
 
     /// Create a new set of Zobrist values for use with game states "like" the
     /// supplied state. Here, "like" means the state must have the same
     /// number of total stones and the same number of pits.
     pub fn for_state_like(state: &impl Mancala, seed: u64) -> Self {
-        let mut rng = StdRng::seed_from_u64(seed);
+        let rules_key = rules_key(state);
+        let mut rng = StdRng::seed_from_u64(seed ^ rules_key);
 
         let pits: Vec<u64> = {
-            let total_entries = 2 * state.pits() * state.total_stones();
+            let total_entries = 2 * state.pits() * (state.total_stones() + 1);
             (0..total_entries).map(|_| rng.next_u64()).collect()
         };
 
         let stores: Vec<u64> = {
-            let total_entries = 2 * state.total_stones();
+            let total_entries = 2 * (state.total_stones() + 1);
             (0..total_entries).map(|_| rng.next_u64()).collect()
         };
 
         Self {
-            pit_vals: pits.into(),
-            store_vals: stores.into(),
+            seed,
+            rules_key,
+            pit_vals: pits,
+            store_vals: stores,
             switch_turn_val: rng.next_u64(),
             p2_moved_val: rng.next_u64(),
         }
@@ -83,7 +130,31 @@ impl ZobristData {
     /// Returns a boolean indicating whether the supplied game state
     /// can be used with the current Zobrist data.
     pub fn is_valid_for(&self, state: &impl Mancala) -> bool {
-        self.total_stones() == state.total_stones() && self.num_pits() == state.pits()
+        self.total_stones() == state.total_stones()
+            && self.num_pits() == state.pits()
+            && self.rules_key == rules_key(state)
+    }
+
+    /// Computes the Zobrist hash of the supplied state from scratch, rather than
+    /// incrementally. Hashes maintained by [`MancalaZobrist::make_move_zobrist`]
+    /// only differ from this value by the full hash of the state they started from.
+    ///
+    /// Assumes the data is valid for the supplied state.
+    pub fn hash_of(&self, state: &impl Mancala) -> u64 {
+        let mut hash = 0;
+        for player in [Player::One, Player::Two] {
+            for (pit, stones) in state.board()[player].as_ref().iter().enumerate() {
+                hash ^= self.get_val(state, ZobristAction::Pit(player, pit, *stones));
+            }
+            hash ^= self.get_val(state, ZobristAction::Store(player, state.score(player)));
+        }
+        if state.current_turn() == Player::Two {
+            hash ^= self.switch_turn_val;
+        }
+        if state.p2_moved() {
+            hash ^= self.p2_moved_val;
+        }
+        hash
     }
 
     /// Gets the Zobrist value for a supplied state and Zobrist action.
@@ -94,14 +165,13 @@ impl ZobristData {
         match action {
             ZobristAction::Pit(player, pit, stones) => {
                 let player = usize::from(player) - 1;
-                let index = player * (state.pits() * self.total_stones())
-                    + pit * self.total_stones()
-                    + stones;
+                let per_location = self.values_per_location();
+                let index = player * (state.pits() * per_location) + pit * per_location + stones;
                 self.pit_vals[index]
             }
             ZobristAction::Store(player, stones) => {
                 let player = usize::from(player) - 1;
-                let index = player * self.total_stones() + stones;
+                let index = player * self.values_per_location() + stones;
                 self.store_vals[index]
             }
             ZobristAction::SwitchTurn => self.switch_turn_val,
@@ -114,9 +184,20 @@ impl ZobristData {
 /// transposition table system. Must be implemented on structs
 /// that also implement [`Mancala`].
 pub trait MancalaZobrist: Mancala {
+    /// Whether [`Minimax`][super::Minimax] searches states of this type by
+    /// making each move in place and taking it back afterward (using
+    /// [`apply_move_zobrist`][Self::apply_move_zobrist]), rather than by
+    /// searching a copy of the state for each move (using
+    /// [`make_move_zobrist`][Self::make_move_zobrist]).
+    ///
+    /// Defaults to [`true`], which avoids allocating a new state for each move
+    /// of heap-backed states. States which can be copied without allocating may
+    /// search faster by copying.
+    const SEARCH_IN_PLACE: bool = true;
+
     /// Makes a move using the underlying [`Mancala::make_move`] logic while
     /// simultaneously updating the Zobrist hash of the implementing object.
-    fn make_move_zobrist(&self, data: &ZobristData, selection: Move) -> Result<Self, ()> {
+    fn make_move_zobrist(&self, data: &ZobristData, selection: Move) -> Result<Self, MoveError> {
         let mut new_state = self.make_move(selection)?;
         perform_updates(data, self, &mut new_state);
         Ok(new_state)
@@ -124,10 +205,38 @@ pub trait MancalaZobrist: Mancala {
 
     /// Makes a random move using the underlying [`Mancala::make_move_rand`] logic
     /// while simultaneously updating the Zobrist hash of the implementing object.
-    fn make_move_rand_zobrist(&self, data: &ZobristData) -> Result<(Self, Move), ()> {
-        let (mut new_state, m) = self.make_move_rand()?;
-        perform_updates(data, self, &mut new_state);
-        Ok((new_state, m))
+    fn make_move_rand_zobrist(&self, data: &ZobristData) -> Result<(Self, Move), MoveError> {
+        match self.valid_moves().choose(&mut rand::rng()) {
+            Some(m) => self.make_move_zobrist(data, *m).map(|s| (s, *m)),
+            None if self.is_over() => Err(MoveError::GameOver),
+            None => Err(MoveError::NoMovesAvailable),
+        }
+    }
+
+    /// Makes a move in place using the underlying [`Mancala::apply_move`] logic,
+    /// while updating the Zobrist hash of the implementing object using only the
+    /// locations changed by the move.
+    #[inline]
+    fn apply_move_zobrist(
+        &mut self,
+        data: &ZobristData,
+        selection: Move,
+    ) -> Result<Undo, MoveError> {
+        let mut undo = self.apply_move(selection)?;
+        undo.zobrist_hash = self.zobrist_hash();
+        let hash = self.zobrist_hash() ^ undo_hash_delta(data, self, &undo);
+        self.set_zobrist_hash(hash);
+        Ok(undo)
+    }
+
+    /// Takes back a move made with [`apply_move_zobrist`][Self::apply_move_zobrist],
+    /// restoring both the state and its Zobrist hash (which is stored in the
+    /// [`Undo`], so no Zobrist data is needed).
+    #[inline]
+    fn undo_move_zobrist(&mut self, undo: Undo) {
+        let hash = undo.zobrist_hash;
+        self.undo_move(undo);
+        self.set_zobrist_hash(hash);
     }
 
     /// Performs a Zobrist hash update based on an old action and a new action.
@@ -158,6 +267,13 @@ pub trait MancalaZobrist: Mancala {
     fn set_zobrist_hash(&mut self, hash: u64);
 }
 
+/// Helper function to deterministically hash the rule set of a state.
+pub(super) fn rules_key(state: &impl Mancala) -> u64 {
+    let mut hasher = FxHasher::default();
+    state.rules().hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Helper function to compare a new state with an old state, and update
 /// its Zobrist hash value accordingly.
 fn perform_updates<T: MancalaZobrist>(data: &ZobristData, old_state: &T, new_state: &mut T) {
@@ -170,9 +286,7 @@ fn perform_updates<T: MancalaZobrist>(data: &ZobristData, old_state: &T, new_sta
             }
             let old = ZobristAction::Pit(player, pit, old_count);
             let new = ZobristAction::Pit(player, pit, new_count);
-            match new_state.update_zobrist_hash(data, old, new) {
-                _ => {}
-            }
+            new_state.update_zobrist_hash(data, old, new);
         }
         let old_score = old_state.score(player);
         let new_score = new_state.score(player);
@@ -181,18 +295,39 @@ fn perform_updates<T: MancalaZobrist>(data: &ZobristData, old_state: &T, new_sta
         }
         let old = ZobristAction::Store(player, old_state.score(player));
         let new = ZobristAction::Store(player, new_state.score(player));
-        match new_state.update_zobrist_hash(data, old, new) {
-            _ => {}
-        }
+        new_state.update_zobrist_hash(data, old, new);
     }
     if new_state.current_turn() != old_state.current_turn() {
-        match new_state.update_zobrist_hash_partial(data, ZobristAction::SwitchTurn) {
-            _ => {}
-        }
+        new_state.update_zobrist_hash_partial(data, ZobristAction::SwitchTurn);
     }
     if new_state.p2_moved() != old_state.p2_moved() {
-        match new_state.update_zobrist_hash_partial(data, ZobristAction::P2Moved) {
-            _ => {}
-        }
+        new_state.update_zobrist_hash_partial(data, ZobristAction::P2Moved);
+    }
+}
+
+/// Helper function to compute the value which must be XORed with a Zobrist hash
+/// to apply (or, since XOR is its own inverse, take back) a move. The supplied
+/// state must be the state immediately after the move was made.
+fn undo_hash_delta(data: &ZobristData, state: &impl Mancala, undo: &Undo) -> u64 {
+    let mut delta = 0;
+    for change in undo.journal().iter() {
+        let (old, new) = match change.location {
+            Landing::Pit(player, pit) => (
+                ZobristAction::Pit(player, pit, change.old),
+                ZobristAction::Pit(player, pit, state.board()[player].as_ref()[pit]),
+            ),
+            Landing::Store(player) => (
+                ZobristAction::Store(player, change.old),
+                ZobristAction::Store(player, state.stores()[player]),
+            ),
+        };
+        delta ^= data.get_val(state, old) ^ data.get_val(state, new);
+    }
+    if state.current_turn() != undo.current_turn() {
+        delta ^= data.get_val(state, ZobristAction::SwitchTurn);
+    }
+    if state.p2_moved() != undo.p2_moved() {
+        delta ^= data.get_val(state, ZobristAction::P2Moved);
     }
+    delta
 }
@@ -328,9 +328,9 @@ impl<const N: usize> MancalaDataset<GameState<N>> {
                 data.push(MancalaExample::new(
                     state,
                     result
-                        .found_moves()
-                        .into_iter()
-                        .zip(result.utilities())
+                        .found_moves
+                        .iter()
+                        .zip(result.utilities.iter())
                         .map(|(m, u)| (*m, *u))
                         .collect(),
                 ));
@@ -1,11 +1,13 @@
 //! Components for the terminal user interface.
 
-use crate::game::{GameOutcome, GameState, Mancala, Move, Player};
-use crate::minimax::{MancalaZobrist, Minimax, MinimaxBuilder};
+use crate::agent::{Agent, Decision};
+use crate::game::{
+    Clock, Game, GameOutcome, GameRecord, Mancala, Move, Player, RecordedMove, TimeControl,
+};
 use regex::Regex;
 use std::fs::{self, OpenOptions};
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Duration;
 
@@ -49,7 +51,11 @@ impl ExternalInterface {
                 todo!()
             }
             ExternalInterface::Minimal => {
-                let mut file = OpenOptions::new().write(true).create(true).open(path)?;
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(path)?;
 
                 let mut values: Vec<usize> = Vec::new();
 
@@ -79,273 +85,228 @@ impl ExternalInterface {
     }
 }
 
-/// Start a terminal-based game of Mancala between a player and a specified
-/// minimax algorithm based on an initial state.
-pub fn player_v_minimax<T: MancalaZobrist>(
-    initial_state: &T,
-    minimax_builder: &MinimaxBuilder<T>,
-    minimax_player: Player,
-) -> T {
-    let mut s = initial_state.clone();
-    let minimax = minimax_builder.build();
-
-    while !s.is_over() {
-        println!("{}", s);
-        if s.current_turn() == minimax_player {
-            s = minimax_or_random_move(&s, &minimax, "MINIMAX");
-        } else {
-            s = s
-                .make_move_zobrist(&minimax.z_data().borrow(), user_move_input(&s))
-                .unwrap();
-            println!();
-        }
-    }
+/// An [`Agent`] controlled by a person, who enters moves via standard input.
+///
+/// The person may enter `undo` or `redo` to move between their own turns.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct HumanAgent {
+    name: String,
+}
 
-    match s.outcome() {
-        GameOutcome::Winner(player) if player == minimax_player => {
-            println!("{}\nWINNER: MINIMAX", s)
-        }
-        GameOutcome::Winner(player) if player != minimax_player => {
-            println!("{}\nWINNER: PLAYER {}", s, usize::from(player))
-        }
-        GameOutcome::Tie => println!("{}\nWINNER: TIE", s),
-        _ => println!("{}\nWINNER: N/A", s),
+impl Default for HumanAgent {
+    /// A person named `Player`.
+    fn default() -> Self {
+        Self::new("Player")
     }
-
-    s
 }
 
-/// Start a terminal-based game of Mancala between a player and the default
-/// minimax algorithm specified by [`MinimaxBuilder::default`], using the
-/// default game state specified by [`GameState::default`].
-pub fn player_v_minimax_default(minimax_player: Player) -> GameState<6> {
-    let minimax_builder = MinimaxBuilder::new().optimize_for(minimax_player);
-    player_v_minimax(&GameState::default(), &minimax_builder, minimax_player)
+impl HumanAgent {
+    /// Create a new agent for a person with the supplied name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
 }
 
-/// Start a terminal-based game of Mancala between two players based on
-/// an initial board state.
-pub fn player_v_player<T: Mancala>(initial_state: &T) -> T {
-    let mut s = initial_state.clone();
-
-    while !s.is_over() {
-        println!("{}", s);
-        s = s.make_move(user_move_input(&s)).unwrap();
-        println!();
+impl<T: Mancala> Agent<T> for HumanAgent {
+    fn choose_move(&mut self, game: &Game<T>) -> Decision {
+        match user_move_input(game.state()) {
+            UserInput::Move(m) => m.into(),
+            UserInput::Undo => Decision::Undo,
+            UserInput::Redo => Decision::Redo,
+        }
     }
 
-    match s.outcome() {
-        GameOutcome::Winner(player) => println!("{}\nWINNER: PLAYER {}", s, usize::from(player)),
-        GameOutcome::Tie => println!("{}\nWINNER: TIE", s),
-        _ => println!("{}\nWINNER: N/A", s),
+    fn name(&self) -> String {
+        self.name.clone()
     }
-
-    s
 }
 
-/// Start a terminal-based game of Mancala between two players based on
-/// the default game state specified by [`GameState::default`].
-pub fn player_v_player_default() -> GameState<6> {
-    player_v_player(&GameState::default())
+/// An [`Agent`] controlled by an external program, which communicates using the
+/// selected interface via files in a communication directory.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ExternalAgent {
+    interface: ExternalInterface,
+    comm_dir: PathBuf,
+    current_move: usize,
 }
 
-/// Start a terminal-based game of Mancala between two minimax opponents
-/// based on an initial state.
-pub fn minimax_v_minimax<T: MancalaZobrist>(
-    initial_state: &T,
-    minimax1: &MinimaxBuilder<T>,
-    minimax2: &MinimaxBuilder<T>,
-) -> T {
-    let mut s = initial_state.clone();
-    let minimax1 = minimax1.build();
-    let minimax2 = minimax2.build();
-
-    while !s.is_over() {
-        println!("{}", s);
-        if s.current_turn() == Player::One {
-            s = minimax_or_random_move(&s, &minimax1, "MINIMAX 1");
-        } else {
-            s = minimax_or_random_move(&s, &minimax2, "MINIMAX 2");
-        }
-    }
-
-    match s.outcome() {
-        GameOutcome::Winner(player) if player == Player::One => {
-            println!("{}\nWINNER: MINIMAX PLAYER 1", s)
-        }
-        GameOutcome::Winner(player) if player == Player::Two => {
-            println!("{}\nWINNER: MINIMAX PLAYER 2", s)
-        }
-        GameOutcome::Tie => println!("{}\nWINNER: TIE", s),
-        _ => println!("{}\nWINNER: N/A", s),
-    }
+impl ExternalAgent {
+    /// Create a new agent communicating via the supplied directory, which is
+    /// created if it does not exist. A reset signal is sent to the external
+    /// program.
+    pub fn new(interface: ExternalInterface, comm_dir: impl AsRef<Path>) -> Self {
+        let comm_dir = comm_dir.as_ref().to_path_buf();
 
-    s
-}
+        // Create communication directory if it does not exist.
+        fs::create_dir_all(&comm_dir).unwrap();
 
-/// Start a terminal-based game of Mancala between a player and an external
-/// agent, using the selected communication interface and directory. A supplied
-/// starting state is used.
-pub fn player_v_external<T: Mancala, P: AsRef<Path>>(
-    initial_state: &T,
-    external_player: Player,
-    interface: ExternalInterface,
-    comm_dir: P,
-) -> T {
-    let mut s = initial_state.clone();
-    let mut current_move = 0usize;
-    let comm_dir = comm_dir.as_ref();
+        // Reset directory.
+        external_reset(&comm_dir);
 
-    // Create communication directory if it does not exist.
-    fs::create_dir_all(comm_dir).unwrap();
-
-    // Reset directory.
-    external_reset(comm_dir);
-
-    while !s.is_over() {
-        println!("{}", s);
-        if s.current_turn() == external_player {
-            let chosen_move = external_move_input(&s, interface, comm_dir, current_move);
-            s = s.make_move(chosen_move).unwrap();
-            println!("EXTERNAL SELECTED: {:?}\n", chosen_move);
-            current_move += 1;
-        } else {
-            s = s.make_move(user_move_input(&s)).unwrap();
-            println!();
+        Self {
+            interface,
+            comm_dir,
+            current_move: 0,
         }
     }
+}
 
-    match s.outcome() {
-        GameOutcome::Winner(player) if player == external_player => {
-            println!("{}\nWINNER: EXTERNAL AGENT", s)
-        }
-        GameOutcome::Winner(player) if player != external_player => {
-            println!("{}\nWINNER: PLAYER {}", s, usize::from(player))
-        }
-        GameOutcome::Tie => println!("{}\nWINNER: TIE", s),
-        _ => println!("{}\nWINNER: N/A", s),
+impl<T: Mancala> Agent<T> for ExternalAgent {
+    fn choose_move(&mut self, game: &Game<T>) -> Decision {
+        let chosen_move = external_move_input(
+            game.state(),
+            self.interface,
+            &self.comm_dir,
+            self.current_move,
+        );
+        self.current_move += 1;
+        chosen_move.into()
     }
 
-    s
+    fn name(&self) -> String {
+        "External agent".to_string()
+    }
 }
 
-/// Start a terminal-based game of Mancala between a player and an external
-/// agent, using the selected communication interface and directory. The
-/// default starting state is used.
-pub fn player_v_external_default<P: AsRef<Path>>(
-    external_player: Player,
-    interface: ExternalInterface,
-    comm_dir: P,
-) -> GameState<6> {
-    player_v_external(&GameState::default(), external_player, interface, comm_dir)
+/// Start a terminal-based game of Mancala between two agents based on an
+/// initial state, where `agent1` plays as [`Player::One`]. Returns a record of
+/// the game.
+///
+/// Agents which undo (or redo) moves move between their own turns, skipping
+/// any opponent moves.
+///
+/// # Panics
+///
+/// Panics if an agent makes an invalid move.
+pub fn play_match<T: Mancala>(
+    agent1: &mut impl Agent<T>,
+    agent2: &mut impl Agent<T>,
+    initial_state: &T,
+) -> GameRecord<T> {
+    run_match(&mut [agent1, agent2], initial_state, None)
 }
 
-/// Start a terminal-based game of Mancala between minimax and an external
-/// agent, using the selected communication interface and directory. A supplied
-/// starting state and minimax configuration are used.
-pub fn minimax_v_external<T: MancalaZobrist, P: AsRef<Path>>(
+/// Same as [`play_match`], but each agent plays under a game clock following
+/// the supplied time control, and chooses its moves using
+/// [`Agent::choose_move_timed`].
+///
+/// An agent's clock runs while it decides what to do (including undoing or
+/// redoing moves). If an agent runs out of time, it loses the game, and the
+/// result is recorded as a [`GameOutcome::TimeForfeit`]. The time control is
+/// recorded in the `TimeControl` tag.
+///
+/// # Panics
+///
+/// Panics if an agent makes an invalid move.
+pub fn play_timed_match<T: Mancala>(
+    agent1: &mut impl Agent<T>,
+    agent2: &mut impl Agent<T>,
     initial_state: &T,
-    minimax_builder: &MinimaxBuilder<T>,
-    external_player: Player,
-    interface: ExternalInterface,
-    comm_dir: P,
-) -> T {
-    let mut s = initial_state.clone();
-    let minimax = minimax_builder.build();
-    let mut current_move = 0usize;
-    let comm_dir = comm_dir.as_ref();
+    control: TimeControl,
+) -> GameRecord<T> {
+    let mut record = run_match(
+        &mut [agent1, agent2],
+        initial_state,
+        Some(Clock::new(control)),
+    );
+    record.set_tag("TimeControl", control.to_string());
+    record
+}
 
-    // Create communication directory if it does not exist.
-    fs::create_dir_all(comm_dir).unwrap();
-
-    // Reset directory.
-    external_reset(comm_dir);
-
-    while !s.is_over() {
-        println!("{}", s);
-        if s.current_turn() == external_player {
-            let chosen_move = external_move_input(&s, interface, comm_dir, current_move);
-            s = s.make_move(chosen_move).unwrap();
-            println!("EXTERNAL SELECTED: {:?}\n", chosen_move);
-            current_move += 1;
-        } else {
-            s = minimax_or_random_move(&s, &minimax, "MINIMAX");
+/// Helper function which plays a match between two agents, with or without a
+/// game clock.
+fn run_match<T: Mancala>(
+    agents: &mut [&mut dyn Agent<T>; 2],
+    initial_state: &T,
+    mut clock: Option<Clock>,
+) -> GameRecord<T> {
+    let mut game = Game::new(initial_state.clone());
+    let mut forfeit: Option<Player> = None;
+
+    while !game.state().is_over() {
+        println!("{}", game.state());
+        let player = game.state().current_turn();
+        let decision = match clock.as_mut() {
+            Some(clock) => {
+                print_clock(clock);
+                clock.start(player);
+                let decision = agents[player].choose_move_timed(&game, clock);
+                match decision {
+                    Decision::Move(_) => clock.press(),
+                    Decision::Undo | Decision::Redo => clock.pause(),
+                };
+                if clock.is_flagged(player) {
+                    forfeit = Some(player);
+                    break;
+                }
+                decision
+            }
+            None => agents[player].choose_move(&game),
+        };
+        match decision {
+            Decision::Move(m) => {
+                game.play(m.clone()).expect("Agent made an invalid move");
+                print_move(player, &m);
+                agents[player.other()].notify_opponent_move(&game, &m);
+            }
+            Decision::Undo => match game.last_turn_of(player) {
+                Some(position) => {
+                    game.jump_to(position);
+                    println!("UNDONE TO MOVE {}\n", position);
+                }
+                None => println!("NOTHING TO UNDO\n"),
+            },
+            Decision::Redo => {
+                if game.redo().is_none() {
+                    println!("NOTHING TO REDO\n");
+                } else {
+                    while game.state().current_turn() != player && game.redo().is_some() {}
+                    println!("REDONE TO MOVE {}\n", game.position());
+                }
+            }
         }
     }
 
-    match s.outcome() {
-        GameOutcome::Winner(player) if player == external_player => {
-            println!("{}\nWINNER: EXTERNAL AGENT", s)
-        }
-        GameOutcome::Winner(player) if player != external_player => {
-            println!("{}\nWINNER: MINIMAX", s)
-        }
+    let s = game.state();
+    let names = [agents[0].name(), agents[1].name()];
+    let outcome = forfeit.map_or(s.outcome(), GameOutcome::TimeForfeit);
+    match outcome {
+        GameOutcome::Winner(player) => println!(
+            "{}\nWINNER: PLAYER {} ({})",
+            s,
+            usize::from(player),
+            names[player]
+        ),
+        GameOutcome::TimeForfeit(player) => println!(
+            "{}\nPLAYER {} RAN OUT OF TIME\nWINNER: PLAYER {} ({})",
+            s,
+            usize::from(player),
+            usize::from(player.other()),
+            names[player.other()]
+        ),
         GameOutcome::Tie => println!("{}\nWINNER: TIE", s),
         _ => println!("{}\nWINNER: N/A", s),
     }
 
-    s
+    let [one, two] = names;
+    let mut record = game.to_record().with_players(one, two);
+    record.set_result(outcome);
+    record
 }
 
-/// Start a terminal-based game of Mancala between minimax and an external
-/// agent, using the selected communication interface and directory. The default
-/// starting state and minimax configuration are used.
-pub fn minimax_v_external_default<P: AsRef<Path>>(
-    external_player: Player,
-    interface: ExternalInterface,
-    comm_dir: P,
-) -> GameState<6> {
-    minimax_v_external(
-        &GameState::default(),
-        &MinimaxBuilder::default(),
-        external_player,
-        interface,
-        comm_dir,
-    )
+/// Helper function for printing the time remaining for each player.
+fn print_clock(clock: &Clock) {
+    println!(
+        "TIME REMAINING: PLAYER 1 {:.1}s, PLAYER 2 {:.1}s",
+        clock.remaining(Player::One).as_secs_f64(),
+        clock.remaining(Player::Two).as_secs_f64()
+    );
 }
 
-/// Helper function for making moves selected by minimax, or, if no moves
-/// are found, making a random move.
-fn minimax_or_random_move<T: MancalaZobrist>(s: &T, m: &Minimax<T>, name: &str) -> T {
-    struct MoveResult {
-        chosen_move: Move,
-        utility: f32,
-        depth_searched: Option<usize>,
-        fully_searched: bool,
-        random: bool,
-    }
-
-    // Attempt to find move via minimax.
-    let (s, result) = match m.search_utility(&s) {
-        // Use the minimax move.
-        Some(r) => {
-            let new_s = s
-                .make_move_zobrist(&m.z_data().borrow(), r.found_move)
-                .unwrap();
-            let r = MoveResult {
-                chosen_move: r.found_move,
-                utility: r.utility,
-                depth_searched: r.depth_searched,
-                fully_searched: r.fully_searched,
-                random: false,
-            };
-            (new_s, r)
-        }
-        // Use a random move.
-        None => {
-            let (new_s, chosen_move) = s.make_move_rand_zobrist(&m.z_data().borrow()).unwrap();
-            let r = MoveResult {
-                chosen_move,
-                utility: f32::NAN,
-                depth_searched: None,
-                fully_searched: false,
-                random: true,
-            };
-            (new_s, r)
-        }
-    };
-
-    let label_selected = format!("{} SELECTED:", name);
+/// Helper function for printing a move made by an agent, along with any
+/// annotations.
+fn print_move(player: Player, m: &RecordedMove) {
+    let label_selected = format!("PLAYER {} SELECTED:", usize::from(player));
     let label_expected = "EXPECTED UTILITY:".to_string();
     let label_depth = "SEARCH DEPTH:".to_string();
     let label_width = [&label_selected, &label_expected, &label_depth]
@@ -356,35 +317,31 @@ fn minimax_or_random_move<T: MancalaZobrist>(s: &T, m: &Minimax<T>, name: &str)
     println!(
         "{:<width$} {:?}",
         label_selected,
-        result.chosen_move,
-        width = label_width
-    );
-    let confidence = if result.random {
-        "Random"
-    } else if result.fully_searched {
-        "Exact"
-    } else {
-        "Estimated"
-    };
-    println!(
-        "{:<width$} {} ({})",
-        label_expected,
-        result.utility,
-        confidence,
-        width = label_width
-    );
-    println!(
-        "{:<width$} {}\n",
-        label_depth,
-        result.depth_searched.unwrap_or(0),
+        m.selection,
         width = label_width
     );
-    s
+    if let Some(eval) = m.eval {
+        println!("{:<width$} {}", label_expected, eval, width = label_width);
+    }
+    if let Some(depth) = m.depth {
+        println!("{:<width$} {}", label_depth, depth, width = label_width);
+    }
+    if let Some(comment) = &m.comment {
+        println!("{}", comment);
+    }
+    println!();
+}
+
+/// Enum used to represent a single input from a user during a game.
+enum UserInput {
+    Move(Move),
+    Undo,
+    Redo,
 }
 
 /// Helper function for collecting valid user inputs via standard input.
-fn user_move_input<T: Mancala>(state: &T) -> Move {
-    let mut selection: Option<Move> = None;
+fn user_move_input<T: Mancala>(state: &T) -> UserInput {
+    let mut selection: Option<UserInput> = None;
     let player_int: usize = state.current_turn().into();
     let valid_moves = state.valid_moves();
 
@@ -396,24 +353,23 @@ fn user_move_input<T: Mancala>(state: &T) -> Move {
         io::stdin()
             .read_line(&mut input_line)
             .expect("Failed to read line");
-        let input = input_line.trim();
-        selection = if input.to_lowercase() == "swap" {
-            if valid_moves.contains(&Move::Swap) {
-                Some(Move::Swap)
-            } else {
-                None
-            }
-        } else {
-            match input.parse::<usize>() {
-                Ok(n) if valid_moves.contains(&Move::Pit(n)) => Some(Move::Pit(n)),
+        let input = input_line.trim().to_lowercase();
+        selection = match input.as_str() {
+            "undo" => Some(UserInput::Undo),
+            "redo" => Some(UserInput::Redo),
+            "swap" if valid_moves.contains(&Move::Swap) => Some(UserInput::Move(Move::Swap)),
+            _ => match input.parse::<usize>() {
+                Ok(n) if valid_moves.contains(&Move::Pit(n)) => Some(UserInput::Move(Move::Pit(n))),
                 _ => None,
-            }
+            },
         };
     }
     selection.unwrap()
 }
 
-/// Helper function for collecting valid external moves from an external interface.
+/// Helper function for collecting valid external moves from an external interface,
+/// and selecting the first valid one. If no valid moves were requested, a random
+/// move is selected instead.
 fn external_move_input<T: Mancala, P: AsRef<Path>>(
     state: &T,
     interface: ExternalInterface,
@@ -436,21 +392,22 @@ fn external_move_input<T: Mancala, P: AsRef<Path>>(
         }
     }
 
-    // Select first valid move.
-    let mut chosen_move: Option<Move> = None;
+    // Select the first valid move.
     for m in moves {
-        if state.valid_moves().contains(&m) {
-            chosen_move = Some(m);
-            break;
-        } else {
-            println!(
-                "External agent requested invalid move {:?}. Finding next-best move...",
-                m
-            )
+        match state.make_move(m) {
+            Ok(_) => return m,
+            Err(e) => println!(
+                "External agent requested invalid move {:?} ({}). Finding next-best move...",
+                m, e
+            ),
         }
     }
 
-    chosen_move.unwrap()
+    println!("External agent requested no valid moves. Making a random move...");
+    state
+        .make_move_rand()
+        .expect("Cannot make a move for a finished game")
+        .1
 }
 
 // Helper function to send a reset signal to the external program.
@@ -458,18 +415,15 @@ fn external_reset<P: AsRef<Path>>(comm_dir: P) {
     let re = Regex::new(r"^(board|moves)\d+\.txt$").unwrap();
     match fs::read_dir(&comm_dir) {
         Ok(entries) => {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if path.is_file() {
-                        if let Some(filename) = path.file_name().and_then(|f| f.to_str()) {
-                            if re.is_match(filename) {
-                                match fs::remove_file(&path) {
-                                    Ok(_) => (),
-                                    Err(e) => eprintln!("Failed to remove {:?}: {}", path, e),
-                                }
-                            }
-                        }
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file()
+                    && let Some(filename) = path.file_name().and_then(|f| f.to_str())
+                    && re.is_match(filename)
+                {
+                    match fs::remove_file(&path) {
+                        Ok(_) => (),
+                        Err(e) => eprintln!("Failed to remove {:?}: {}", path, e),
                     }
                 }
             }
@@ -479,6 +433,47 @@ fn external_reset<P: AsRef<Path>>(comm_dir: P) {
     OpenOptions::new()
         .write(true)
         .create(true)
+        .truncate(true)
         .open(comm_dir.as_ref().join("RESET.txt"))
         .expect("Failed to create file");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::RandomAgent;
+    use crate::game::GameState;
+
+    /// Makes random moves, but only after waiting for the supplied time.
+    struct SlowAgent(Duration);
+
+    impl<T: Mancala> Agent<T> for SlowAgent {
+        fn choose_move(&mut self, game: &Game<T>) -> Decision {
+            thread::sleep(self.0);
+            RandomAgent.choose_move(game)
+        }
+
+        fn name(&self) -> String {
+            "Slow".to_string()
+        }
+    }
+
+    #[test]
+    fn flag_fall_forfeits() {
+        let control = TimeControl::sudden_death(Duration::from_millis(100));
+        let record = play_timed_match(
+            &mut RandomAgent,
+            &mut SlowAgent(Duration::from_millis(150)),
+            &GameState::<6>::default(),
+            control,
+        );
+        assert_eq!(record.result(), GameOutcome::TimeForfeit(Player::Two));
+        assert_eq!(record.result().winner(), Some(Player::One));
+        assert!(!record.moves().is_empty());
+        assert!(
+            record
+                .tags()
+                .contains(&("TimeControl".to_string(), control.to_string()))
+        );
+    }
+}